@@ -1,54 +1,324 @@
 use std::io;
 use std::io::BufRead;
 
-fn exercise_1(numbers: &mut Vec<u32>) {
-    numbers.sort();
-    numbers.reverse();
-    for i in 0..numbers.len() {
-        let mut j = numbers.len() - 1;
-        while j > i && numbers[i] + numbers[j] < 2020 {
-            j -= 1;
-        }
-        if numbers[i] + numbers[j] == 2020 {
-            println!(
-                "Found ! {} + {} => {}",
-                numbers[i],
-                numbers[j],
-                numbers[i] * numbers[j]
-            );
-        }
-    }
-}
-
-fn exercise_2(numbers: &mut Vec<u32>) {
-    numbers.sort();
-    let n = numbers.len();
-    for i in 0..numbers.len() {
-        let index = n - i - 1;
-        let mut j = 0;
-        while j < index && numbers[index] + numbers[j] <= 2020 {
-            let target = 2020 - (numbers[index] + numbers[j]);
-            if numbers.binary_search(&target).is_ok() {
-                println!(
-                    "Found : {} + {} + {} => {}",
-                    numbers[index],
-                    numbers[j],
-                    target,
-                    numbers[index] * numbers[j] * target
-                );
+type Int = i64;
+
+#[derive(Debug, PartialEq)]
+struct Solution {
+    // Positions in the input, increasing
+    indices: Vec<usize>,
+    values: Vec<Int>,
+}
+
+// Entries sorted once, then searched with k - 2 nested loops around a two-pointer scan
+struct KSum {
+    values: Vec<Int>,
+    // Position in the input of each sorted value
+    indices: Vec<usize>,
+}
+
+impl KSum {
+    fn new(numbers: &[Int]) -> Self {
+        let mut order: Vec<usize> = (0..numbers.len()).collect();
+        order.sort_by_key(|i| numbers[*i]);
+        KSum {
+            values: order.iter().map(|i| numbers[*i]).collect(),
+            indices: order,
+        }
+    }
+
+    fn solution(&self, chosen: &[usize]) -> Solution {
+        let mut entries: Vec<(usize, Int)> = chosen
+            .iter()
+            .map(|c| (self.indices[*c], self.values[*c]))
+            .collect();
+        entries.sort_unstable();
+        let (indices, values) = entries.into_iter().unzip();
+        Solution { indices, values }
+    }
+
+    // Smallest and largest sums of k entries taken from `start..`
+    fn bounds(&self, k: usize, start: usize) -> (Int, Int) {
+        let n = self.values.len();
+        (
+            self.values[start..start + k].iter().sum(),
+            self.values[n - k..].iter().sum(),
+        )
+    }
+
+    // Calls `found` on each set of k sorted positions from `start..` summing to target,
+    // stops as soon as it returns true
+    fn search(
+        &self,
+        k: usize,
+        target: Int,
+        start: usize,
+        chosen: &mut Vec<usize>,
+        found: &mut impl FnMut(&[usize]) -> bool,
+    ) -> bool {
+        let n = self.values.len();
+        if k == 0 {
+            return target == 0 && found(chosen);
+        }
+        if n - start < k {
+            return false;
+        }
+        let (low, high) = self.bounds(k, start);
+        if target < low || high < target {
+            return false;
+        }
+        if k == 1 {
+            for i in start..n {
+                if self.values[i] == target {
+                    chosen.push(i);
+                    let stop = found(chosen);
+                    chosen.pop();
+                    if stop {
+                        return true;
+                    }
+                }
+            }
+            return false;
+        }
+        if k == 2 {
+            return self.two_pointers(target, start, chosen, found);
+        }
+        for i in start..n - k + 1 {
+            chosen.push(i);
+            let stop = self.search(k - 1, target - self.values[i], i + 1, chosen, found);
+            chosen.pop();
+            if stop {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn two_pointers(
+        &self,
+        target: Int,
+        start: usize,
+        chosen: &mut Vec<usize>,
+        found: &mut impl FnMut(&[usize]) -> bool,
+    ) -> bool {
+        let (mut low, mut high) = (start, self.values.len() - 1);
+        while low < high {
+            let sum = self.values[low] + self.values[high];
+            if sum < target {
+                low += 1;
+            } else if sum > target {
+                high -= 1;
+            } else {
+                // Every pair between the runs of equal values matches
+                let low_end = self.run_end(low, high);
+                let high_start = self.run_start(high, low);
+                for i in low..low_end {
+                    for j in high_start.max(i + 1)..=high {
+                        chosen.push(i);
+                        chosen.push(j);
+                        let stop = found(chosen);
+                        chosen.truncate(chosen.len() - 2);
+                        if stop {
+                            return true;
+                        }
+                    }
+                }
+                if self.values[low] == self.values[high] {
+                    return false;
+                }
+                low = low_end;
+                high = high_start - 1;
             }
-            j += 1;
         }
+        false
+    }
+
+    // First position after the run of values equal to values[from], up to `limit` included
+    fn run_end(&self, from: usize, limit: usize) -> usize {
+        (from..=limit)
+            .find(|i| self.values[*i] != self.values[from])
+            .unwrap_or(limit + 1)
+    }
+
+    // First position of the run of values equal to values[from], down to `limit`
+    fn run_start(&self, from: usize, limit: usize) -> usize {
+        (limit..=from)
+            .rev()
+            .find(|i| self.values[*i] != self.values[from])
+            .map_or(limit, |i| i + 1)
+    }
+
+    fn first(&self, k: usize, target: Int) -> Option<Solution> {
+        let mut first = None;
+        self.search(k, target, 0, &mut vec![], &mut |chosen| {
+            first = Some(self.solution(chosen));
+            true
+        });
+        first
+    }
+
+    // Every solution, as distinct sets of positions
+    fn all(&self, k: usize, target: Int) -> Vec<Solution> {
+        let mut solutions = vec![];
+        self.search(k, target, 0, &mut vec![], &mut |chosen| {
+            solutions.push(self.solution(chosen));
+            false
+        });
+        solutions.sort_by(|a, b| a.indices.cmp(&b.indices));
+        solutions
+    }
+
+    fn count(&self, k: usize, target: Int) -> usize {
+        let mut count = 0;
+        self.search(k, target, 0, &mut vec![], &mut |_| {
+            count += 1;
+            false
+        });
+        count
+    }
+}
+
+fn exercise_1(numbers: &[Int]) -> Option<Int> {
+    KSum::new(numbers)
+        .first(2, 2020)
+        .map(|solution| solution.values.iter().product())
+}
+
+fn exercise_2(numbers: &[Int]) -> Option<Int> {
+    KSum::new(numbers)
+        .first(3, 2020)
+        .map(|solution| solution.values.iter().product())
+}
+
+fn option_value<T: std::str::FromStr>(args: &[String], name: &str) -> anyhow::Result<Option<T>> {
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(None),
+        Some(position) => match args.get(position + 1).map(|value| value.parse()) {
+            Some(Ok(value)) => Ok(Some(value)),
+            _ => anyhow::bail!("{} expects a number", name),
+        },
     }
 }
 
 fn main() -> anyhow::Result<()> {
-    let mut numbers: Vec<u32> = vec![];
+    let mut numbers: Vec<Int> = vec![];
     for line in io::stdin().lock().lines() {
-        numbers.push(line.unwrap().parse::<u32>().unwrap());
+        numbers.push(line?.parse::<Int>()?);
     }
 
-    exercise_1(&mut numbers);
-    exercise_2(&mut numbers);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        println!(
+            "{}",
+            exercise_1(&numbers).ok_or_else(|| anyhow::anyhow!("no two entries sum to 2020"))?
+        );
+        println!(
+            "{}",
+            exercise_2(&numbers).ok_or_else(|| anyhow::anyhow!("no three entries sum to 2020"))?
+        );
+        return Ok(());
+    }
+
+    let k: usize = option_value(&args, "--k")?.unwrap_or(2);
+    let target: Int = option_value(&args, "--target")?.unwrap_or(2020);
+    let solver = KSum::new(&numbers);
+    if args.iter().any(|arg| arg == "--count") {
+        println!("{}", solver.count(k, target));
+    } else if args.iter().any(|arg| arg == "--all") {
+        for solution in solver.all(k, target) {
+            println!("{:?} {:?}", solution.indices, solution.values);
+        }
+    } else {
+        let solution = solver
+            .first(k, target)
+            .ok_or_else(|| anyhow::anyhow!("no {} entries sum to {}", k, target))?;
+        println!("{:?} {:?}", solution.indices, solution.values);
+    }
     Ok(())
 }
+
+#[test]
+fn test_exo_1() {
+    assert_eq!(exercise_1(&[1721, 979, 366, 299, 675, 1456]), Some(514579));
+    assert_eq!(exercise_1(&[1010, 979, 366]), None);
+    assert_eq!(exercise_1(&[1010, 979, 1010]), Some(1010 * 1010));
+}
+
+#[test]
+fn test_exo_2() {
+    assert_eq!(
+        exercise_2(&[1721, 979, 366, 299, 675, 1456]),
+        Some(241861950)
+    );
+    assert_eq!(exercise_2(&[1721, 979, 366]), None);
+    assert_eq!(exercise_2(&[1000, 20]), None);
+}
+
+#[test]
+fn test_k_sum() {
+    let solver = KSum::new(&[1721, 979, 366, 299, 675, 1456]);
+    assert_eq!(
+        solver.first(2, 2020),
+        Some(Solution {
+            indices: vec![0, 3],
+            values: vec![1721, 299]
+        })
+    );
+    assert_eq!(solver.count(3, 2020), 1);
+    assert_eq!(solver.all(3, 2020)[0].indices, vec![1, 2, 4]);
+    assert_eq!(solver.count(1, 366), 1);
+    assert_eq!(solver.count(0, 0), 1);
+    assert_eq!(solver.count(7, 2020), 0);
+
+    // Brute force over every subset
+    let numbers = [3, -1, 4, 1, 5, 9, 2, 6, 5, 3, 5, 0, -4];
+    let solver = KSum::new(&numbers);
+    for k in 1..6 {
+        for target in -5..25 {
+            let mut expected: Vec<Vec<usize>> = (0..1u32 << numbers.len())
+                .filter(|mask| mask.count_ones() as usize == k)
+                .map(|mask| {
+                    (0..numbers.len())
+                        .filter(|i| mask >> i & 1 == 1)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|indices| indices.iter().map(|i| numbers[*i]).sum::<Int>() == target)
+                .collect();
+            let mut found: Vec<Vec<usize>> = solver
+                .all(k, target)
+                .into_iter()
+                .map(|solution| solution.indices)
+                .collect();
+            found.sort();
+            expected.sort();
+            assert_eq!(found, expected, "k = {}, target = {}", k, target);
+            assert_eq!(solver.count(k, target), expected.len());
+        }
+    }
+}
+
+#[test]
+fn test_duplicates() {
+    let solver = KSum::new(&[1010, 1010, 1010]);
+    assert_eq!(solver.count(2, 2020), 3);
+    assert_eq!(
+        solver
+            .all(2, 2020)
+            .into_iter()
+            .map(|solution| solution.indices)
+            .collect::<Vec<_>>(),
+        vec![vec![0, 1], vec![0, 2], vec![1, 2]]
+    );
+    assert_eq!(KSum::new(&[5, 5, 5, 5]).count(3, 15), 4);
+    assert_eq!(KSum::new(&[1, 1, 2, 2]).count(2, 3), 4);
+}
+
+#[test]
+fn test_option_value() {
+    let args = ["--k", "3", "--target", "x"].map(String::from);
+    assert_eq!(option_value::<usize>(&args, "--k").unwrap(), Some(3));
+    assert_eq!(option_value::<Int>(&args, "--all").unwrap(), None);
+    assert!(option_value::<Int>(&args, "--target").is_err());
+    assert!(option_value::<usize>(&args[..1], "--k").is_err());
+    assert!(option_value::<usize>(&["--k", "-1"].map(String::from), "--k").is_err());
+}
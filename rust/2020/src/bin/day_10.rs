@@ -1,78 +1,379 @@
 use std::io::BufRead;
 
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::integer::{run_checked, BigUint, Integer, Solver, CHECKED_FLAG};
+
 type Int = i64;
 
-fn exercise_1(numbers: &mut Vec<Int>) -> Int {
-    let mut number_jump_1 = 0;
-    let mut number_jump_3 = 1;
-    numbers.push(0);
-    numbers.sort_unstable();
-    for i in 1..numbers.len() {
-        let jump = numbers[i] - numbers[i - 1];
-        if jump == 1 {
-            number_jump_1 += 1;
+// Sorted joltages, the outlet (0) first and the device last
+struct Adapters<I> {
+    joltages: Vec<I>,
+    max_gap: I,
+}
+
+impl<I: Integer> Adapters<I> {
+    fn new(numbers: &[I], max_gap: I) -> Self {
+        let mut joltages = numbers.to_vec();
+        joltages.sort_unstable();
+        let device = joltages.last().cloned().unwrap_or_else(I::zero) + max_gap.clone();
+        joltages.insert(0, I::zero());
+        joltages.push(device);
+        Adapters { joltages, max_gap }
+    }
+
+    fn gap(&self, from: usize, to: usize) -> I {
+        self.joltages[to].clone() - self.joltages[from].clone()
+    }
+
+    fn can_follow(&self, from: usize, to: usize) -> bool {
+        let gap = self.gap(from, to);
+        I::one() <= gap && gap <= self.max_gap
+    }
+
+    // Indices which can be plugged right after `from`
+    fn next_adapters(&self, from: usize) -> impl Iterator<Item = usize> + '_ {
+        (from + 1..self.joltages.len())
+            .take_while(move |to| self.gap(from, *to) <= self.max_gap)
+            .filter(move |to| self.can_follow(from, *to))
+    }
+
+    // For each adapter, the number of ways to go from it to the device
+    fn ways_to_end<T: Clone>(&self, zero: T, one: T, add: impl Fn(&T, &T) -> T) -> Vec<T> {
+        let n = self.joltages.len();
+        let mut ways = vec![zero; n];
+        ways[n - 1] = one;
+        for from in (0..n - 1).rev() {
+            for to in self.next_adapters(from) {
+                ways[from] = add(&ways[from], &ways[to]);
+            }
         }
-        if jump == 3 {
-            number_jump_3 += 1;
+        ways
+    }
+
+    fn count_arrangements(&self) -> BigUint {
+        self.ways_to_end(BigUint::zero(), BigUint::one(), |x, y| x + y)
+            .swap_remove(0)
+    }
+
+    fn count_arrangements_modulo(&self, modulus: u64) -> anyhow::Result<u64> {
+        anyhow::ensure!(modulus > 0, "the modulus should be positive");
+        Ok(self.ways_to_end(0, 1 % modulus, |x, y| {
+            ((*x as u128 + *y as u128) % modulus as u128) as u64
+        })[0])
+    }
+
+    fn arrangements(&self) -> Arrangements<'_, I> {
+        Arrangements {
+            reachable: self.ways_to_end(false, true, |x, y| *x || *y),
+            adapters: self,
+            path: vec![],
+            started: false,
         }
     }
-    number_jump_1 * number_jump_3
+
+    // Uniformly picks one of the arrangements, None if there is none
+    fn sample_arrangement(&self, rng: &mut XorShift) -> Option<Vec<I>> {
+        let ways = self.ways_to_end(BigUint::zero(), BigUint::one(), |x, y| x + y);
+        if ways[0] == BigUint::zero() {
+            return None;
+        }
+        let mut current = 0;
+        let mut path = vec![];
+        while current != self.joltages.len() - 1 {
+            let mut choice = rng.below(&ways[current]);
+            for next in self.next_adapters(current) {
+                if choice < ways[next] {
+                    current = next;
+                    break;
+                }
+                choice -= &ways[next];
+            }
+            path.push(current);
+        }
+        Some(self.to_joltages(&path))
+    }
+
+    // Only the adapters, without the outlet and the device
+    fn to_joltages(&self, path: &[usize]) -> Vec<I> {
+        path.iter()
+            .filter(|index| **index != 0 && **index != self.joltages.len() - 1)
+            .map(|index| self.joltages[*index].clone())
+            .collect()
+    }
+}
+
+// Lazy enumeration of the arrangements, in lexicographic order
+struct Arrangements<'a, I> {
+    adapters: &'a Adapters<I>,
+    // reachable[i]: the device can be reached from i
+    reachable: Vec<bool>,
+    path: Vec<usize>,
+    started: bool,
 }
 
-fn exercise_2(numbers: &mut Vec<Int>) -> Int {
-    numbers.sort_unstable();
-    numbers.reverse();
-    if numbers[numbers.len() - 1] != 0 {
-        numbers.push(0);
+impl<'a, I: Integer> Arrangements<'a, I> {
+    fn first_next(&self, from: usize, after: usize) -> Option<usize> {
+        self.adapters
+            .next_adapters(from)
+            .find(|next| *next > after && self.reachable[*next])
     }
 
-    let n = numbers.len();
+    fn extend_to_device(&mut self) {
+        let device = self.adapters.joltages.len() - 1;
+        while *self.path.last().unwrap() != device {
+            let last = *self.path.last().unwrap();
+            self.path.push(self.first_next(last, last).unwrap());
+        }
+    }
+}
 
-    let mut ways_to_go_to_end: Vec<Int> = Vec::new();
-    ways_to_go_to_end.resize(n, 0);
-    ways_to_go_to_end[0] = 1;
+impl<'a, I: Integer> Iterator for Arrangements<'a, I> {
+    type Item = Vec<I>;
 
-    for i in 1..n {
-        let mut j = 1;
-        while i >= j && numbers[i - j] - numbers[i] <= 3 {
-            ways_to_go_to_end[i] += ways_to_go_to_end[i - j];
-            j += 1;
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            if !self.reachable[0] {
+                return None;
+            }
+            self.path.push(0);
+        } else {
+            loop {
+                let last = self.path.pop()?;
+                let previous = *self.path.last()?;
+                if let Some(next) = self.first_next(previous, last) {
+                    self.path.push(next);
+                    break;
+                }
+            }
         }
+        self.extend_to_device();
+        Some(self.adapters.to_joltages(&self.path))
+    }
+}
+
+// Small xorshift64* generator, enough to pick arrangements for debugging
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32) as u32
     }
 
-    ways_to_go_to_end[n - 1]
+    // Uniform in 0..bound
+    fn below(&mut self, bound: &BigUint) -> BigUint {
+        let bits = bound.bits();
+        let digits = bits.div_ceil(32) as usize;
+        let top_bits = bits % 32;
+        let top_mask = if top_bits == 0 {
+            u32::MAX
+        } else {
+            (1 << top_bits) - 1
+        };
+        loop {
+            let mut random: Vec<u32> = (0..digits).map(|_| self.next_u32()).collect();
+            *random.last_mut().unwrap() &= top_mask;
+            let value = BigUint::new(random);
+            if &value < bound {
+                return value;
+            }
+        }
+    }
 }
 
-fn main() {
-    let mut numbers: Vec<Int> = std::io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .map(|x| x.parse::<Int>())
-        .map(Result::unwrap)
-        .collect();
-    println!("{}", exercise_1(&mut numbers));
-    println!("{}", exercise_2(&mut numbers));
+fn exercise_1<I: Integer>(numbers: &[I]) -> I {
+    let three = I::from_u64(3).expect("3 fits in every integer type");
+    let adapters = Adapters::new(numbers, three.clone());
+    let mut number_jump_1 = I::zero();
+    let mut number_jump_3 = I::zero();
+    for pair in adapters.joltages.windows(2) {
+        let jump = pair[1].clone() - pair[0].clone();
+        if jump == I::one() {
+            number_jump_1 += I::one();
+        }
+        if jump == three {
+            number_jump_3 += I::one();
+        }
+    }
+    number_jump_1 * number_jump_3
+}
+
+fn exercise_2<I: Integer>(numbers: &[I]) -> BigUint {
+    Adapters::new(
+        numbers,
+        I::from_u64(3).expect("3 fits in every integer type"),
+    )
+    .count_arrangements()
+}
+
+fn option_value(args: &[String], name: &str) -> anyhow::Result<Option<u64>> {
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(None),
+        Some(position) => match args.get(position + 1).map(|value| value.parse()) {
+            Some(Ok(value)) => Ok(Some(value)),
+            _ => anyhow::bail!("{} expects a number", name),
+        },
+    }
+}
+
+// Same output as {:?} for primitive integers, whatever the integer type
+fn format_joltages<I: Integer>(joltages: &[I]) -> String {
+    let joltages = joltages.iter().map(I::to_string).collect::<Vec<_>>();
+    format!("[{}]", joltages.join(", "))
+}
+
+fn main() -> anyhow::Result<()> {
+    run_checked::<Int, _>(Solve(std::env::args().skip(1).collect()))
+}
+
+struct Solve(Vec<String>);
+
+impl Solver for Solve {
+    type Output = anyhow::Result<()>;
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let args = &self.0;
+        let numbers = std::io::stdin()
+            .lock()
+            .lines()
+            .map(|line| Ok(line?.parse::<I>()?))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        if args.iter().all(|arg| arg == CHECKED_FLAG) {
+            println!("{}", exercise_1(&numbers));
+            println!("{}", exercise_2(&numbers));
+            return Ok(());
+        }
+
+        let max_gap = option_value(args, "--max-gap")?.unwrap_or(3);
+        let adapters = Adapters::new(
+            &numbers,
+            I::from_u64(max_gap)
+                .ok_or_else(|| anyhow::anyhow!("--max-gap {} is too large", max_gap))?,
+        );
+        match option_value(args, "--modulo")? {
+            Some(modulus) => println!("{}", adapters.count_arrangements_modulo(modulus)?),
+            None => println!("{}", adapters.count_arrangements()),
+        }
+        if let Some(number) = option_value(args, "--enumerate")? {
+            for arrangement in adapters.arrangements().take(number as usize) {
+                println!("{}", format_joltages(&arrangement));
+            }
+        }
+        if let Some(number) = option_value(args, "--sample")? {
+            let mut rng = XorShift::new(option_value(args, "--seed")?.unwrap_or(42));
+            for _ in 0..number {
+                if let Some(arrangement) = adapters.sample_arrangement(&mut rng) {
+                    println!("{}", format_joltages(&arrangement));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[test]
 fn test_exo_1() {
-    let mut numbers = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-    assert_eq!(exercise_1(&mut numbers), 7 * 5);
-    let mut numbers = vec![
+    let numbers = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+    assert_eq!(exercise_1(&numbers), 7 * 5);
+    let numbers = vec![
         28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8,
         17, 7, 9, 4, 2, 34, 10, 3,
     ];
-    assert_eq!(exercise_1(&mut numbers), 22 * 10);
+    assert_eq!(exercise_1(&numbers), 22 * 10);
 }
 
 #[test]
 fn test_exo_2() {
-    let mut numbers = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
-    assert_eq!(exercise_2(&mut numbers), 8);
-    let mut numbers = vec![
+    let numbers = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+    assert_eq!(exercise_2(&numbers), BigUint::from(8u32));
+    let numbers = vec![
         28, 33, 18, 42, 31, 14, 46, 20, 48, 47, 24, 23, 49, 45, 19, 38, 39, 11, 1, 32, 25, 35, 8,
         17, 7, 9, 4, 2, 34, 10, 3,
     ];
-    assert_eq!(exercise_2(&mut numbers), 19208);
+    assert_eq!(exercise_2(&numbers), BigUint::from(19208u32));
+    assert_eq!(
+        Adapters::new(&numbers, 3)
+            .count_arrangements_modulo(1000)
+            .unwrap(),
+        208
+    );
+    assert!(Adapters::new(&numbers, 3)
+        .count_arrangements_modulo(0)
+        .is_err());
+}
+
+#[test]
+fn test_max_gap() {
+    // Only the last adapter reaches the device, any subset of the others works
+    let numbers = (1..=100).collect::<Vec<Int>>();
+    let adapters = Adapters::new(&numbers, 101);
+    assert_eq!(adapters.count_arrangements(), BigUint::one() << 99);
+    assert_eq!(
+        adapters.count_arrangements_modulo(1_000_000_007).unwrap(),
+        988185646
+    );
+
+    assert_eq!(
+        Adapters::new(&[1, 2, 3], 1).count_arrangements(),
+        BigUint::one()
+    );
+    assert_eq!(
+        Adapters::new(&[1, 5], 2).count_arrangements(),
+        BigUint::zero()
+    );
+    assert_eq!(Adapters::new(&[1, 5], 2).arrangements().count(), 0);
+}
+
+#[test]
+fn test_arrangements() {
+    let numbers = vec![16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4];
+    let adapters = Adapters::new(&numbers, 3);
+    let arrangements = adapters.arrangements().collect::<Vec<_>>();
+    assert_eq!(arrangements.len(), 8);
+    assert_eq!(arrangements[0], vec![1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]);
+    assert_eq!(arrangements[7], vec![1, 4, 7, 10, 12, 15, 16, 19]);
+
+    let mut rng = XorShift::new(7);
+    for _ in 0..20 {
+        let sample = adapters.sample_arrangement(&mut rng).unwrap();
+        assert!(arrangements.contains(&sample));
+    }
+}
+
+#[test]
+fn test_option_value() {
+    let args = ["--modulo", "1000", "--seed", "x"].map(String::from);
+    assert_eq!(option_value(&args, "--modulo").unwrap(), Some(1000));
+    assert_eq!(option_value(&args, "--sample").unwrap(), None);
+    assert!(option_value(&args, "--seed").is_err());
+    assert!(option_value(&args[..1], "--modulo").is_err());
+}
+
+#[test]
+fn test_checked() {
+    let numbers = [16, 10, 15, 5, 1, 11, 7, 19, 6, 12, 4].map(Checked::<u8>);
+    assert_eq!(exercise_1(&numbers), Checked(7 * 5));
+    assert_eq!(exercise_2(&numbers), BigUint::from(8u32));
+    let arrangements = Adapters::new(&numbers, Checked(3))
+        .arrangements()
+        .collect::<Vec<_>>();
+    assert_eq!(
+        format_joltages(&arrangements[0]),
+        "[1, 4, 5, 6, 7, 10, 11, 12, 15, 16, 19]"
+    );
+}
+
+#[test]
+#[should_panic(expected = "250 + 10 overflows u8")]
+fn test_checked_device() {
+    Adapters::new(&[Checked(250u8)], Checked(10));
 }
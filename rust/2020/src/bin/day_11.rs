@@ -1,6 +1,12 @@
+use std::fmt;
 use std::io::BufRead;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use advent_of_code_common::{
+    cycle,
+    grid::{Grid, Position, NEIGHBOURS_8},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum Cell {
     Floor,
     Occupied,
@@ -13,83 +19,62 @@ enum RuleType {
     Complex,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct Grid(Vec<Vec<Cell>>);
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Layout(Grid<Cell>);
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Cell::Empty => 'L',
+            Cell::Floor => '.',
+            Cell::Occupied => '#',
+        };
+        write!(f, "{}", c)
+    }
+}
 
-impl Grid {
+impl Layout {
     fn build(lines: &[String]) -> Self {
-        Grid(
-            lines
-                .iter()
-                .map(|line| {
-                    line.chars()
-                        .map(|cell| match cell {
-                            'L' => Cell::Empty,
-                            '#' => Cell::Occupied,
-                            '.' => Cell::Floor,
-                            _ => unreachable!("Not a valid cell char"),
-                        })
-                        .collect()
-                })
-                .collect(),
+        Layout(
+            Grid::parse(lines, |cell| match cell {
+                'L' => Some(Cell::Empty),
+                '#' => Some(Cell::Occupied),
+                '.' => Some(Cell::Floor),
+                _ => None,
+            })
+            .expect("Not a valid cell char"),
         )
     }
 
-    fn value_neight_simple(&self, x: i32, y: i32) -> i32 {
-        let mut val = -Grid::value(self.0[x as usize][y as usize]);
-        for i in -1..=1 {
-            for j in -1..=1 {
-                if (0..self.0.len() as i32).contains(&(x + i))
-                    && (0..self.0[0].len() as i32).contains(&(y + j))
-                {
-                    val += Grid::value(self.0[(x + i) as usize][(y + j) as usize]);
-                }
-            }
-        }
-        val
+    fn value_neight_simple(&self, position: Position) -> i32 {
+        self.0
+            .neighbours_8(position)
+            .map(|neighbour| Layout::value(self.0[neighbour]))
+            .sum()
     }
 
-    fn value_neight_complex(&self, x: i32, y: i32) -> i32 {
-        let mut val = 0;
-        for (i, j) in [
-            (1, 0),
-            (-1, 0),
-            (0, 1),
-            (0, -1),
-            (1, 1),
-            (-1, -1),
-            (-1, 1),
-            (1, -1),
-        ]
-        .iter()
-        {
-            let mut x = x + i;
-            let mut y = y + j;
-            while (0..self.0.len() as i32).contains(&x) && (0..self.0[0].len() as i32).contains(&y)
-            {
-                match self.0[(x) as usize][(y) as usize] {
-                    Cell::Occupied => {
-                        val += 1;
-                        break;
-                    }
-                    Cell::Empty => break,
-                    _ => (),
-                };
-                x += i;
-                y += j;
-            }
-        }
-        val
+    // First seat seen in each direction
+    fn value_neight_complex(&self, position: Position) -> i32 {
+        NEIGHBOURS_8
+            .iter()
+            .filter_map(|direction| {
+                self.0
+                    .ray(position, *direction)
+                    .map(|seen| self.0[seen])
+                    .find(|cell| *cell != Cell::Floor)
+            })
+            .map(Layout::value)
+            .sum()
     }
 
-    fn next_cell(&self, x: i32, y: i32, rule: RuleType, maximal_neight: i32) -> Cell {
-        let old_cell = self.0[x as usize][y as usize];
+    fn next_cell(&self, position: Position, rule: RuleType, maximal_neight: i32) -> Cell {
+        let old_cell = self.0[position];
         if old_cell == Cell::Floor {
             return old_cell;
         }
         let val = match rule {
-            RuleType::Simple => self.value_neight_simple(x, y),
-            RuleType::Complex => self.value_neight_complex(x, y),
+            RuleType::Simple => self.value_neight_simple(position),
+            RuleType::Complex => self.value_neight_complex(position),
         };
         if val == 0 {
             Cell::Occupied
@@ -100,19 +85,38 @@ impl Grid {
         }
     }
 
-    fn one_step(&mut self, rule: RuleType, maximal_neight: i32) -> bool {
-        let mut modified = false;
-        let mut new_grid = Grid(vec![]);
-        for (x, line) in self.0.iter().enumerate() {
-            new_grid.0.push(vec![]);
-            for (y, cell) in line.iter().enumerate() {
-                let new_cell = self.next_cell(x as i32, y as i32, rule, maximal_neight);
-                modified |= &new_cell != cell;
-                new_grid.0[x].push(new_cell);
+    fn one_step(&self, rule: RuleType, maximal_neight: i32) -> Layout {
+        Layout(
+            self.0
+                .map_positions(|position, _| self.next_cell(position, rule, maximal_neight)),
+        )
+    }
+
+    // Runs the rule until the seats stop changing
+    fn settle(self, rule: RuleType, maximal_neight: i32) -> anyhow::Result<Layout> {
+        // Coming back to a layout saved at a power of two round means the seats cycle
+        // with a period above 1, which only then is measured
+        let (mut current, mut saved) = (self.clone(), self.clone());
+        let mut round = 0_u64;
+        loop {
+            let next = current.one_step(rule, maximal_neight);
+            if next == current {
+                return Ok(current);
+            }
+            if next == saved {
+                let found = cycle::brent(self, |layout| layout.one_step(rule, maximal_neight));
+                anyhow::bail!(
+                    "the seats never settle: from round {} they repeat every {} rounds",
+                    found.start,
+                    found.length
+                );
             }
+            round += 1;
+            if round.is_power_of_two() {
+                saved = next.clone();
+            }
+            current = next;
         }
-        self.0 = new_grid.0;
-        modified
     }
 
     fn value(cell: Cell) -> i32 {
@@ -123,55 +127,34 @@ impl Grid {
     }
 
     fn count_occupied(&self) -> usize {
-        self.0
-            .iter()
-            .cloned()
-            .map(|line| line.iter().filter(|x| *x == &Cell::Occupied).count())
-            .sum()
-    }
-
-    fn _print(&self) {
-        for line in &self.0 {
-            for x in line {
-                print!(
-                    "{}",
-                    match x {
-                        Cell::Empty => 'L',
-                        Cell::Floor => '.',
-                        Cell::Occupied => '#',
-                    }
-                )
-            }
-            println!();
-        }
+        self.0.values().filter(|x| **x == Cell::Occupied).count()
     }
 }
 
-fn exercise_1(mut grid: Grid) -> i32 {
-    while grid.one_step(RuleType::Simple, 4) {}
-    grid.count_occupied() as i32
+fn exercise_1(grid: Layout) -> anyhow::Result<usize> {
+    Ok(grid.settle(RuleType::Simple, 4)?.count_occupied())
 }
 
-fn exercise_2(mut grid: Grid) -> i32 {
-    while grid.one_step(RuleType::Complex, 5) {}
-    grid.count_occupied() as i32
+fn exercise_2(grid: Layout) -> anyhow::Result<usize> {
+    Ok(grid.settle(RuleType::Complex, 5)?.count_occupied())
 }
 
-fn main() {
-    let grid = Grid::build(
+fn main() -> anyhow::Result<()> {
+    let grid = Layout::build(
         &std::io::stdin()
             .lock()
             .lines()
             .map(Result::unwrap)
             .collect::<Vec<_>>(),
     );
-    println!("{}", exercise_1(grid.clone()));
-    println!("{}", exercise_2(grid));
+    println!("{}", exercise_1(grid.clone())?);
+    println!("{}", exercise_2(grid)?);
+    Ok(())
 }
 
 #[test]
 fn test_one_step_simple() {
-    let mut grid1 = Grid::build(&vec![
+    let grid1 = Layout::build(&vec![
         "#.LL.L#.##".into(),
         "#LLLLLL.L#".into(),
         "L.L.L..L..".into(),
@@ -183,9 +166,9 @@ fn test_one_step_simple() {
         "#.LLLLLL.L".into(),
         "#.#LLLL.##".into(),
     ]);
-    grid1.one_step(RuleType::Simple, 4);
-    grid1._print();
-    let grid2 = Grid::build(&vec![
+    let grid1 = grid1.one_step(RuleType::Simple, 4);
+    println!("{}", grid1.0);
+    let grid2 = Layout::build(&vec![
         "#.##.L#.##".into(),
         "#L###LL.L#".into(),
         "L.#.#..#..".into(),
@@ -197,13 +180,12 @@ fn test_one_step_simple() {
         "#.LL###L.L".into(),
         "#.#L###.##".into(),
     ]);
-    println!();
-    grid2._print();
+    println!("{}", grid2.0);
     assert_eq!(grid1, grid2);
 }
 #[test]
 fn test_one_step_complex() {
-    let mut grid1 = Grid::build(&vec![
+    let grid1 = Layout::build(&vec![
         "#.L#.##.L#".into(),
         "#L#####.LL".into(),
         "L.#.#..#..".into(),
@@ -215,9 +197,9 @@ fn test_one_step_complex() {
         "#.L#####.L".into(),
         "#.L####.L#".into(),
     ]);
-    grid1.one_step(RuleType::Complex, 5);
-    grid1._print();
-    let grid2 = Grid::build(&vec![
+    let grid1 = grid1.one_step(RuleType::Complex, 5);
+    println!("{}", grid1.0);
+    let grid2 = Layout::build(&vec![
         "#.L#.L#.L#".into(),
         "#LLLLLL.LL".into(),
         "L.L.L..#..".into(),
@@ -229,14 +211,13 @@ fn test_one_step_complex() {
         "#.LLLLL#.L".into(),
         "#.L#LL#.L#".into(),
     ]);
-    println!();
-    grid2._print();
+    println!("{}", grid2.0);
     assert_eq!(grid1, grid2);
 }
 
 #[test]
 fn test_exo_1() {
-    let grid = Grid::build(&vec![
+    let grid = Layout::build(&vec![
         "L.LL.LL.LL".into(),
         "LLLLLLL.LL".into(),
         "L.L.L..L..".into(),
@@ -248,11 +229,11 @@ fn test_exo_1() {
         "L.LLLLLL.L".into(),
         "L.LLLLL.LL".into(),
     ]);
-    assert_eq!(exercise_1(grid), 37);
+    assert_eq!(exercise_1(grid).unwrap(), 37);
 }
 #[test]
 fn test_exo_2() {
-    let grid = Grid::build(&vec![
+    let grid = Layout::build(&vec![
         "L.LL.LL.LL".into(),
         "LLLLLLL.LL".into(),
         "L.L.L..L..".into(),
@@ -264,5 +245,24 @@ fn test_exo_2() {
         "L.LLLLLL.L".into(),
         "L.LLLLL.LL".into(),
     ]);
-    assert_eq!(exercise_2(grid), 26);
+    assert_eq!(exercise_2(grid).unwrap(), 26);
+}
+
+#[test]
+fn test_settle() {
+    let grid = Layout::build(&["L.L".to_string(), ".L.".to_string()]);
+    let settled = grid.clone().settle(RuleType::Simple, 4).unwrap();
+    assert_eq!(settled.count_occupied(), 3);
+    // Two neighbours fill up together, then leave together
+    let error = Layout::build(&["LL".to_string()])
+        .settle(RuleType::Simple, 1)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "the seats never settle: from round 0 they repeat every 2 rounds"
+    );
+    let error = Layout::build(&["LLL".to_string(), "...".to_string(), "LL.".to_string()])
+        .settle(RuleType::Simple, 1)
+        .unwrap_err();
+    assert!(error.to_string().contains("they repeat every 2 rounds"));
 }
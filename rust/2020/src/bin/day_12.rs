@@ -1,10 +1,11 @@
-#![feature(destructuring_assignment)]
 use std::io::BufRead;
 
-peg::parser! {
+use advent_of_code_common::geometry::{Coordinate, Direction, GeometryError, Point2};
+
+advent_of_code_common::parser! {
 grammar instruction_parser() for str {
-    rule number() -> i32
-      = n:$(['0'..='9']+) { n.parse().unwrap() }
+    rule number() -> Coordinate
+      = i64()
 
     rule north() -> Instruction
        = "N" n:number() {Instruction::N(n)}
@@ -27,146 +28,82 @@ grammar instruction_parser() for str {
 
 #[derive(Debug, PartialEq)]
 pub enum Instruction {
-    N(i32),
-    S(i32),
-    E(i32),
-    W(i32),
-    L(i32),
-    R(i32),
-    F(i32),
+    N(Coordinate),
+    S(Coordinate),
+    E(Coordinate),
+    W(Coordinate),
+    L(Coordinate),
+    R(Coordinate),
+    F(Coordinate),
 }
 
 #[derive(Debug, Clone, Copy)]
-enum Direction {
-    East,
-    West,
-    North,
-    South,
-}
-
-#[derive(Debug, Clone)]
-struct Position {
-    x: i32,
-    y: i32,
+struct Ship {
+    position: Point2,
     facing: Direction,
 }
 
-fn rotate(mut facing: Direction, angle: i32) -> Direction {
-    // trigo sense
-    if angle % 90 != 0 {
-        panic!("Should not do non-squared angles");
-    }
-
-    let mut number_round = angle / 90;
-    while number_round != 0 {
-        facing = match (facing, number_round > 0) {
-            (Direction::East, true) | (Direction::West, false) => Direction::North,
-            (Direction::West, true) | (Direction::East, false) => Direction::South,
-            (Direction::North, true) | (Direction::South, false) => Direction::West,
-            (Direction::South, true) | (Direction::North, false) => Direction::East,
-        };
-        if number_round < 0 {
-            number_round += 1;
-        } else {
-            number_round -= 1;
-        }
-    }
-    // dbg!(facing);
-    facing
-}
-
-fn execute_instruction_1(pos: &Position, inst: &Instruction) -> Position {
-    let mut pos = pos.clone();
-    match (inst, pos.facing) {
-        (Instruction::N(x), _) | (Instruction::F(x), Direction::North) => pos.y += x,
-        (Instruction::S(x), _) | (Instruction::F(x), Direction::South) => pos.y -= x,
-        (Instruction::E(x), _) | (Instruction::F(x), Direction::East) => pos.x += x,
-        (Instruction::W(x), _) | (Instruction::F(x), Direction::West) => pos.x -= x,
-        (Instruction::L(x), _) => pos.facing = rotate(pos.facing, *x),
-        (Instruction::R(x), _) => pos.facing = rotate(pos.facing, -*x),
+fn execute_instruction_1(ship: &Ship, inst: &Instruction) -> Result<Ship, GeometryError> {
+    let mut ship = *ship;
+    match *inst {
+        Instruction::N(x) => ship.position += Direction::North.unit() * x,
+        Instruction::S(x) => ship.position += Direction::South.unit() * x,
+        Instruction::E(x) => ship.position += Direction::East.unit() * x,
+        Instruction::W(x) => ship.position += Direction::West.unit() * x,
+        Instruction::L(x) => ship.facing = ship.facing.rotate_degrees(x)?,
+        Instruction::R(x) => ship.facing = ship.facing.rotate_degrees(-x)?,
+        Instruction::F(x) => ship.position += ship.facing.unit() * x,
     }
-    pos
+    Ok(ship)
 }
 
-fn exercise_1(instructions: &[Instruction]) -> i32 {
-    let mut current_position = Position {
-        x: 0,
-        y: 0,
+fn exercise_1(instructions: &[Instruction]) -> Result<Coordinate, GeometryError> {
+    let mut ship = Ship {
+        position: Point2::ORIGIN,
         facing: Direction::East,
     };
     for inst in instructions {
-        current_position = execute_instruction_1(&current_position, inst);
+        ship = execute_instruction_1(&ship, inst)?;
     }
 
-    current_position.x.abs() + current_position.y.abs()
+    Ok(ship.position.manhattan())
 }
 
+// The waypoint is relative to the ship
 fn execute_instruction_2(
-    (pos, target): &(Position, Position),
+    (ship, waypoint): (Point2, Point2),
     inst: &Instruction,
-) -> (Position, Position) {
-    let (mut pos, mut target) = (pos.clone(), target.clone());
-    match inst {
-        Instruction::N(x) => target.y += x,
-        Instruction::S(x) => target.y -= x,
-        Instruction::E(x) => target.x += x,
-        Instruction::W(x) => target.x -= x,
-        Instruction::L(mut x) => {
-            if x % 90 != 0 {
-                panic!("Should not do non-squared angles");
-            }
-            x /= 90;
-            for _ in 0..x {
-                (target.x, target.y) = (-target.y, target.x)
-            }
-        }
-        Instruction::R(mut x) => {
-            if x % 90 != 0 {
-                panic!("Should not do non-squared angles");
-            }
-            x /= 90;
-            for _ in 0..x {
-                (target.x, target.y) = (target.y, -target.x)
-            }
-        }
-        Instruction::F(n) => {
-            pos.x += n * target.x;
-            pos.y += n * target.y;
-        }
-    }
-    (pos, target)
+) -> Result<(Point2, Point2), GeometryError> {
+    Ok(match *inst {
+        Instruction::N(x) => (ship, waypoint + Direction::North.unit() * x),
+        Instruction::S(x) => (ship, waypoint + Direction::South.unit() * x),
+        Instruction::E(x) => (ship, waypoint + Direction::East.unit() * x),
+        Instruction::W(x) => (ship, waypoint + Direction::West.unit() * x),
+        Instruction::L(x) => (ship, waypoint.rotate_degrees(x)?),
+        Instruction::R(x) => (ship, waypoint.rotate_degrees(-x)?),
+        Instruction::F(n) => (ship + waypoint * n, waypoint),
+    })
 }
 
-fn exercise_2(instructions: &[Instruction]) -> i32 {
-    let (mut current_position, mut target) = (
-        Position {
-            x: 0,
-            y: 0,
-            facing: Direction::East,
-        },
-        Position {
-            x: 10,
-            y: 1,
-            facing: Direction::East,
-        },
-    );
+fn exercise_2(instructions: &[Instruction]) -> Result<Coordinate, GeometryError> {
+    let mut state = (Point2::ORIGIN, Point2::new(10, 1));
     for inst in instructions {
-        (current_position, target) = execute_instruction_2(&(current_position, target), inst);
+        state = execute_instruction_2(state, inst)?;
     }
 
-    current_position.x.abs() + current_position.y.abs()
+    Ok(state.0.manhattan())
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let instructions = std::io::stdin()
         .lock()
         .lines()
-        .map(Result::unwrap)
-        .map(|x| instruction_parser::instruction(&x).unwrap())
-        .collect::<Vec<_>>();
+        .map(|x| Ok(instruction_parser::instruction(&x?)?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    println!("{}", exercise_1(&instructions));
-    println!("{}", exercise_2(&instructions));
+    println!("{}", exercise_1(&instructions)?);
+    println!("{}", exercise_2(&instructions)?);
+    Ok(())
 }
 
 #[test]
@@ -191,7 +128,11 @@ fn test_exo1() {
         .map(instruction_parser::instruction)
         .map(Result::unwrap)
         .collect::<Vec<_>>();
-    assert_eq!(exercise_1(&instructions), 25);
+    assert_eq!(exercise_1(&instructions), Ok(25));
+    assert_eq!(
+        exercise_1(&[Instruction::L(45)]),
+        Err(GeometryError::NotRightAngle(45))
+    );
 }
 #[test]
 fn test_exo2() {
@@ -201,5 +142,10 @@ fn test_exo2() {
         .map(instruction_parser::instruction)
         .map(Result::unwrap)
         .collect::<Vec<_>>();
-    assert_eq!(exercise_2(&instructions), 286);
+    assert_eq!(exercise_2(&instructions), Ok(286));
+    // Turning a full circle either way leaves the waypoint in place
+    assert_eq!(
+        exercise_2(&[Instruction::L(270), Instruction::L(450), Instruction::F(1)]),
+        Ok(11)
+    );
 }
@@ -1,70 +1,79 @@
 use std::io::BufRead;
 
-use itertools::zip;
-use num::Integer;
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::{
+    integer::{run_checked, Integer, Solver},
+    number_theory::{crt, Congruence},
+};
 
 type Int = i128;
 
-fn exercise_1(start: Int, buses: &[Option<Int>]) -> Int {
+fn exercise_1<I: Integer>(start: I, buses: &[Option<I>]) -> I {
     // remaining time, bus id
-    let remaining_time: Vec<(Int, Int)> = buses
+    let remaining_time: Vec<(I, I)> = buses
         .iter()
-        .cloned()
-        .filter(Option::is_some)
-        .map(Option::unwrap)
-        .map(|bus_id| (bus_id - start % bus_id, bus_id))
+        .flatten()
+        .map(|bus_id| {
+            (
+                bus_id.clone() - start.clone() % bus_id.clone(),
+                bus_id.clone(),
+            )
+        })
         .collect();
 
-    let (time, bus) = remaining_time.iter().min().unwrap();
+    let (time, bus) = remaining_time.into_iter().min().unwrap();
     time * bus
 }
 
-fn crt(targets: &[Int], modulos: &[Int]) -> Option<Int> {
-    let mut current_modulus = 1;
-    let mut current_value = 0;
-    for (target, modulus) in zip(targets.iter(), modulos.iter()) {
-        let gcd = current_modulus.extended_gcd(&modulus);
-        assert!(gcd.x * current_modulus + gcd.y * modulus == gcd.gcd);
-        if gcd.gcd != 1 {
-            return None;
-        }
-        current_value = target * gcd.x * current_modulus + current_value * gcd.y * modulus;
-        current_modulus *= modulus;
-        current_value = current_value.rem_euclid(current_modulus);
-    }
-    Some(current_value)
-}
-
-fn exercise_2(buses: &[Option<Int>]) -> Int {
-    let (target, modulo): (Vec<Int>, Vec<Int>) = buses
+fn exercise_2<I: Integer>(buses: &[Option<I>]) -> I {
+    // Bus x leaves n minutes after t: t = -n mod x, written without negative numbers
+    let congruences: Vec<Congruence<I>> = buses
         .iter()
-        .cloned()
         .enumerate()
-        .filter(|x| x.1.is_some())
-        .map(|(n, x)| ((x.unwrap() - n as Int), x.unwrap()))
-        .unzip();
-    dbg!(&target, &modulo);
-    crt(&target, &modulo).expect("CRT algorithm could not conclude")
+        .filter_map(|(n, bus)| {
+            bus.clone().map(|x| {
+                let n = I::from_u64(n as u64).expect("the position of a bus fits") % x.clone();
+                Congruence {
+                    residue: (x.clone() - n) % x.clone(),
+                    modulus: x,
+                }
+            })
+        })
+        .collect();
+    crt(&congruences)
+        .expect("CRT algorithm could not conclude")
+        .residue
 }
 
-fn parse_string(line: &str) -> Vec<Option<Int>> {
+fn parse_string<I: Integer>(line: &str) -> Vec<Option<I>> {
     line.split(',').map(|x| x.parse().ok()).collect()
 }
 
 fn main() {
-    let stdin = std::io::stdin();
-    let mut lines = stdin.lock().lines();
-    let start: Int = lines.next().unwrap().map(|x| x.parse().unwrap()).unwrap();
-    let buses = parse_string(&lines.next().unwrap().unwrap());
+    run_checked::<Int, _>(Solve)
+}
 
-    println!("{}", exercise_1(start, &buses));
-    println!("{}", exercise_2(&buses));
+struct Solve;
+
+impl Solver for Solve {
+    type Output = ();
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let stdin = std::io::stdin();
+        let mut lines = stdin.lock().lines();
+        let start: I = lines.next().unwrap().map(|x| x.parse().unwrap()).unwrap();
+        let buses = parse_string(&lines.next().unwrap().unwrap());
+
+        println!("{}", exercise_1(start, &buses));
+        println!("{}", exercise_2(&buses));
+    }
 }
 
 #[test]
 fn test_parse_string() {
     assert_eq!(
-        parse_string("7,13,x,x,59,x,31,19"),
+        parse_string::<Int>("7,13,x,x,59,x,31,19"),
         vec![
             Some(7),
             Some(13),
@@ -80,17 +89,26 @@ fn test_parse_string() {
 
 #[test]
 fn test_exo1() {
-    assert_eq!(exercise_1(939, &parse_string("7,13,x,x,59,x,31,19")), 295);
+    assert_eq!(
+        exercise_1(939, &parse_string::<Int>("7,13,x,x,59,x,31,19")),
+        295
+    );
 }
 
 #[test]
 fn test_exo2() {
-    assert_eq!(exercise_2(&parse_string("7,13,x,x,59,x,31,19")), 1068781);
-    assert_eq!(exercise_2(&parse_string("17,x,13,19")), 3417);
-    assert_eq!(exercise_2(&parse_string("67,7,59,61")), 754018);
-    assert_eq!(exercise_2(&parse_string("67,x,7,59,61")), 779210);
-    assert_eq!(exercise_2(&parse_string("67,7,x,59,61")), 1261476);
-    assert_eq!(exercise_2(&parse_string("1789,37,47,1889")), 1202161486);
+    assert_eq!(
+        exercise_2(&parse_string::<Int>("7,13,x,x,59,x,31,19")),
+        1068781
+    );
+    assert_eq!(exercise_2(&parse_string::<Int>("17,x,13,19")), 3417);
+    assert_eq!(exercise_2(&parse_string::<Int>("67,7,59,61")), 754018);
+    assert_eq!(exercise_2(&parse_string::<Int>("67,x,7,59,61")), 779210);
+    assert_eq!(exercise_2(&parse_string::<Int>("67,7,x,59,61")), 1261476);
+    assert_eq!(
+        exercise_2(&parse_string::<Int>("1789,37,47,1889")),
+        1202161486
+    );
 }
 
 #[test]
@@ -99,7 +117,23 @@ fn test_crt() {
     let total_mod: Int = primes.iter().product();
     let numbers = [5000, 15698, 123548, 123549, 13215687, 12358914, 985621];
     for n in numbers.iter() {
-        let targets = primes.iter().map(|x| n % x).collect::<Vec<Int>>();
-        assert_eq!(crt(&targets, &primes).unwrap(), n % total_mod);
+        let congruences = primes
+            .iter()
+            .map(|x| Congruence {
+                residue: n % x,
+                modulus: *x,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(crt(&congruences).unwrap().residue, n % total_mod);
     }
+    // Buses sharing a factor can still agree
+    assert_eq!(exercise_2(&parse_string::<Int>("6,x,4")), 6);
+}
+
+#[test]
+fn test_other_integers() {
+    let buses = parse_string::<Checked<u64>>("7,13,x,x,59,x,31,19");
+    assert_eq!(exercise_1(Checked(939), &buses), Checked(295));
+    assert_eq!(exercise_2(&buses), Checked(1068781));
+    assert_eq!(exercise_2(&parse_string::<u16>("17,x,13,19")), 3417);
 }
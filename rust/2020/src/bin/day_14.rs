@@ -1,90 +1,36 @@
 use std::collections::HashMap;
 use std::io::BufRead;
 
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::{
+    bits::{self, Pattern},
+    integer::{run_checked, Integer, Solver},
+};
+
+// Only the sum is an Int, addresses and values stay u64
+type Int = u64;
+
+// 36 bits, X being left as is for values and floating for addresses
 #[derive(Debug, PartialEq, Clone)]
-pub struct Mask {
-    pub mask1: u64,
-    pub mask0: u64,
-    pub floating: [bool; 36],
-}
+pub struct Mask(Pattern);
 
 impl Mask {
     fn new() -> Self {
-        Mask {
-            floating: [false; 36],
-            mask1: 0,
-            mask0: 0xFFFFFFFFFFFFFFFF,
-        }
-    }
-    fn from_str(s: &str) -> Self {
-        let mut mask = Mask::new();
-        let _ = s
-            .chars()
-            .rev()
-            .enumerate()
-            .map(|(n, c)| match c {
-                '0' => mask.mask0 ^= 1 << n,
-                '1' => mask.mask1 ^= 1 << n,
-                'X' => mask.floating[n] = true,
-                _ => (),
-            })
-            .collect::<Vec<_>>();
-        mask
+        Mask(Pattern {
+            width: 36,
+            ..Pattern::default()
+        })
     }
 
     fn apply_value(&self, x: u64) -> u64 {
-        x & self.mask0 | self.mask1
+        self.0.apply(x)
     }
-    fn apply_mem(&self, real_address: u64) -> Address {
-        let mut address = Address([None; 36]);
-
-        let physical_address = real_address | self.mask1;
-
-        for i in 0..36 {
-            if !self.floating[i] {
-                address.0[i] = Some(physical_address & (1 << i) != 0);
-            }
-        }
-
-        address
-    }
-}
-
-#[derive(Debug, Clone, PartialEq)]
-struct Address([Option<bool>; 36]);
 
-impl Address {
-    fn generate_all_adresses(&self) -> Vec<u64> {
-        let mut total_addr: Vec<u64> = vec![];
-        let mut stack: Vec<Address> = vec![self.clone()];
-
-        while !stack.is_empty() {
-            let current_addr = stack.pop().unwrap();
-            let mut finished_addr = true;
-            for i in 0..36 {
-                if current_addr.0[i].is_none() {
-                    finished_addr = false;
-                    let mut new_addr1 = current_addr.clone();
-                    let mut new_addr2 = current_addr.clone();
-                    new_addr1.0[i] = Some(false);
-                    new_addr2.0[i] = Some(true);
-                    stack.push(new_addr1);
-                    stack.push(new_addr2);
-                    break;
-                }
-            }
-            if finished_addr {
-                total_addr.push(current_addr.to_real_address().unwrap());
-            }
-        }
-        total_addr
-    }
-    fn to_real_address(&self) -> Option<u64> {
-        self.0
-            .iter()
-            .enumerate()
-            .map(|(n, b)| if (*b)? { Some(1 << n) } else { Some(0) })
-            .sum()
+    fn apply_mem(&self, real_address: u64) -> impl Iterator<Item = u64> {
+        let floating = self.0.wildcards;
+        let fixed = (real_address | self.0.value) & !floating;
+        bits::subsets(floating).map(move |bits| fixed | bits)
     }
 }
 
@@ -94,22 +40,29 @@ pub enum Instruction {
     MaskAssign(Mask),
 }
 
-peg::parser! {
+advent_of_code_common::parser! {
 grammar instruction_parser() for str {
-    rule number() -> u64
-      = n:$(['0'..='9']+) { n.parse().unwrap() }
     rule mask() -> Mask
-      =  s:$(("0" / "1" / "X")+) {Mask::from_str(s)}
+      =  s:$(("0" / "1" / "X")+) {? Pattern::parse(s, &bits::MASK).map(Mask).or(Err("a mask of at most 64 bits")) }
     rule mask_assign() -> Instruction
       = "mask = " m:mask() {Instruction::MaskAssign(m)}
     rule mem_assign() -> Instruction
-      = "mem[" addr:number() "] = " val:number() {Instruction::MemAssign(addr, val)}
+      = "mem[" addr:u64() "] = " val:u64() {Instruction::MemAssign(addr, val)}
     pub rule instruction() -> Instruction
         = instr:(mem_assign()/mask_assign()) {instr}
 }
 }
 
-fn exercise_1(instructions: &[Instruction]) -> u64 {
+fn memory_sum<I: Integer>(memory: &HashMap<u64, u64>) -> anyhow::Result<I> {
+    memory
+        .values()
+        .map(|&value| {
+            I::from_u64(value).ok_or_else(|| anyhow::anyhow!("the value {} is too large", value))
+        })
+        .sum()
+}
+
+fn exercise_1<I: Integer>(instructions: &[Instruction]) -> anyhow::Result<I> {
     let mut memory: HashMap<u64, u64> = HashMap::new();
 
     let mut current_mask = Mask::new();
@@ -123,10 +76,10 @@ fn exercise_1(instructions: &[Instruction]) -> u64 {
         }
     }
 
-    memory.values().sum()
+    memory_sum(&memory)
 }
 
-fn exercise_2(instructions: &[Instruction]) -> u64 {
+fn exercise_2<I: Integer>(instructions: &[Instruction]) -> anyhow::Result<I> {
     let mut memory: HashMap<u64, u64> = HashMap::new();
 
     let mut current_mask = Mask::new();
@@ -134,7 +87,7 @@ fn exercise_2(instructions: &[Instruction]) -> u64 {
     for instr in instructions {
         match instr {
             Instruction::MemAssign(addr, val) => {
-                for address in current_mask.apply_mem(*addr).generate_all_adresses() {
+                for address in current_mask.apply_mem(*addr) {
                     *memory.entry(address).or_insert(0) = *val;
                 }
             }
@@ -142,133 +95,138 @@ fn exercise_2(instructions: &[Instruction]) -> u64 {
         }
     }
 
-    memory.values().sum()
+    memory_sum(&memory)
+}
+
+fn main() -> anyhow::Result<()> {
+    run_checked::<Int, _>(Solve)
 }
 
-fn main() {
-    let instructions = std::io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .map(|x| instruction_parser::instruction(&x).unwrap())
-        .collect::<Vec<_>>();
+struct Solve;
 
-    println!("{}", exercise_1(&instructions));
-    println!("{}", exercise_2(&instructions));
+impl Solver for Solve {
+    type Output = anyhow::Result<()>;
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let instructions = std::io::stdin()
+            .lock()
+            .lines()
+            .map(|x| Ok(instruction_parser::instruction(&x?)?))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        println!("{}", exercise_1::<I>(&instructions)?);
+        println!("{}", exercise_2::<I>(&instructions)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn mask(s: &str) -> Mask {
+    Mask(Pattern::parse(s, &bits::MASK).unwrap())
 }
 
 #[test]
 fn test_parser() {
+    let instructions = vec![
+        "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X",
+        "mem[8] = 11",
+        "mem[7] = 101",
+        "mem[8] = 0",
+    ]
+    .iter()
+    .map(|x| instruction_parser::instruction(&x).unwrap())
+    .collect::<Vec<_>>();
+    match &instructions[0] {
+        Instruction::MaskAssign(Mask(pattern)) => {
+            assert_eq!(pattern.value, 0b1000000);
+            assert_eq!(pattern.mask, 0b1000010);
+            assert_eq!(pattern.wildcards.count_ones(), 34);
+            assert_eq!(pattern.width, 36);
+        }
+        other => panic!("{:?} is not a mask", other),
+    }
     assert_eq!(
-        vec![
-            "mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X",
-            "mem[8] = 11",
-            "mem[7] = 101",
-            "mem[8] = 0",
-        ]
-        .iter()
-        .map(|x| instruction_parser::instruction(&x).unwrap())
-        .collect::<Vec<_>>(),
-        vec![
-            Instruction::MaskAssign(Mask {
-                mask0: 0xFFFFFFFFFFFFFFFF ^ 0b10,
-                mask1: 0b1000000,
-                floating: [
-                    true, false, true, true, true, true, false, true, true, true, true, true, true,
-                    true, true, true, true, true, true, true, true, true, true, true, true, true,
-                    true, true, true, true, true, true, true, true, true, true
-                ]
-            }),
+        instructions[1..],
+        [
             Instruction::MemAssign(8, 11),
             Instruction::MemAssign(7, 101),
             Instruction::MemAssign(8, 0)
         ]
-    )
-}
-
-#[test]
-fn test_address() {
-    let address = Address([Some(true); 36]);
-    assert_eq!(address.to_real_address().unwrap(), 0xFFFFFFFFF);
-    assert_eq!(address.generate_all_adresses().len(), 1);
-
-    let mut address = Address([Some(false); 36]);
-    assert_eq!(address.to_real_address().unwrap(), 0);
-    assert_eq!(address.generate_all_adresses().len(), 1);
-
-    address.0[8] = None;
-    assert_eq!(address.generate_all_adresses().len(), 2);
-    assert_eq!(address.generate_all_adresses(), vec![1 << 8, 0]);
-
-    address.0[32] = None;
-    assert_eq!(address.generate_all_adresses().len(), 4);
-    assert_eq!(
-        address.generate_all_adresses(),
-        vec![(1 << 8) + (1 << 32), 1 << 8, 1 << 32, 0]
     );
+    assert!(instruction_parser::instruction("mem[18446744073709551616] = 0").is_err());
+    assert!(instruction_parser::instruction(&format!("mask = {}", "X".repeat(65))).is_err());
 }
 
 #[test]
 fn test_apply_mem() {
-    let mut mask = Mask::new();
-    let mut addr = Address([Some(false); 36]);
-    assert_eq!(mask.apply_mem(0), addr);
+    let mut no_mask = Mask::new().apply_mem(0);
+    assert_eq!(no_mask.next(), Some(0));
+    assert_eq!(no_mask.next(), None);
 
-    mask.floating[4] = true;
-    addr.0[4] = None;
-    assert_eq!(mask.apply_mem(0), addr);
+    let floating = mask("000000000000000000000000000000000000");
+    assert_eq!(floating.apply_mem(1 << 8).collect::<Vec<_>>(), vec![1 << 8]);
 
-    mask.mask1 = 1 << 7;
-    addr.0[7] = Some(true);
-    assert_eq!(mask.apply_mem(0), addr);
+    let floating = mask("000X0000000000000000000000000000X000");
+    assert_eq!(
+        floating.apply_mem(0).collect::<Vec<_>>(),
+        vec![0, 1 << 3, 1 << 32, (1 << 32) + (1 << 3)]
+    );
+    // Floating bits replace the address bits, ones are forced
+    let floating = mask("0000000000000000000000000000X0000010");
+    assert_eq!(
+        floating.apply_mem(0b10000001).collect::<Vec<_>>(),
+        vec![0b00000011, 0b10000011]
+    );
 }
 
 #[test]
 fn test_exo1() {
     assert_eq!(
-        exercise_1(&[
-            Instruction::MaskAssign(Mask {
-                mask0: 0xFFFFFFFFFFFFFFFF ^ 0b10,
-                mask1: 0b1000000,
-                floating: [
-                    true, false, true, true, true, true, false, true, true, true, true, true, true,
-                    true, true, true, true, true, true, true, true, true, true, true, true, true,
-                    true, true, true, true, true, true, true, true, true, true
-                ]
-            }),
+        exercise_1::<Int>(&[
+            Instruction::MaskAssign(mask("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X")),
             Instruction::MemAssign(8, 11),
             Instruction::MemAssign(7, 101),
             Instruction::MemAssign(8, 0)
-        ]),
+        ])
+        .unwrap(),
         165
     );
 }
 
 #[test]
 fn test_exo2() {
-    let mut floating_1 = [false; 36];
-    floating_1[0] = true;
-    floating_1[5] = true;
-
-    let mut floating_2 = [false; 36];
-    floating_2[0] = true;
-    floating_2[1] = true;
-    floating_2[3] = true;
     assert_eq!(
-        exercise_2(&[
-            Instruction::MaskAssign(Mask {
-                mask0: 0,
-                mask1: 0b10010,
-                floating: floating_1,
-            }),
+        exercise_2::<Int>(&[
+            Instruction::MaskAssign(mask("000000000000000000000000000000X1001X")),
             Instruction::MemAssign(42, 100),
-            Instruction::MaskAssign(Mask {
-                mask0: 0,
-                mask1: 0,
-                floating: floating_2,
-            }),
+            Instruction::MaskAssign(mask("00000000000000000000000000000000X0XX")),
             Instruction::MemAssign(26, 1),
-        ]),
+        ])
+        .unwrap(),
         208
     );
 }
+
+#[test]
+fn test_checked() {
+    let instructions = [
+        Instruction::MaskAssign(mask("XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX")),
+        Instruction::MemAssign(1, 200),
+        Instruction::MemAssign(2, 50),
+    ];
+    assert_eq!(
+        exercise_1::<Checked<u8>>(&instructions).unwrap(),
+        Checked(250)
+    );
+    assert!(exercise_1::<Checked<u8>>(&[Instruction::MemAssign(1, 256)]).is_err());
+}
+
+#[test]
+#[should_panic(expected = "overflows u8")]
+fn test_checked_overflow() {
+    exercise_2::<Checked<u8>>(&[
+        Instruction::MemAssign(1, 250),
+        Instruction::MemAssign(2, 100),
+    ])
+    .unwrap();
+}
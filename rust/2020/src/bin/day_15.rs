@@ -1,46 +1,60 @@
 use std::collections::HashMap;
 use std::io::BufRead;
 
-fn exercise_1(numbers: &[i32], stopping_time: i32) -> i32 {
-    let mut current_time = 1;
-    let mut last_time_seen: HashMap<i32, i32> = HashMap::new();
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::integer::{run_checked, Integer, Solver};
+
+type Int = i32;
+
+fn exercise_1<I: Integer>(numbers: &[I], stopping_time: I) -> I {
+    let mut current_time = I::one();
+    let mut last_time_seen: HashMap<I, I> = HashMap::new();
 
     for n in &numbers[0..(numbers.len() - 1)] {
-        last_time_seen.insert(*n, current_time);
-        current_time += 1;
+        last_time_seen.insert(n.clone(), current_time.clone());
+        current_time += I::one();
     }
 
     // number said at time current_time-1, NOT already inserted
-    let mut previous_number = numbers[numbers.len() - 1];
+    let mut previous_number = numbers[numbers.len() - 1].clone();
 
     while current_time < stopping_time {
         // At this point, previous_number is not in last_time_seen for the last time
-        current_time += 1;
-        let last_time = *last_time_seen
-            .get(&previous_number)
-            .unwrap_or(&(current_time - 1));
-
-        last_time_seen.insert(previous_number, current_time - 1);
+        let said_at = current_time.clone();
+        current_time += I::one();
+        let last_time = last_time_seen
+            .insert(previous_number, said_at.clone())
+            .unwrap_or_else(|| said_at.clone());
 
-        previous_number = current_time - 1 - last_time;
+        previous_number = said_at - last_time;
     }
-    dbg!(current_time);
-    dbg!(previous_number);
     previous_number
 }
 
 fn main() {
-    let numbers = std::io::stdin()
-        .lock()
-        .lines()
-        .next()
-        .unwrap()
-        .unwrap()
-        .split(',')
-        .map(|x| x.parse::<i32>().unwrap())
-        .collect::<Vec<_>>();
-    println!("{}", exercise_1(&numbers, 2020));
-    println!("{}", exercise_1(&numbers, 30000000));
+    run_checked::<Int, _>(Solve)
+}
+
+struct Solve;
+
+impl Solver for Solve {
+    type Output = ();
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let numbers = std::io::stdin()
+            .lock()
+            .lines()
+            .next()
+            .unwrap()
+            .unwrap()
+            .split(',')
+            .map(|x| x.parse::<I>().unwrap())
+            .collect::<Vec<_>>();
+        let time = |t| I::from_u64(t).unwrap();
+        println!("{}", exercise_1(&numbers, time(2020)));
+        println!("{}", exercise_1(&numbers, time(30000000)));
+    }
 }
 
 #[test]
@@ -54,3 +68,11 @@ fn test_exo_1() {
     assert_eq!(exercise_1(&[3, 2, 1], 2020), 438);
     assert_eq!(exercise_1(&[3, 1, 2], 2020), 1836);
 }
+
+#[test]
+fn test_checked() {
+    assert_eq!(
+        exercise_1(&[0u16, 3, 6].map(Checked), Checked(2020)),
+        Checked(436)
+    );
+}
@@ -1,15 +1,23 @@
-#![feature(iter_map_while)]
-#![feature(map_first_last)]
-use std::{collections::BTreeSet, collections::HashMap, io::BufRead, ops::RangeInclusive};
+use std::{fmt::Write, ops::RangeInclusive};
+
+use advent_of_code_2020::matching::{
+    all_perfect_matchings, perfect_matching, MatchingError, Matchings,
+};
+use advent_of_code_common::{
+    input::{Input, Section},
+    interner::{Id, Interner},
+    range_set::RangeSet,
+};
+use itertools::Itertools;
 
 type Int = u64;
 
 #[derive(Debug, PartialEq)]
-pub struct Field(String, RangeInclusive<Int>, RangeInclusive<Int>);
+pub struct Field(Id, RangeSet<Int>);
 
 impl Field {
     fn contains(&self, x: &Int) -> bool {
-        self.1.contains(&x) || self.2.contains(&x)
+        self.1.contains(x)
     }
 }
 
@@ -18,6 +26,8 @@ pub struct Ticket(Vec<Int>);
 
 #[derive(Debug, PartialEq)]
 struct ProblemData {
+    // Field i is named by Id(i)
+    names: Interner,
     fields: Vec<Field>,
     my_ticket: Ticket,
     all_tickets: Vec<Ticket>,
@@ -26,27 +36,38 @@ struct ProblemData {
 }
 
 impl ProblemData {
-    fn from_strings(lines: &[String]) -> anyhow::Result<Self> {
+    fn from_input(input: &Input) -> anyhow::Result<Self> {
+        let rules = match input.sections_by_header().first() {
+            Some(Section {
+                header: None,
+                lines,
+            }) => lines
+                .iter()
+                .map(|line| ticket_parser::rule_field(line))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => anyhow::bail!("the input should start with the field rules"),
+        };
+        let mut names = Interner::new();
         let mut fields = vec![];
-        let mut i = 0;
-        while let Ok(field) = ticket_parser::rule_field(&lines[i]) {
-            fields.push(field);
-            i += 1;
-        }
-        i += 2;
-        let my_ticket = ticket_parser::ticket(&lines[i])?;
-        i += 3;
-        let mut nearby_tickets = vec![];
-
-        while i < lines.len() {
-            if let Ok(ticket) = ticket_parser::ticket(&lines[i]) {
-                nearby_tickets.push(ticket);
-            } else {
-                break;
+        for (name, ranges) in rules {
+            let id = names.intern(name);
+            if id.index() != fields.len() {
+                anyhow::bail!("the field \"{}\" has two rules", name);
             }
-            i += 1;
+            fields.push(Field(id, ranges));
         }
+        let my_ticket = match input.section("your ticket").as_deref() {
+            Some([line]) => ticket_parser::ticket(line)?,
+            _ => anyhow::bail!("expected a single line under \"your ticket:\""),
+        };
+        let nearby_tickets = input
+            .section("nearby tickets")
+            .ok_or_else(|| anyhow::anyhow!("missing \"nearby tickets:\""))?
+            .iter()
+            .map(|line| ticket_parser::ticket(line))
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(ProblemData {
+            names,
             number_fields: fields.len(),
             fields,
             my_ticket,
@@ -54,37 +75,26 @@ impl ProblemData {
         })
     }
 
-    fn error_rate_ticket(&self, ticket: &Ticket) -> Int {
-        let mut error_rate = 0;
-
-        for value in &ticket.0 {
-            if self
-                .fields
-                .iter()
-                .filter(|field| field.contains(value))
-                .count()
-                == 0
-            {
-                error_rate += value;
-            }
-        }
-        error_rate
+    // Values allowed by at least one field
+    fn valid_values(&self) -> RangeSet<Int> {
+        self.fields
+            .iter()
+            .fold(RangeSet::new(), |valid, field| valid.union(&field.1))
     }
 
     fn scanning_error_rate(&self) -> Int {
+        let valid = self.valid_values();
         self.all_tickets
             .iter()
-            .map(|ticket| self.error_rate_ticket(ticket))
+            .flat_map(|ticket| invalid_values(&valid, ticket))
             .sum()
     }
 
     fn remove_error_tickets(&mut self) {
-        self.all_tickets = self
-            .all_tickets
-            .iter()
-            .filter(|ticket| self.error_rate_ticket(ticket) == 0)
-            .cloned()
-            .collect();
+        // A 0 value can be invalid without adding to the error rate
+        let valid = self.valid_values();
+        self.all_tickets
+            .retain(|ticket| invalid_values(&valid, ticket).next().is_none());
     }
 
     fn id_possible_for_field(&self, field: &Field, id: usize) -> bool {
@@ -95,102 +105,291 @@ impl ProblemData {
             == 0
     }
 
-    fn determine_fields_values(self) -> HashMap<String, Int> {
-        // Vec<field_id> -> <my_field_ticket>
-        let mut possible_fields = Vec::new();
+    // field_id -> ticket ids it could be
+    fn candidates(&self) -> Vec<Vec<usize>> {
+        self.fields
+            .iter()
+            .map(|field| {
+                (0..self.number_fields)
+                    .filter(|id| self.id_possible_for_field(field, *id))
+                    .collect()
+            })
+            .collect()
+    }
 
-        for field_id in 0..self.number_fields {
-            possible_fields.push(Vec::new());
-            for (i, field) in self.fields.iter().enumerate() {
-                if self.id_possible_for_field(field, field_id) {
-                    possible_fields[field_id].push(i);
-                }
+    // Value of my ticket for each field id
+    fn determine_fields_values(self) -> anyhow::Result<Vec<Int>> {
+        let candidates = self.candidates();
+
+        let matching =
+            perfect_matching(&candidates, self.number_fields).map_err(|err| match err {
+                MatchingError::NoPerfectMatching { unmatched } => anyhow::anyhow!(
+                    "no consistent assignment of the ticket fields, cannot place: {}",
+                    unmatched
+                        .iter()
+                        .map(|field_id| self.name(*field_id))
+                        .join(", ")
+                ),
+                err => err.into(),
+            })?;
+        if !matching.unique {
+            anyhow::bail!("several assignments of the ticket fields are consistent");
+        }
+
+        Ok(matching
+            .left_to_right
+            .into_iter()
+            .map(|ticket_id| self.my_ticket.0[ticket_id])
+            .collect())
+    }
+
+    fn name(&self, field_id: usize) -> &str {
+        self.names.name(self.fields[field_id].0)
+    }
+
+    fn describe_assignments(&self, report: &mut String, assignments: &Matchings) {
+        match assignments.count {
+            0 => writeln!(report, "No consistent assignment").unwrap(),
+            1 => writeln!(report, "Unique assignment, not found by deduction:").unwrap(),
+            n if (assignments.listed.len() as u64) < n => writeln!(
+                report,
+                "Under-constrained, {} consistent assignments, the first {}:",
+                n,
+                assignments.listed.len()
+            )
+            .unwrap(),
+            n => writeln!(report, "Under-constrained, {} consistent assignments:", n).unwrap(),
+        }
+        for assignment in &assignments.listed {
+            writeln!(
+                report,
+                "  {}",
+                assignment
+                    .iter()
+                    .enumerate()
+                    .map(|(field_id, column)| format!("{}={}", self.name(field_id), column))
+                    .join(", ")
+            )
+            .unwrap();
+        }
+    }
+
+    /// Lists every consistent assignment the deduction misses, or the first `max_listed`
+    fn explain(mut self, max_listed: Option<usize>) -> String {
+        let mut report = String::new();
+
+        let valid = self.valid_values();
+        writeln!(report, "Valid values: {}", valid).unwrap();
+        writeln!(report, "Invalid nearby tickets:").unwrap();
+        for (n, ticket) in self.all_tickets.iter().enumerate() {
+            let invalid = invalid_values(&valid, ticket).collect_vec();
+            if !invalid.is_empty() {
+                writeln!(
+                    report,
+                    "  ticket {} ({}): {} fits no field",
+                    n,
+                    ticket.0.iter().join(","),
+                    invalid.iter().join(", ")
+                )
+                .unwrap();
             }
         }
+        self.remove_error_tickets();
 
-        let mut fixed_fields = BTreeSet::new();
+        let candidates = self.candidates();
+        let name_width = self
+            .names
+            .iter()
+            .map(|(_, name)| name.len())
+            .max()
+            .unwrap_or(0);
+        let column_width = self.number_fields.to_string().len();
+        writeln!(report, "Candidates (field x column):").unwrap();
+        writeln!(
+            report,
+            "  {:name_width$} {}",
+            "",
+            (0..self.number_fields)
+                .map(|id| format!("{:>column_width$}", id, column_width = column_width))
+                .join(" "),
+            name_width = name_width
+        )
+        .unwrap();
+        for (field, possible) in self.fields.iter().zip(&candidates) {
+            writeln!(
+                report,
+                "  {:name_width$} {}",
+                self.names.name(field.0),
+                (0..self.number_fields)
+                    .map(|id| {
+                        let cell = if possible.contains(&id) { "X" } else { "." };
+                        format!("{:>column_width$}", cell, column_width = column_width)
+                    })
+                    .join(" "),
+                name_width = name_width
+            )
+            .unwrap();
+        }
 
-        let mut modification = true;
+        let pins = pin_fields(&candidates, self.number_fields);
+        writeln!(report, "Deduction:").unwrap();
+        for pin in &pins {
+            let reason = match pin.reason {
+                PinReason::OnlyColumnForField => "the only column left for this field",
+                PinReason::OnlyFieldForColumn => "the only field left for this column",
+            };
+            writeln!(
+                report,
+                "  {} -> column {}: {}",
+                self.name(pin.field_id),
+                pin.column,
+                reason
+            )
+            .unwrap();
+        }
 
-        let mut association = HashMap::new();
+        if pins.len() == self.number_fields {
+            writeln!(report, "Unique assignment").unwrap();
+            return report;
+        }
 
-        while modification {
-            // println!("{:?}", possible_fields);
+        let assignments = all_perfect_matchings(&candidates, self.number_fields, max_listed);
+        self.describe_assignments(&mut report, &assignments);
+        report
+    }
+}
 
-            modification = false;
-            for (field_id, ticket_field_id) in possible_fields
-                .iter()
-                .enumerate()
-                .filter(|(_, possible_ids)| possible_ids.len() == 1)
-                .map(|x| (x.0, x.1[0]))
-            {
-                // println!("Fixed {}-{}", ticket_field_id, field_id);
-                fixed_fields.insert(ticket_field_id);
-                association.insert(ticket_field_id, field_id);
-            }
-            for i in 0..possible_fields.len() {
-                let new_possible: Vec<usize> = possible_fields[i]
-                    .iter()
-                    .cloned()
-                    .filter(|x| !fixed_fields.contains(x))
-                    .collect();
-                if new_possible.len() != possible_fields[i].len() {
-                    modification = true;
-                    possible_fields[i] = new_possible;
+fn invalid_values<'a>(
+    valid: &'a RangeSet<Int>,
+    ticket: &'a Ticket,
+) -> impl Iterator<Item = Int> + 'a {
+    ticket
+        .0
+        .iter()
+        .filter(move |value| !valid.contains(value))
+        .cloned()
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum PinReason {
+    OnlyColumnForField,
+    OnlyFieldForColumn,
+}
+
+#[derive(Debug, PartialEq)]
+struct Pin {
+    field_id: usize,
+    column: usize,
+    reason: PinReason,
+}
+
+// Fixes the fields one by one, as long as a field or a column has a single possibility left
+fn pin_fields(candidates: &[Vec<usize>], number_columns: usize) -> Vec<Pin> {
+    let mut remaining = candidates.to_vec();
+    let mut pinned_fields = vec![false; candidates.len()];
+    let mut pinned_columns = vec![false; number_columns];
+    let mut pins = vec![];
+
+    loop {
+        let pin = (0..candidates.len())
+            .find(|field_id| !pinned_fields[*field_id] && remaining[*field_id].len() == 1)
+            .map(|field_id| Pin {
+                field_id,
+                column: remaining[field_id][0],
+                reason: PinReason::OnlyColumnForField,
+            })
+            .or_else(|| {
+                (0..number_columns)
+                    .filter(|column| !pinned_columns[*column])
+                    .find_map(|column| {
+                        let fields = (0..candidates.len())
+                            .filter(|field_id| {
+                                !pinned_fields[*field_id] && remaining[*field_id].contains(&column)
+                            })
+                            .collect_vec();
+                        if fields.len() == 1 {
+                            Some(Pin {
+                                field_id: fields[0],
+                                column,
+                                reason: PinReason::OnlyFieldForColumn,
+                            })
+                        } else {
+                            None
+                        }
+                    })
+            });
+
+        match pin {
+            Some(pin) => {
+                pinned_fields[pin.field_id] = true;
+                pinned_columns[pin.column] = true;
+                for possible in remaining.iter_mut() {
+                    possible.retain(|column| *column != pin.column);
                 }
+                pins.push(pin);
             }
+            None => return pins,
         }
-
-        // println!("{:?}", association);
-        let mut final_repartition = HashMap::new();
-        for (field_id, associated_id) in association {
-            final_repartition.insert(
-                self.fields[field_id].0.clone(),
-                self.my_ticket.0[associated_id],
-            );
-        }
-        // final_repartition.insert(, v)
-        final_repartition
     }
 }
 
-peg::parser! {
+advent_of_code_common::parser! {
     grammar ticket_parser() for str {
         rule number() -> Int
-            = n:$(['0'..='9']+) { n.parse().unwrap() }
+            = u64()
         rule range() -> RangeInclusive<Int>
             = a:number() "-" b:number() {a..=b}
-        pub rule rule_field() -> Field
-            = name:$(['a'..='z' | ' ']+)": " r1:range() " or " r2:range() {Field(name.into(), r1, r2)}
+        rule ranges() -> RangeSet<Int>
+            = r1:range() " or " r2:range() {vec![r1, r2].into_iter().collect()}
+        pub rule rule_field() -> (&'input str, RangeSet<Int>)
+            = key_value(<$(['a'..='z' | ' ']+)>, <ranges()>)
         pub rule ticket() -> Ticket
-            = l:(number()**",") {Ticket(l)}
+            = l:comma_list(<number()>) {Ticket(l)}
+    }
+}
+
+fn option_value(args: &[String], name: &str) -> anyhow::Result<Option<usize>> {
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(None),
+        Some(position) => match args.get(position + 1).map(|value| value.parse()) {
+            Some(Ok(value)) => Ok(Some(value)),
+            _ => anyhow::bail!("{} expects a number", name),
+        },
     }
 }
 
 fn main() -> anyhow::Result<()> {
-    let lines = std::io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .collect::<Vec<_>>();
-    let mut problem_data = ProblemData::from_strings(&lines)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let max_listed = option_value(&args, "--max-assignments")?;
+    let mut problem_data = ProblemData::from_input(&Input::from_stdin()?)?;
+    if args.iter().any(|arg| arg == "--explain") {
+        print!("{}", problem_data.explain(max_listed));
+        return Ok(());
+    }
     println!("{}", problem_data.scanning_error_rate());
     problem_data.remove_error_tickets();
-    let fields = problem_data.determine_fields_values();
-    let score: Int = fields
+    let departures = problem_data
+        .names
         .iter()
-        .filter(|(name, _)| name.starts_with("departure"))
-        .map(|x| x.1)
-        .product();
+        .filter(|(_, name)| name.starts_with("departure"))
+        .map(|(id, _)| id)
+        .collect_vec();
+    let values = problem_data.determine_fields_values()?;
+    let score: Int = departures.iter().map(|id| values[id.index()]).product();
     println!("{}", score);
     Ok(())
 }
 
+#[cfg(test)]
+fn ranges(ranges: &[RangeInclusive<Int>]) -> RangeSet<Int> {
+    ranges.iter().cloned().collect()
+}
+
 #[test]
 fn test_parser() {
     assert_eq!(
         ticket_parser::rule_field("departure location: 30-828 or 839-971").unwrap(),
-        Field("departure location".into(), 30..=828, 839..=971)
+        ("departure location", ranges(&[30..=828, 839..=971]))
     );
     assert_eq!(
         ticket_parser::ticket("7,3,47").unwrap(),
@@ -199,13 +398,16 @@ fn test_parser() {
 }
 #[test]
 fn test_parser_problem_data() -> anyhow::Result<()> {
+    let mut names = Interner::new();
+    let ids = ["class", "row", "seat"].map(|name| names.intern(name));
     assert_eq!(
         ProblemData {
+            names,
             my_ticket: Ticket(vec![7, 1, 14]),
             fields: vec![
-                Field("class".into(), 1..=3, 5..=7),
-                Field("row".into(), 6..=11, 33..=44),
-                Field("seat".into(), 13..=40, 45..=50),
+                Field(ids[0], ranges(&[1..=3, 5..=7])),
+                Field(ids[1], ranges(&[6..=11, 33..=44])),
+                Field(ids[2], ranges(&[13..=40, 45..=50])),
             ],
             all_tickets: vec![
                 Ticket(vec![7, 3, 47]),
@@ -215,69 +417,72 @@ fn test_parser_problem_data() -> anyhow::Result<()> {
             ],
             number_fields: 3
         },
-        ProblemData::from_strings(&[
-            "class: 1-3 or 5-7".into(),
-            "row: 6-11 or 33-44".into(),
-            "seat: 13-40 or 45-50".into(),
-            "".into(),
-            "your ticket:".into(),
-            "7,1,14".into(),
-            "".into(),
-            "nearby tickets:".into(),
-            "7,3,47".into(),
-            "40,4,50".into(),
-            "55,2,20".into(),
-            "38,6,12".into(),
-        ])?
+        ProblemData::from_input(&Input::from_lines(&[
+            "class: 1-3 or 5-7",
+            "row: 6-11 or 33-44",
+            "seat: 13-40 or 45-50",
+            "",
+            "your ticket:",
+            "7,1,14",
+            "",
+            "nearby tickets:",
+            "7,3,47",
+            "40,4,50",
+            "55,2,20",
+            "38,6,12",
+        ]))?
     );
     Ok(())
 }
 
 #[test]
 fn test_exo1() -> anyhow::Result<()> {
-    let data = ProblemData::from_strings(&[
-        "class: 1-3 or 5-7".into(),
-        "row: 6-11 or 33-44".into(),
-        "seat: 13-40 or 45-50".into(),
-        "".into(),
-        "your ticket:".into(),
-        "7,1,14".into(),
-        "".into(),
-        "nearby tickets:".into(),
-        "7,3,47".into(),
-        "40,4,50".into(),
-        "55,2,20".into(),
-        "38,6,12".into(),
-    ])?;
+    let data = ProblemData::from_input(&Input::from_lines(&[
+        "class: 1-3 or 5-7",
+        "row: 6-11 or 33-44",
+        "seat: 13-40 or 45-50",
+        "",
+        "your ticket:",
+        "7,1,14",
+        "",
+        "nearby tickets:",
+        "7,3,47",
+        "40,4,50",
+        "55,2,20",
+        "38,6,12",
+    ]))?;
 
     assert_eq!(data.scanning_error_rate(), 71);
+    assert_eq!(data.valid_values(), ranges(&[1..=3, 5..=11, 13..=50]));
 
     Ok(())
 }
 #[test]
 fn test_determine_fields_possible() -> anyhow::Result<()> {
-    let mut data = ProblemData::from_strings(&[
-        "class: 0-1 or 4-19".into(),
-        "row: 0-5 or 8-19".into(),
-        "seat: 0-13 or 16-19".into(),
-        "".into(),
-        "your ticket:".into(),
-        "11,12,13".into(),
-        "".into(),
-        "nearby tickets:".into(),
-        "3,9,18".into(),
-        "15,1,5".into(),
-        "5,14,9".into(),
-    ])?;
+    let mut data = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-19",
+        "row: 0-5 or 8-19",
+        "seat: 0-13 or 16-19",
+        "",
+        "your ticket:",
+        "11,12,13",
+        "",
+        "nearby tickets:",
+        "3,9,18",
+        "15,1,5",
+        "5,14,9",
+    ]))?;
 
     data.remove_error_tickets();
     dbg!(&data);
-    let fields = data.determine_fields_values();
+    let id = |name| data.names.get(name).unwrap().index();
+    let (class, row, seat) = (id("class"), id("row"), id("seat"));
+    let fields = data.determine_fields_values()?;
     dbg!(&fields);
 
-    let class = &fields[&"class".to_string()];
-    let row = &fields[&"row".to_string()];
-    let seat = &fields[&"seat".to_string()];
+    let class = &fields[class];
+    let row = &fields[row];
+    let seat = &fields[seat];
 
     let mut results = [*class, *row, *seat];
     results.sort_unstable();
@@ -292,26 +497,149 @@ fn test_determine_fields_possible() -> anyhow::Result<()> {
 }
 #[test]
 fn test_determine_fields() -> anyhow::Result<()> {
-    let mut data = ProblemData::from_strings(&[
-        "class: 0-1 or 4-19".into(),
-        "row: 0-5 or 8-19".into(),
-        "seat: 0-13 or 16-19".into(),
-        "".into(),
-        "your ticket:".into(),
-        "11,12,13".into(),
-        "".into(),
-        "nearby tickets:".into(),
-        "3,9,18".into(),
-        "15,1,5".into(),
-        "5,14,9".into(),
-    ])?;
+    let mut data = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-19",
+        "row: 0-5 or 8-19",
+        "seat: 0-13 or 16-19",
+        "",
+        "your ticket:",
+        "11,12,13",
+        "",
+        "nearby tickets:",
+        "3,9,18",
+        "15,1,5",
+        "5,14,9",
+    ]))?;
+
+    data.remove_error_tickets();
+    let names = data.names.clone();
+    let fields = data.determine_fields_values()?;
+
+    assert_eq!(fields[names.get("class").unwrap().index()], 12);
+    assert_eq!(fields[names.get("row").unwrap().index()], 11);
+    assert_eq!(fields[names.get("seat").unwrap().index()], 13);
+
+    Ok(())
+}
+
+#[test]
+fn test_determine_fields_inconsistent() -> anyhow::Result<()> {
+    let mut data = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-5",
+        "row: 0-1 or 4-5",
+        "seat: 0-13 or 16-19",
+        "",
+        "your ticket:",
+        "1,12,13",
+        "",
+        "nearby tickets:",
+        "4,9,18",
+        "5,14,9",
+    ]))?;
 
     data.remove_error_tickets();
-    let fields = data.determine_fields_values();
+    let error = data.determine_fields_values().unwrap_err();
+    assert!(error.to_string().contains("row"));
 
-    assert_eq!(fields.get("class"), Some(&12));
-    assert_eq!(fields.get("row"), Some(&11));
-    assert_eq!(fields.get("seat"), Some(&13));
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> anyhow::Result<()> {
+    let report = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-19",
+        "row: 0-5 or 8-19",
+        "seat: 0-13 or 16-19",
+        "",
+        "your ticket:",
+        "11,12,13",
+        "",
+        "nearby tickets:",
+        "3,9,18",
+        "15,1,5",
+        "5,14,9",
+        "20,1,5",
+    ]))?
+    .explain(None);
+
+    assert!(report.contains("  ticket 3 (20,1,5): 20 fits no field\n"));
+    assert!(report.contains("  class . X X\n"));
+    assert!(report.contains("  row   X X X\n"));
+    assert!(report.contains("  seat  . . X\n"));
+    assert!(report.contains(
+        "  seat -> column 2: the only column left for this field\n  \
+         class -> column 1: the only column left for this field\n  \
+         row -> column 0: the only column left for this field\n"
+    ));
+    assert!(report.ends_with("Unique assignment\n"));
+    Ok(())
+}
+
+#[test]
+fn test_explain_under_constrained() -> anyhow::Result<()> {
+    let report = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-19",
+        "row: 0-1 or 4-19",
+        "",
+        "your ticket:",
+        "11,12",
+        "",
+        "nearby tickets:",
+        "5,9",
+    ]))?
+    .explain(None);
+
+    assert!(report.ends_with(
+        "Under-constrained, 2 consistent assignments:\n  class=0, row=1\n  class=1, row=0\n"
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_field() {
+    let error = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-19",
+        "class: 0-5 or 8-19",
+        "",
+        "your ticket:",
+        "11,12",
+        "",
+        "nearby tickets:",
+        "3,9",
+    ]))
+    .unwrap_err();
+    assert!(error.to_string().contains("\"class\" has two rules"));
+}
+
+#[test]
+fn test_explain_assignments() -> anyhow::Result<()> {
+    let data = ProblemData::from_input(&Input::from_lines(&[
+        "class: 0-1 or 4-19",
+        "row: 0-1 or 4-19",
+        "",
+        "your ticket:",
+        "11,12",
+        "",
+        "nearby tickets:",
+        "5,9",
+    ]))?;
+
+    let mut report = String::new();
+    data.describe_assignments(
+        &mut report,
+        &Matchings {
+            listed: vec![vec![1, 0]],
+            count: 1,
+        },
+    );
+    assert_eq!(
+        report,
+        "Unique assignment, not found by deduction:\n  class=1, row=0\n"
+    );
 
+    let report = data.explain(Some(1));
+    assert!(report.ends_with(
+        "Under-constrained, 2 consistent assignments, the first 1:\n  class=0, row=1\n"
+    ));
     Ok(())
 }
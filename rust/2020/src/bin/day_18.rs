@@ -1,55 +1,55 @@
-#![feature(box_syntax)]
-
 use std::io::BufRead;
 
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::integer::{run_checked, Integer, Solver};
 use itertools::Itertools;
 
 type Int = u64;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Operation {
-    Number(Int),
+    Number(u64),
     Addition(Box<Operation>, Box<Operation>),
     Multiplication(Box<Operation>, Box<Operation>),
 }
 
 impl Operation {
-    fn evaluate(&self) -> Int {
+    fn evaluate<I: Integer>(&self) -> I {
         use Operation::*;
         match self {
-            Number(x) => *x,
-            Addition(x, y) => x.evaluate() + y.evaluate(),
-            Multiplication(x, y) => x.evaluate() * y.evaluate(),
+            Number(x) => I::from_u64(*x).expect("the literals fit in the integer type"),
+            Addition(x, y) => x.evaluate::<I>() + y.evaluate(),
+            Multiplication(x, y) => x.evaluate::<I>() * y.evaluate(),
         }
     }
 }
 
-peg::parser! {
+advent_of_code_common::parser! {
 grammar operation_parser_pt1() for str {
     rule number() -> Operation
-        = n:$(['0'..='9']+) { Operation::Number(n.parse().unwrap()) }
+        = n:u64() { Operation::Number(n) }
 
     pub rule operation() -> Operation =
         precedence!{
-            x:(@) " + " y:@ { Operation::Addition(box x, box y) }
-            x:(@) " * " y:@ { Operation::Multiplication(box x, box y) }
+            x:(@) " + " y:@ { Operation::Addition(Box::new(x), Box::new(y)) }
+            x:(@) " * " y:@ { Operation::Multiplication(Box::new(x), Box::new(y)) }
             n:number() { n }
             --
             "(" e:operation() ")" { e }
         }
     }
 }
-peg::parser! {
-
+advent_of_code_common::parser! {
     grammar operation_parser_pt2() for str {
         rule number() -> Operation
-            = n:$(['0'..='9']+) { Operation::Number(n.parse().unwrap()) }
+            = n:u64() { Operation::Number(n) }
 
         pub rule operation() -> Operation =
             precedence!{
-                x:(@) " * " y:@ { Operation::Multiplication(box x, box y) }
+                x:(@) " * " y:@ { Operation::Multiplication(Box::new(x), Box::new(y)) }
                 --
-                x:(@) " + " y:@ { Operation::Addition(box x, box y) }
+                x:(@) " + " y:@ { Operation::Addition(Box::new(x), Box::new(y)) }
                 n:number() { n }
                 --
                 "(" e:operation() ")" { e }
@@ -58,27 +58,35 @@ peg::parser! {
 }
 
 fn main() {
-    let lines = std::io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .collect_vec();
-
-    let pt1 = lines
-        .iter()
-        .map(|x| operation_parser_pt1::operation(&x))
-        .map(Result::unwrap)
-        .collect_vec();
-    let pt2 = lines
-        .iter()
-        .map(|x| operation_parser_pt2::operation(&x))
-        .map(Result::unwrap)
-        .collect_vec();
-
-    let evaluations_pt1 = pt1.iter().map(|x| x.evaluate()).collect_vec();
-    println!("{}", evaluations_pt1.iter().sum::<Int>());
-    let evaluations_pt2 = pt2.iter().map(|x| x.evaluate()).collect_vec();
-    println!("{}", evaluations_pt2.iter().sum::<Int>());
+    run_checked::<Int, _>(Solve)
+}
+
+struct Solve;
+
+impl Solver for Solve {
+    type Output = ();
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let lines = std::io::stdin()
+            .lock()
+            .lines()
+            .map(Result::unwrap)
+            .collect_vec();
+
+        let pt1 = lines
+            .iter()
+            .map(|x| operation_parser_pt1::operation(x))
+            .map(Result::unwrap)
+            .collect_vec();
+        let pt2 = lines
+            .iter()
+            .map(|x| operation_parser_pt2::operation(x))
+            .map(Result::unwrap)
+            .collect_vec();
+
+        println!("{}", pt1.iter().map(Operation::evaluate::<I>).sum::<I>());
+        println!("{}", pt2.iter().map(Operation::evaluate::<I>).sum::<I>());
+    }
 }
 
 #[test]
@@ -86,25 +94,34 @@ fn test_parsing_pt1() -> anyhow::Result<()> {
     use Operation::*;
     assert_eq!(
         operation_parser_pt1::operation("3 + 4")?,
-        Addition(box Number(3), box Number(4))
+        Addition(Box::new(Number(3)), Box::new(Number(4)))
     );
     assert_eq!(
         operation_parser_pt1::operation("1 + 2 + 3")?,
-        Addition(box Addition(box Number(1), box Number(2)), box Number(3))
+        Addition(
+            Box::new(Addition(Box::new(Number(1)), Box::new(Number(2)))),
+            Box::new(Number(3))
+        )
     );
     assert_eq!(
         operation_parser_pt1::operation("1 + (2 + 3)")?,
-        Addition(box Number(1), box Addition(box Number(2), box Number(3)))
+        Addition(
+            Box::new(Number(1)),
+            Box::new(Addition(Box::new(Number(2)), Box::new(Number(3))))
+        )
     );
     assert_eq!(
         operation_parser_pt1::operation("1 + 2 * 3")?,
-        Multiplication(box Addition(box Number(1), box Number(2)), box Number(3))
+        Multiplication(
+            Box::new(Addition(Box::new(Number(1)), Box::new(Number(2)))),
+            Box::new(Number(3))
+        )
     );
     assert_eq!(
         operation_parser_pt1::operation("1 * 2 + 3")?,
         Addition(
-            box Multiplication(box Number(1), box Number(2)),
-            box Number(3)
+            Box::new(Multiplication(Box::new(Number(1)), Box::new(Number(2)))),
+            Box::new(Number(3))
         )
     );
     Ok(())
@@ -113,20 +130,21 @@ fn test_parsing_pt1() -> anyhow::Result<()> {
 #[test]
 fn test_evaluate_pt1() -> anyhow::Result<()> {
     assert_eq!(
-        operation_parser_pt1::operation("2 * 3 + (4 * 5)")?.evaluate(),
+        operation_parser_pt1::operation("2 * 3 + (4 * 5)")?.evaluate::<Int>(),
         26
     );
     assert_eq!(
-        operation_parser_pt1::operation("5 + (8 * 3 + 9 + 3 * 4 * 3)")?.evaluate(),
+        operation_parser_pt1::operation("5 + (8 * 3 + 9 + 3 * 4 * 3)")?.evaluate::<Int>(),
         437
     );
     assert_eq!(
-        operation_parser_pt1::operation("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))")?.evaluate(),
+        operation_parser_pt1::operation("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))")?
+            .evaluate::<Int>(),
         12240
     );
     assert_eq!(
         operation_parser_pt1::operation("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2")?
-            .evaluate(),
+            .evaluate::<Int>(),
         13632
     );
     Ok(())
@@ -137,23 +155,35 @@ fn test_parsing_pt2() -> anyhow::Result<()> {
     use Operation::*;
     assert_eq!(
         operation_parser_pt2::operation("3 + 4")?,
-        Addition(box Number(3), box Number(4))
+        Addition(Box::new(Number(3)), Box::new(Number(4)))
     );
     assert_eq!(
         operation_parser_pt2::operation("1 + 2 + 3")?,
-        Addition(box Addition(box Number(1), box Number(2)), box Number(3))
+        Addition(
+            Box::new(Addition(Box::new(Number(1)), Box::new(Number(2)))),
+            Box::new(Number(3))
+        )
     );
     assert_eq!(
         operation_parser_pt2::operation("1 + (2 + 3)")?,
-        Addition(box Number(1), box Addition(box Number(2), box Number(3)))
+        Addition(
+            Box::new(Number(1)),
+            Box::new(Addition(Box::new(Number(2)), Box::new(Number(3))))
+        )
     );
     assert_eq!(
         operation_parser_pt2::operation("1 + 2 * 3")?,
-        Multiplication(box Addition(box Number(1), box Number(2)), box Number(3))
+        Multiplication(
+            Box::new(Addition(Box::new(Number(1)), Box::new(Number(2)))),
+            Box::new(Number(3))
+        )
     );
     assert_eq!(
         operation_parser_pt2::operation("1 * 2 + 3")?,
-        Multiplication(box Number(1), box Addition(box Number(2), box Number(3)),)
+        Multiplication(
+            Box::new(Number(1)),
+            Box::new(Addition(Box::new(Number(2)), Box::new(Number(3)))),
+        )
     );
     Ok(())
 }
@@ -161,25 +191,34 @@ fn test_parsing_pt2() -> anyhow::Result<()> {
 #[test]
 fn test_evaluate_pt2() -> anyhow::Result<()> {
     assert_eq!(
-        operation_parser_pt2::operation("1 + (2 * 3) + (4 * (5 + 6))")?.evaluate(),
+        operation_parser_pt2::operation("1 + (2 * 3) + (4 * (5 + 6))")?.evaluate::<Int>(),
         51
     );
     assert_eq!(
-        operation_parser_pt2::operation("2 * 3 + (4 * 5)")?.evaluate(),
+        operation_parser_pt2::operation("2 * 3 + (4 * 5)")?.evaluate::<Int>(),
         46
     );
     assert_eq!(
-        operation_parser_pt2::operation("5 + (8 * 3 + 9 + 3 * 4 * 3)")?.evaluate(),
+        operation_parser_pt2::operation("5 + (8 * 3 + 9 + 3 * 4 * 3)")?.evaluate::<Int>(),
         1445
     );
     assert_eq!(
-        operation_parser_pt2::operation("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))")?.evaluate(),
+        operation_parser_pt2::operation("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))")?
+            .evaluate::<Int>(),
         669060
     );
     assert_eq!(
         operation_parser_pt2::operation("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2")?
-            .evaluate(),
+            .evaluate::<Int>(),
         23340
     );
     Ok(())
 }
+
+#[test]
+#[should_panic(expected = "4294967295 * 4294967295 overflows u32")]
+fn test_checked() {
+    operation_parser_pt2::operation("4294967295 * 4294967295")
+        .unwrap()
+        .evaluate::<Checked<u32>>();
+}
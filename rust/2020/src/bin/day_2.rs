@@ -3,14 +3,10 @@ use std::io::BufRead;
 
 fn check_password_1(mini: i32, maxi: i32, letter: char, password: String) -> i32 {
     let count_letter = password.chars().filter(|x| &letter == x).count();
-    if count_letter >= mini as usize && count_letter <= maxi as usize {
-        return 1;
-    } else {
-        return 0;
-    }
+    (count_letter >= mini as usize && count_letter <= maxi as usize) as i32
 }
 
-pub fn exercise_1(lines: &Vec<String>) {
+pub fn exercise_1(lines: &[String]) -> i32 {
     let regex_input = regex::Regex::new(r"(\d+)-(\d+) (\w): (\w+)").unwrap();
     let mut counter = 0;
     for line in lines {
@@ -39,15 +35,14 @@ pub fn exercise_1(lines: &Vec<String>) {
         let password: String = matching.get(4).unwrap().as_str().to_string();
         counter += check_password_1(mini, maxi, letter, password);
     }
-    println!("{}", counter);
+    counter
 }
 fn check_password_2(i1: i32, i2: i32, letter: char, password: String) -> i32 {
     let letters: Vec<char> = password.chars().collect();
-    return ((letters[(i1 - 1) as usize] == letter) ^ (letters[(i2 - 1) as usize] == letter))
-        as i32;
+    ((letters[(i1 - 1) as usize] == letter) ^ (letters[(i2 - 1) as usize] == letter)) as i32
 }
 
-pub fn exercise_2(lines: &Vec<String>) {
+pub fn exercise_2(lines: &[String]) -> i32 {
     let regex_input = regex::Regex::new(r"(\d+)-(\d+) (\w): (\w+)").unwrap();
     let mut counter = 0;
     for line in lines {
@@ -76,18 +71,34 @@ pub fn exercise_2(lines: &Vec<String>) {
         let password: String = matching.get(4).unwrap().as_str().to_string();
         counter += check_password_2(mini, maxi, letter, password);
     }
-    println!("{}", counter);
+    counter
 }
 
 fn main() -> anyhow::Result<()> {
-    let lines: Vec<String> = io::stdin()
-        .lock()
-        .lines()
-        .map(|x| x.unwrap().into())
-        .collect();
+    let lines: Vec<String> = io::stdin().lock().lines().map(Result::unwrap).collect();
 
-    exercise_1(&lines);
-    exercise_2(&lines);
+    println!("{}", exercise_1(&lines));
+    println!("{}", exercise_2(&lines));
 
     Ok(())
 }
+
+#[test]
+fn test_exo_1() {
+    let lines = vec![
+        "1-3 a: abcde".to_string(),
+        "1-3 b: cdefg".to_string(),
+        "2-9 c: ccccccccc".to_string(),
+    ];
+    assert_eq!(exercise_1(&lines), 2);
+}
+
+#[test]
+fn test_exo_2() {
+    let lines = vec![
+        "1-3 a: abcde".to_string(),
+        "1-3 b: cdefg".to_string(),
+        "2-9 c: ccccccccc".to_string(),
+    ];
+    assert_eq!(exercise_2(&lines), 1);
+}
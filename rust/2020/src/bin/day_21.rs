@@ -1,203 +1,160 @@
-use std::{
-    collections::{HashMap, HashSet},
-    hash::Hash,
-    io::BufRead,
-};
+use std::io::BufRead;
 
+use advent_of_code_2020::matching::{perfect_matching, MatchingError};
+use advent_of_code_common::interner::{Id, IdSet, Interner};
 use itertools::Itertools;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Ingredient(String);
-
-impl std::fmt::Display for Ingredient {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Allergen(String);
-
 #[derive(Debug, PartialEq, Eq)]
 pub struct Food {
-    ingredients: HashSet<Ingredient>,
-
-    allergens: HashSet<Allergen>,
+    ingredients: IdSet,
+    allergens: IdSet,
 }
 
-fn set_minus<T: Hash + Eq>(set: HashSet<T>, minus: &HashSet<T>) -> HashSet<T> {
-    set.into_iter().filter(|x| !minus.contains(x)).collect()
-}
-fn set_intersect<T: Hash + Eq>(set: HashSet<T>, inter: &HashSet<T>) -> HashSet<T> {
-    set.into_iter().filter(|x| inter.contains(x)).collect()
-}
-
-peg::parser! {
+advent_of_code_common::parser! {
     grammar food_parser() for str {
-        rule allergen() -> Allergen
-            =  name:$(['a'..='z']+) {Allergen(name.to_string())}
-        rule ingredient() -> Ingredient
-            =  name:$(['a'..='z']+) {Ingredient(name.to_string())}
-
-        pub rule food() -> Food
-            = ingredients:(ingredient()**" ") " (contains " allergens:(allergen()**", ") ")"
-            {
-                Food{
-                    ingredients: ingredients.into_iter().collect(),
-                    allergens: allergens.into_iter().collect()
-                }
-            }
+        rule name() -> &'input str
+            = $(['a'..='z']+)
+
+        pub rule food() -> (Vec<&'input str>, Vec<&'input str>)
+            = ingredients:space_list(<name()>) " (contains " allergens:comma_list(<name()>) ")"
+            { (ingredients, allergens) }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct FoodList(Vec<Food>);
+#[derive(Debug)]
+struct FoodList {
+    ingredients: Interner,
+    allergens: Interner,
+    foods: Vec<Food>,
+}
 
 impl FoodList {
-    fn from_lines(lines: &[String]) -> Self {
-        FoodList(
-            lines
-                .iter()
-                .map(|x| food_parser::food(x))
-                .map(Result::unwrap)
-                .collect(),
-        )
-    }
-    fn all_ingredients(&self) -> HashSet<Ingredient> {
-        let mut ingredients = HashSet::new();
-        for food in &self.0 {
-            for ing in &food.ingredients {
-                ingredients.insert(ing.clone());
-            }
+    fn from_lines(lines: &[String]) -> anyhow::Result<Self> {
+        let mut ingredients = Interner::new();
+        let mut allergens = Interner::new();
+        let mut foods = vec![];
+        for line in lines {
+            let (ingredient_names, allergen_names) = food_parser::food(line)?;
+            foods.push(Food {
+                ingredients: ingredient_names
+                    .into_iter()
+                    .map(|name| ingredients.intern(name))
+                    .collect(),
+                allergens: allergen_names
+                    .into_iter()
+                    .map(|name| allergens.intern(name))
+                    .collect(),
+            });
         }
-        ingredients
-    }
-    fn all_allergens(&self) -> HashSet<Allergen> {
-        let mut allergens = HashSet::new();
-        for food in &self.0 {
-            for all in &food.allergens {
-                allergens.insert(all.clone());
-            }
-        }
-        allergens
+        Ok(FoodList {
+            ingredients,
+            allergens,
+            foods,
+        })
     }
 
-    fn all_possible_allergens(&self) -> HashMap<Ingredient, HashSet<Allergen>> {
-        let all_allergens = self.all_allergens();
-        let mut possible_allergens: HashMap<Ingredient, HashSet<Allergen>> = self
-            .all_ingredients()
-            .into_iter()
-            .map(|x| (x, all_allergens.clone()))
-            .collect();
-        for food in &self.0 {
-            possible_allergens = possible_allergens
-                .into_iter()
-                .map(|(ing, allergens)| {
-                    if food.ingredients.contains(&ing) {
-                        (ing, allergens)
-                    } else {
-                        (ing, set_minus(allergens, &food.allergens))
-                    }
-                })
-                .collect()
+    // allergen -> ingredients of every food which contains it
+    fn candidates(&self) -> Vec<IdSet> {
+        let mut candidates = vec![IdSet::full(self.ingredients.len()); self.allergens.len()];
+        for food in &self.foods {
+            for allergen in food.allergens.iter() {
+                candidates[allergen.index()].intersect_with(&food.ingredients);
+            }
         }
-        possible_allergens
+        candidates
     }
 
     fn count_non_allergic_ingredients(&self) -> usize {
-        let possibles_allergens = self.all_possible_allergens();
-        let zero_allergens_ingredients: HashSet<Ingredient> = possibles_allergens
-            .into_iter()
-            .filter(|(_, allergens)| allergens.is_empty())
-            .map(|x| x.0)
-            .collect();
-        self.0
+        let mut maybe_allergic = IdSet::new();
+        for candidates in self.candidates() {
+            maybe_allergic.union_with(&candidates);
+        }
+        self.foods
             .iter()
             .map(|food| {
-                food.ingredients
-                    .iter()
-                    .filter(|ing| zero_allergens_ingredients.contains(ing))
-                    .count()
+                let mut safe = food.ingredients.clone();
+                safe.difference_with(&maybe_allergic);
+                safe.len()
             })
             .sum()
     }
 
-    fn associate_ingredient_allergens(&self) -> HashMap<Ingredient, Option<Allergen>> {
-        let mut allergen_possibilities = self.all_possible_allergens();
-        let mut association = HashMap::new();
-
-        let mut remaining_allergens = self.all_allergens();
-        let mut remaining_ingredients = self.all_ingredients();
-
-        for (ing, all_set) in &allergen_possibilities {
-            if all_set.is_empty() {
-                association.insert(ing.clone(), None);
-                remaining_ingredients.remove(ing);
-            }
-        }
-
-        while !remaining_ingredients.is_empty() {
-            for ing in &remaining_ingredients.clone() {
-                let possibilities = allergen_possibilities.get(ing).unwrap();
-                if possibilities.len() == 1 {
-                    let all = possibilities.iter().next().unwrap();
-                    if remaining_allergens.contains(all) {
-                        remaining_allergens.remove(all);
-                        remaining_ingredients.remove(ing);
-                        association.insert(ing.clone(), Some(all.clone()));
-                    }
-                }
-            }
-            allergen_possibilities = allergen_possibilities
-                .into_iter()
-                .map(|(x, y)| (x, set_intersect(y, &remaining_allergens)))
-                .collect();
+    // ingredient of every allergen
+    fn associate_ingredient_allergens(&self) -> anyhow::Result<Vec<Id>> {
+        let candidates = self
+            .candidates()
+            .iter()
+            .map(|ingredients| ingredients.iter().map(Id::index).collect_vec())
+            .collect_vec();
+
+        let matching =
+            perfect_matching(&candidates, self.ingredients.len()).map_err(|err| match err {
+                MatchingError::NoPerfectMatching { unmatched } => anyhow::anyhow!(
+                    "no consistent assignment of the allergens, no ingredient left for: {}",
+                    unmatched
+                        .iter()
+                        .map(|all| self.allergens.name(Id(*all as u32)))
+                        .join(", ")
+                ),
+                err => err.into(),
+            })?;
+        if !matching.unique {
+            anyhow::bail!("several assignments of the allergens are consistent");
         }
 
-        association
+        Ok(matching
+            .left_to_right
+            .into_iter()
+            .map(|ing| Id(ing as u32))
+            .collect())
     }
 
-    fn canonical_list(&self) -> String {
-        let map_ingredients_allergens = self.associate_ingredient_allergens();
-        let mut ingredients_allergens: Vec<_> = map_ingredients_allergens
-            .into_iter()
-            .filter(|(_, al)| al.is_some())
-            .map(|(x, y)| (x, y.unwrap()))
-            .collect();
-        ingredients_allergens.sort_by_key(|(_, y)| y.0.clone());
-        ingredients_allergens.into_iter().map(|x| x.0).join(",")
+    fn canonical_list(&self) -> anyhow::Result<String> {
+        let association = self.associate_ingredient_allergens()?;
+        Ok(self
+            .allergens
+            .ids()
+            .sorted_by_key(|all| self.allergens.name(*all))
+            .map(|all| self.ingredients.name(association[all.index()]))
+            .join(","))
     }
 }
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     let lines: Vec<String> = std::io::stdin()
         .lock()
         .lines()
         .map(Result::unwrap)
         .collect();
-    let foods = FoodList::from_lines(&lines);
+    let foods = FoodList::from_lines(&lines)?;
     println!("{:?}", foods.count_non_allergic_ingredients());
-    println!("{}", foods.canonical_list());
+    println!("{}", foods.canonical_list()?);
+    Ok(())
 }
 
 #[test]
 fn test_parser() {
     assert_eq!(
         food_parser::food("mxmxvkd kfcds sqjhc nhms (contains dairy, fish)"),
-        Ok(Food {
-            ingredients: vec![
-                Ingredient("mxmxvkd".to_string()),
-                Ingredient("kfcds".to_string()),
-                Ingredient("sqjhc".to_string()),
-                Ingredient("nhms".to_string()),
-            ]
-            .into_iter()
-            .collect(),
-            allergens: vec![Allergen("dairy".to_string()), Allergen("fish".to_string())]
-                .into_iter()
-                .collect()
-        })
+        Ok((
+            vec!["mxmxvkd", "kfcds", "sqjhc", "nhms"],
+            vec!["dairy", "fish"]
+        ))
+    );
+    let foods = FoodList::from_lines(&[
+        "mxmxvkd kfcds (contains dairy, fish)".to_string(),
+        "kfcds sqjhc (contains fish)".to_string(),
+    ])
+    .unwrap();
+    assert_eq!(foods.ingredients.get("sqjhc"), Some(Id(2)));
+    assert_eq!(
+        foods.foods[1],
+        Food {
+            ingredients: vec![Id(1), Id(2)].into_iter().collect(),
+            allergens: vec![Id(1)].into_iter().collect(),
+        }
     );
+    assert!(FoodList::from_lines(&["mxmxvkd (contains)".to_string()]).is_err());
 }
 
 #[test]
@@ -209,6 +166,7 @@ fn test_exo1() {
             "sqjhc fvjkl (contains soy)".to_string(),
             "sqjhc mxmxvkd sbzzf (contains fish)".to_string(),
         ])
+        .unwrap()
         .count_non_allergic_ingredients(),
         5
     );
@@ -223,7 +181,21 @@ fn test_exo2() {
             "sqjhc fvjkl (contains soy)".to_string(),
             "sqjhc mxmxvkd sbzzf (contains fish)".to_string(),
         ])
-        .canonical_list(),
+        .unwrap()
+        .canonical_list()
+        .unwrap(),
         "mxmxvkd,sqjhc,fvjkl"
     );
 }
+
+#[test]
+fn test_associate_inconsistent() {
+    let error = FoodList::from_lines(&[
+        "mxmxvkd (contains dairy)".to_string(),
+        "mxmxvkd (contains fish)".to_string(),
+    ])
+    .unwrap()
+    .associate_ingredient_allergens()
+    .unwrap_err();
+    assert!(error.to_string().contains("fish") || error.to_string().contains("dairy"));
+}
@@ -1,6 +1,11 @@
-use std::{
-    collections::{HashSet, VecDeque},
-    io::BufRead,
+use std::collections::VecDeque;
+
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::{
+    cycle::CycleDetector,
+    input::Input,
+    integer::{run_checked, Integer, Solver},
 };
 
 type Int = u32;
@@ -12,18 +17,25 @@ enum Player {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct Card(Int);
+struct Card<I>(I);
+
+impl<I: Integer> Card<I> {
+    // The number of cards to recurse with, if the deck holds enough of them
+    fn sub_deck_size(&self, deck: &Deck<I>) -> Option<usize> {
+        self.0.to_usize().filter(|&n| n <= deck.len())
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Deck(VecDeque<Card>);
+struct Deck<I>(VecDeque<Card<I>>);
 
-impl Deck {
-    fn score(&self) -> Int {
+impl<I: Integer> Deck<I> {
+    fn score(&self) -> I {
         let n = self.0.len();
         self.0
             .iter()
             .enumerate()
-            .map(|(i, x)| ((n - i) as Int) * x.0)
+            .map(|(i, x)| I::from_u64((n - i) as u64).expect("the deck is too large") * x.0.clone())
             .sum()
     }
     fn len(&self) -> usize {
@@ -32,40 +44,37 @@ impl Deck {
     fn is_empty(&self) -> bool {
         self.0.is_empty()
     }
-    fn draw(&mut self) -> Card {
+    fn draw(&mut self) -> Card<I> {
         self.0.pop_front().unwrap()
     }
-    fn add_bottom(&mut self, card: Card) {
+    fn add_bottom(&mut self, card: Card<I>) {
         self.0.push_back(card);
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Game {
-    deck_p1: Deck,
-    deck_p2: Deck,
+struct Game<I> {
+    deck_p1: Deck<I>,
+    deck_p2: Deck<I>,
 }
 
-type Configuration = (Deck, Deck);
+type Configuration<I> = (Deck<I>, Deck<I>);
 
-impl Game {
-    fn from_lines(lines: &[String]) -> Self {
-        let mut n = lines.len();
-        n = n + (n % 2);
-        let p = n / 2 - 2; // Number of cards
-        let deck_p1: Deck = Deck(
-            lines[1..(p + 1)]
+impl<I: Integer> Game<I> {
+    fn from_input(input: &Input) -> anyhow::Result<Self> {
+        let deck = |player: &str| -> anyhow::Result<Deck<I>> {
+            input
+                .section(player)
+                .ok_or_else(|| anyhow::anyhow!("missing the deck of {}", player))?
                 .iter()
-                .map(|x| Card(x.parse::<Int>().unwrap()))
-                .collect(),
-        );
-        let deck_p2: Deck = Deck(
-            lines[n / 2 + 1..n / 2 + 1 + p]
-                .iter()
-                .map(|x| Card(x.parse::<Int>().unwrap()))
-                .collect(),
-        );
-        Game { deck_p1, deck_p2 }
+                .map(|x| Ok(Card(x.parse::<I>()?)))
+                .collect::<anyhow::Result<_>>()
+                .map(Deck)
+        };
+        Ok(Game {
+            deck_p1: deck("Player 1")?,
+            deck_p2: deck("Player 2")?,
+        })
     }
 
     fn play_turn(&mut self) {
@@ -90,41 +99,28 @@ impl Game {
         }
     }
 
+    // Each sub-game starts with its own history of configurations
     fn play_recursive(&mut self) -> Player {
-        self._recurs_game(HashSet::new())
-    }
-
-    fn _recurs_game(&mut self, mut seen_configurations: HashSet<Configuration>) -> Player {
+        let mut seen_configurations: CycleDetector<Configuration<I>> = CycleDetector::new();
         while !(self.deck_p1.is_empty() || self.deck_p2.is_empty()) {
             let current_configuration = (self.deck_p1.clone(), self.deck_p2.clone());
-            if seen_configurations.contains(&current_configuration) {
+            if seen_configurations.visit(current_configuration).is_some() {
                 return Player::P1;
             }
 
-            seen_configurations.insert(current_configuration);
-
             let card_p1 = self.deck_p1.draw();
             let card_p2 = self.deck_p2.draw();
 
             let winner: Player;
-            if card_p1.0 < self.deck_p1.len() as Int && card_p2.0 < self.deck_p2.len() as Int {
+            if let (Some(size_p1), Some(size_p2)) = (
+                card_p1.sub_deck_size(&self.deck_p1),
+                card_p2.sub_deck_size(&self.deck_p2),
+            ) {
                 winner = Game {
-                    deck_p1: Deck(
-                        self.deck_p1
-                            .0
-                            .range(0..(card_p1.0 as usize))
-                            .cloned()
-                            .collect(),
-                    ),
-                    deck_p2: Deck(
-                        self.deck_p2
-                            .0
-                            .range(0..(card_p2.0 as usize))
-                            .cloned()
-                            .collect(),
-                    ),
+                    deck_p1: Deck(self.deck_p1.0.range(0..size_p1).cloned().collect()),
+                    deck_p2: Deck(self.deck_p2.0.range(0..size_p2).cloned().collect()),
                 }
-                ._recurs_game(seen_configurations.clone())
+                .play_recursive()
             } else if card_p1.0 > card_p2.0 {
                 winner = Player::P1
             } else {
@@ -147,56 +143,51 @@ impl Game {
     }
 }
 
-fn main() {
-    let lines: Vec<String> = std::io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .collect();
-    let mut game1 = Game::from_lines(&lines);
-    let mut game2 = game1.clone();
-    let deck_winner = match game1.play_game() {
-        Player::P1 => game1.deck_p1,
-        Player::P2 => game1.deck_p2,
-    };
-    println!("{}", deck_winner.score());
+fn main() -> anyhow::Result<()> {
+    run_checked::<Int, _>(Solve)
+}
 
-    let deck_winner = match game2.play_recursive() {
-        Player::P1 => game2.deck_p1,
-        Player::P2 => game2.deck_p2,
-    };
-    println!("{}", deck_winner.score());
+struct Solve;
+
+impl Solver for Solve {
+    type Output = anyhow::Result<()>;
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let mut game1 = Game::<I>::from_input(&Input::from_stdin()?)?;
+        let mut game2 = game1.clone();
+        let deck_winner = match game1.play_game() {
+            Player::P1 => game1.deck_p1,
+            Player::P2 => game1.deck_p2,
+        };
+        println!("{}", deck_winner.score());
+
+        let deck_winner = match game2.play_recursive() {
+            Player::P1 => game2.deck_p1,
+            Player::P2 => game2.deck_p2,
+        };
+        println!("{}", deck_winner.score());
+        Ok(())
+    }
 }
 
 #[test]
 fn test_parsing() {
     assert_eq!(
-        Game::from_lines(&[
-            "Player 1:".to_string(),
-            "9".to_string(),
-            "2".to_string(),
-            "6".to_string(),
-            "3".to_string(),
-            "1".to_string(),
-            "".to_string(),
-            "Player 2:".to_string(),
-            "5".to_string(),
-            "8".to_string(),
-            "4".to_string(),
-            "7".to_string(),
-            "10".to_string(),
-            "".to_string(),
-        ]),
+        Game::<Int>::from_input(&Input::new(
+            "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10\n\n"
+        ))
+        .unwrap(),
         Game {
             deck_p1: Deck(vec![9, 2, 6, 3, 1].into_iter().map(Card).collect()),
             deck_p2: Deck(vec![5, 8, 4, 7, 10].into_iter().map(Card).collect()),
         }
     );
+    assert!(Game::<Int>::from_input(&Input::new("Player 1:\n9\n")).is_err());
 }
 
 #[test]
 fn test_exo1() {
-    let mut game = Game {
+    let mut game: Game<Int> = Game {
         deck_p1: Deck(vec![9, 2, 6, 3, 1].into_iter().map(Card).collect()),
         deck_p2: Deck(vec![5, 8, 4, 7, 10].into_iter().map(Card).collect()),
     };
@@ -212,7 +203,7 @@ fn test_exo1() {
 
 #[test]
 fn test_exo2() {
-    let mut game = Game {
+    let mut game: Game<Int> = Game {
         deck_p1: Deck(vec![9, 2, 6, 3, 1].into_iter().map(Card).collect()),
         deck_p2: Deck(vec![5, 8, 4, 7, 10].into_iter().map(Card).collect()),
     };
@@ -228,9 +219,46 @@ fn test_exo2() {
 
 #[test]
 fn test_exo2_infinite() {
-    let mut game = Game {
+    let mut game: Game<Int> = Game {
         deck_p1: Deck(vec![43, 19].into_iter().map(Card).collect()),
         deck_p2: Deck(vec![2, 29, 14].into_iter().map(Card).collect()),
     };
     game.play_recursive();
 }
+
+#[test]
+fn test_recursion_exact_cards() {
+    // Both players draw 1 with one card left: the sub-game [9] vs [3] gives
+    // player 1 the round instead of the tie going to player 2
+    let mut game: Game<Int> = Game {
+        deck_p1: Deck(vec![1, 9].into_iter().map(Card).collect()),
+        deck_p2: Deck(vec![1, 3].into_iter().map(Card).collect()),
+    };
+    assert_eq!(game.play_recursive(), Player::P1);
+    assert_eq!(
+        game.deck_p1,
+        Deck(vec![1, 1, 9, 3].into_iter().map(Card).collect())
+    );
+    assert_eq!(game.deck_p1.score(), 28);
+}
+
+#[test]
+fn test_checked() {
+    let deck = |cards: &[u16]| Deck(cards.iter().copied().map(Checked).map(Card).collect());
+    let mut game = Game {
+        deck_p1: deck(&[9, 2, 6, 3, 1]),
+        deck_p2: deck(&[5, 8, 4, 7, 10]),
+    };
+    assert_eq!(game.play_game(), Player::P2);
+    assert_eq!(game.deck_p2.score(), Checked(306));
+}
+
+#[test]
+#[should_panic(expected = "overflows u8")]
+fn test_checked_score() {
+    Deck(VecDeque::from(vec![
+        Card(Checked(100u8)),
+        Card(Checked(100)),
+    ]))
+    .score();
+}
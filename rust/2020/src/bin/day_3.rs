@@ -1,45 +1,77 @@
 use std::io;
 use std::io::BufRead;
 
-type Field = Vec<Vec<char>>;
+use advent_of_code_common::grid::{Grid, GridError, Wrap};
 
-fn test_slope(field: &Field, delta_x: i32, delta_y: i32) -> i64 {
-    let n: i32 = field.len() as i32;
-    let mut number_tree = 0;
-    let mut x: i32 = 0;
-    let mut y: i32 = 0;
-    while y < n {
-        if field[y as usize][x as usize] == '#' {
-            number_tree += 1;
-        }
-        x += delta_x;
-        x %= field[0].len() as i32;
-        y += delta_y;
-    }
-    number_tree
+// true for a tree, the pattern repeats to the right
+type Field = Grid<bool>;
+
+fn parse_field<S: AsRef<str>>(lines: &[S]) -> Result<Field, GridError> {
+    let field = Grid::parse(lines, |c| match c {
+        '#' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?;
+    Ok(field.with_wrap(Wrap {
+        rows: false,
+        columns: true,
+    }))
+}
+
+fn test_slope(field: &Field, delta_x: isize, delta_y: isize) -> i64 {
+    std::iter::once((0, 0))
+        .chain(field.ray((0, 0), (delta_y, delta_x)))
+        .filter(|position| field[*position])
+        .count() as i64
 }
 
-pub fn exercise_1(field: &Field) {
-    println!("{}", test_slope(field, 3, 1));
+pub fn exercise_1(field: &Field) -> i64 {
+    test_slope(field, 3, 1)
 }
 
-pub fn exercise_2(field: &Field) {
+pub fn exercise_2(field: &Field) -> i64 {
     let a1 = test_slope(field, 1, 1);
     let a2 = test_slope(field, 3, 1);
     let a3 = test_slope(field, 5, 1);
     let a4 = test_slope(field, 7, 1);
     let a5 = test_slope(field, 1, 2);
-    println!("{}", a1 * a2 * a3 * a4 * a5);
+    a1 * a2 * a3 * a4 * a5
 }
 
 fn main() -> anyhow::Result<()> {
-    let mut field: Field = Vec::new();
-    for line in io::stdin().lock().lines() {
-        field.push(line.unwrap().chars().collect());
-    }
+    let lines = io::stdin().lock().lines().collect::<Result<Vec<_>, _>>()?;
+    let field = parse_field(&lines)?;
 
-    exercise_1(&field);
-    exercise_2(&field);
+    println!("{}", exercise_1(&field));
+    println!("{}", exercise_2(&field));
 
     Ok(())
 }
+
+#[cfg(test)]
+fn field_test() -> Field {
+    parse_field(&[
+        "..##.......",
+        "#...#...#..",
+        ".#....#..#.",
+        "..#.#...#.#",
+        ".#...##..#.",
+        "..#.##.....",
+        ".#.#.#....#",
+        ".#........#",
+        "#.##...#...",
+        "#...##....#",
+        ".#..#...#.#",
+    ])
+    .unwrap()
+}
+
+#[test]
+fn test_exo_1() {
+    assert_eq!(exercise_1(&field_test()), 7);
+}
+
+#[test]
+fn test_exo_2() {
+    assert_eq!(exercise_2(&field_test()), 336);
+}
@@ -1,168 +1,332 @@
-#[macro_use]
-extern crate lazy_static;
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
 
-use std::io;
-use std::io::BufRead;
+use advent_of_code_common::input::Input;
 
-use std::collections::HashMap;
+type KeyVal = HashMap<String, String>;
 
-use regex::Regex;
-
-fn check_password_accepted(keys: &[String]) -> bool {
-    keys.contains(&"byr".to_string())
-        && keys.contains(&"iyr".to_string())
-        && keys.contains(&"eyr".to_string())
-        && keys.contains(&"hgt".to_string())
-        && keys.contains(&"hcl".to_string())
-        && keys.contains(&"ecl".to_string())
-        && keys.contains(&"pid".to_string())
-}
-
-pub fn exercise_1(lines: &[String]) {
-    let regex_key_val = regex::Regex::new(r"(\w\w\w):[^ ]+").unwrap();
-    let mut current_keys: Vec<String> = Vec::new();
-    let mut accepted_password = 0;
-    for line in lines {
-        if line == &"".to_string() {
-            if check_password_accepted(&current_keys) {
-                accepted_password += 1;
-            }
-            current_keys.clear();
-        } else {
-            for matching in regex_key_val.captures_iter(line.as_str()) {
-                current_keys.push(matching.get(1).unwrap().as_str().to_string());
-            }
-        }
-    }
-    if check_password_accepted(&current_keys) {
-        accepted_password += 1;
-    }
+enum Rule {
+    Year(RangeInclusive<u32>),
+    // Allowed range for each unit suffix
+    Measure(&'static [(&'static str, RangeInclusive<u32>)]),
+    HexColor,
+    OneOf(&'static [&'static str]),
+    Digits(usize),
+}
 
-    println!("{}", accepted_password);
+struct FieldRule {
+    key: &'static str,
+    rule: Rule,
 }
 
-type KeyVal = HashMap<String, String>;
+const SCHEMA: [FieldRule; 7] = [
+    FieldRule {
+        key: "byr",
+        rule: Rule::Year(1920..=2002),
+    },
+    FieldRule {
+        key: "iyr",
+        rule: Rule::Year(2010..=2020),
+    },
+    FieldRule {
+        key: "eyr",
+        rule: Rule::Year(2020..=2030),
+    },
+    FieldRule {
+        key: "hgt",
+        rule: Rule::Measure(&[("cm", 150..=193), ("in", 59..=76)]),
+    },
+    FieldRule {
+        key: "hcl",
+        rule: Rule::HexColor,
+    },
+    FieldRule {
+        key: "ecl",
+        rule: Rule::OneOf(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+    },
+    FieldRule {
+        key: "pid",
+        rule: Rule::Digits(9),
+    },
+];
 
-fn check_password_accepted_strict(keys: &KeyVal) -> bool {
-    lazy_static! {
-        static ref REGEX_YEAR: Regex = Regex::new(r"\d{4}").unwrap();
-        static ref REGEX_COLOR: Regex = Regex::new(r"#[\da-f]{6}").unwrap();
-        static ref VEC_COLOR: Vec<String> = vec![
-            "amb".to_string(),
-            "blu".to_string(),
-            "brn".to_string(),
-            "gry".to_string(),
-            "grn".to_string(),
-            "hzl".to_string(),
-            "oth".to_string()
-        ];
-        static ref REGEX_PID: Regex = Regex::new(r"\d{9}").unwrap();
-        static ref REGEX_HEIGHT: Regex = Regex::new(r"(\d+)([a-z]{2}+)").unwrap();
-    }
+fn is_digits(value: &str, length: usize) -> bool {
+    value.len() == length && value.chars().all(|c| c.is_ascii_digit())
+}
 
-    let byr = keys
-        .get(&"byr".to_string())
-        .map(|x: &String| {
-            if REGEX_YEAR.is_match(x.as_str()) {
-                let x: i32 = x.parse().unwrap();
-                x >= 1920 && x <= 2002
-            } else {
-                false
+impl Rule {
+    // None if the value follows the rule, otherwise the reason why it does not
+    fn check(&self, value: &str) -> Option<String> {
+        match self {
+            Rule::Year(range) => {
+                if !is_digits(value, 4) {
+                    Some("not a 4 digits year".to_string())
+                } else if !range.contains(&value.parse().unwrap()) {
+                    Some(format!("not in {:?}", range))
+                } else {
+                    None
+                }
             }
-        })
-        .unwrap_or(false);
-    let iyr = keys
-        .get(&"iyr".to_string())
-        .map(|x: &String| {
-            if REGEX_YEAR.is_match(x.as_str()) {
-                let x: i32 = x.parse().unwrap();
-                x >= 2010 && x <= 2020
-            } else {
-                false
+            Rule::Measure(units) => {
+                let split = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(split);
+                match units.iter().find(|(name, _)| *name == unit) {
+                    None => Some(format!("unknown unit {:?}", unit)),
+                    Some(_) if number.is_empty() => Some("no number".to_string()),
+                    Some((_, range)) => match number.parse::<u32>() {
+                        Ok(number) if range.contains(&number) => None,
+                        _ => Some(format!("not in {:?}{}", range, unit)),
+                    },
+                }
             }
-        })
-        .unwrap_or(false);
-
-    let eyr = keys
-        .get(&"eyr".to_string())
-        .map(|x: &String| {
-            if REGEX_YEAR.is_match(x.as_str()) {
-                let x: i32 = x.parse().unwrap();
-                2020 <= x && x <= 2030
-            } else {
-                false
+            Rule::HexColor => {
+                let valid = value.len() == 7
+                    && value.starts_with('#')
+                    && value[1..]
+                        .chars()
+                        .all(|c| matches!(c, '0'..='9' | 'a'..='f'));
+                if valid {
+                    None
+                } else {
+                    Some("not a #rrggbb color".to_string())
+                }
             }
-        })
-        .unwrap_or(false);
-
-    let hcl = keys
-        .get(&"hcl".to_string())
-        .map(|x: &String| REGEX_COLOR.is_match(x.as_str()))
-        .unwrap_or(false);
-
-    let ecl = keys
-        .get(&"ecl".to_string())
-        .map(|x: &String| VEC_COLOR.contains(x))
-        .unwrap_or(false);
-
-    let pid = keys
-        .get(&"pid".to_string())
-        .map(|x: &String| REGEX_PID.is_match(x.as_str()))
-        .unwrap_or(false);
-
-    let hgt = keys
-        .get(&"hgt".to_string())
-        .map(|x: &String| {
-            let capt = REGEX_HEIGHT.captures(x.as_str());
-            if let Some(capt) = capt {
-                let size: i32 = capt.get(1).unwrap().as_str().to_string().parse().unwrap();
-                let unit = capt.get(2).unwrap().as_str().to_string();
-                if &unit == "cm" {
-                    150 <= size && size <= 193
-                } else if &unit == "in" {
-                    59 <= size && size <= 76
+            Rule::OneOf(values) => {
+                if values.contains(&value) {
+                    None
                 } else {
-                    false
+                    Some(format!("not one of {}", values.join(", ")))
                 }
-            } else {
-                false
             }
-        })
-        .unwrap_or(false);
+            Rule::Digits(length) => {
+                if is_digits(value, *length) {
+                    None
+                } else {
+                    Some(format!("not a {} digits number", length))
+                }
+            }
+        }
+    }
+}
 
-    byr && iyr && eyr && hcl && ecl && pid && hgt
+#[derive(Debug, PartialEq)]
+enum FieldError {
+    Missing(&'static str),
+    Invalid {
+        key: &'static str,
+        value: String,
+        reason: String,
+    },
 }
 
-// TODO: something is going on, I have 1 more password validated that I should...
-pub fn exercise_2(lines: &[String]) {
-    let regex_key_val = regex::Regex::new(r"(\w\w\w):([^ ]+)").unwrap();
-    let mut current_keys: KeyVal = KeyVal::new();
-    let mut accepted_password = 0;
-    for line in lines {
-        if line == &"".to_string() {
-            if check_password_accepted_strict(&current_keys) {
-                accepted_password += 1;
+impl std::fmt::Display for FieldError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldError::Missing(key) => write!(f, "{}: missing", key),
+            FieldError::Invalid { key, value, reason } => {
+                write!(f, "{}: {:?} is {}", key, value, reason)
             }
-            current_keys.clear();
-        } else {
-            for matching in regex_key_val.captures_iter(line.as_str()) {
-                current_keys.insert(
-                    matching.get(1).unwrap().as_str().to_string(),
-                    matching.get(2).unwrap().as_str().to_string(),
-                );
+        }
+    }
+}
+
+fn missing_fields(keys: &KeyVal) -> Vec<FieldError> {
+    SCHEMA
+        .iter()
+        .filter(|field| !keys.contains_key(field.key))
+        .map(|field| FieldError::Missing(field.key))
+        .collect()
+}
+
+fn validate(keys: &KeyVal) -> Vec<FieldError> {
+    SCHEMA
+        .iter()
+        .filter_map(|field| match keys.get(field.key) {
+            None => Some(FieldError::Missing(field.key)),
+            Some(value) => field.rule.check(value).map(|reason| FieldError::Invalid {
+                key: field.key,
+                value: value.clone(),
+                reason,
+            }),
+        })
+        .collect()
+}
+
+fn parse_passports(input: &Input) -> Vec<KeyVal> {
+    let regex_key_val = regex::Regex::new(r"(\w\w\w):([^ ]+)").unwrap();
+    input
+        .paragraphs()
+        .iter()
+        .map(|lines| {
+            lines
+                .iter()
+                .flat_map(|line| regex_key_val.captures_iter(line))
+                .map(|matching| {
+                    (
+                        matching.get(1).unwrap().as_str().to_string(),
+                        matching.get(2).unwrap().as_str().to_string(),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+pub fn exercise_1(input: &Input) -> usize {
+    parse_passports(input)
+        .iter()
+        .filter(|keys| missing_fields(keys).is_empty())
+        .count()
+}
+
+pub fn exercise_2(input: &Input) -> usize {
+    parse_passports(input)
+        .iter()
+        .filter(|keys| validate(keys).is_empty())
+        .count()
+}
+
+fn explain(input: &Input) {
+    for (n, keys) in parse_passports(input).iter().enumerate() {
+        let errors = validate(keys);
+        if !errors.is_empty() {
+            println!("passport {}:", n);
+            for error in errors {
+                println!("  {}", error);
             }
         }
     }
-    if check_password_accepted_strict(&current_keys) {
-        accepted_password += 1;
+}
+
+fn main() -> anyhow::Result<()> {
+    let input = Input::from_stdin()?;
+
+    if std::env::args().any(|arg| arg == "--explain") {
+        explain(&input);
+        return Ok(());
     }
 
-    println!("{}", accepted_password);
+    println!("{}", exercise_1(&input));
+    println!("{}", exercise_2(&input));
+    Ok(())
+}
+
+#[test]
+fn test_rules() {
+    assert_eq!(Rule::Year(1920..=2002).check("2002"), None);
+    assert!(Rule::Year(1920..=2002).check("2003").is_some());
+    assert!(Rule::Year(1920..=2002).check("02002").is_some());
+
+    let height = &SCHEMA[3].rule;
+    assert_eq!(height.check("60in"), None);
+    assert_eq!(height.check("190cm"), None);
+    assert_eq!(height.check("190in"), Some("not in 59..=76in".to_string()));
+    assert_eq!(height.check("190"), Some("unknown unit \"\"".to_string()));
+    assert_eq!(height.check("cm"), Some("no number".to_string()));
+
+    assert_eq!(Rule::HexColor.check("#123abc"), None);
+    assert!(Rule::HexColor.check("#123abz").is_some());
+    assert!(Rule::HexColor.check("123abc").is_some());
+
+    assert_eq!(SCHEMA[5].rule.check("brn"), None);
+    assert!(SCHEMA[5].rule.check("wat").is_some());
+
+    assert_eq!(Rule::Digits(9).check("000000001"), None);
+    assert!(Rule::Digits(9).check("0123456789").is_some());
+}
+
+#[test]
+fn test_validate() {
+    let passports = parse_passports(&Input::from_lines(&[
+        "eyr:1972 cid:100",
+        "hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926",
+        "",
+        "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980",
+        "hcl:#623a2f",
+    ]));
+    assert_eq!(passports.len(), 2);
+
+    assert_eq!(
+        validate(&passports[0])
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![
+            "eyr: \"1972\" is not in 2020..=2030",
+            "hgt: \"170\" is unknown unit \"\"",
+            "pid: \"186cm\" is not a 9 digits number",
+        ]
+    );
+    assert_eq!(validate(&passports[1]), vec![]);
+
+    let mut incomplete = passports[1].clone();
+    incomplete.remove("byr");
+    assert_eq!(
+        missing_fields(&incomplete),
+        vec![FieldError::Missing("byr")]
+    );
+}
+
+#[test]
+fn test_exo_1() {
+    let lines = vec![
+        "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd",
+        "byr:1937 iyr:2017 cid:147 hgt:183cm",
+        "",
+        "iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884",
+        "hcl:#cfa07d byr:1929",
+        "",
+        "hcl:#ae17e1 iyr:2013",
+        "eyr:2024",
+        "ecl:brn pid:760753108 byr:1931",
+        "hgt:179cm",
+        "",
+        "hcl:#cfa07d eyr:2025 pid:166559648",
+        "iyr:2011 ecl:brn hgt:59in",
+    ]
+    .into_iter()
+    .collect::<Input>();
+    assert_eq!(exercise_1(&lines), 2);
 }
 
-fn main() {
-    let lines: Vec<String> = io::stdin().lock().lines().map(|x| x.unwrap()).collect();
+#[test]
+fn test_exo_2() {
+    let invalid = vec![
+        "eyr:1972 cid:100",
+        "hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926",
+        "",
+        "iyr:2019",
+        "hcl:#602927 eyr:1967 hgt:170cm",
+        "ecl:grn pid:012533040 byr:1946",
+        "",
+        "hcl:dab227 iyr:2012",
+        "ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277",
+        "",
+        "hgt:59cm ecl:zzz",
+        "eyr:2038 hcl:74454a iyr:2023",
+        "pid:3556412378 byr:2007",
+    ]
+    .into_iter()
+    .collect::<Input>();
+    assert_eq!(exercise_2(&invalid), 0);
 
-    exercise_1(&lines);
-    exercise_2(&lines);
+    let valid = vec![
+        "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980",
+        "hcl:#623a2f",
+        "",
+        "eyr:2029 ecl:blu cid:129 byr:1989",
+        "iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm",
+        "",
+        "hcl:#888785",
+        "hgt:164cm byr:2001 iyr:2015 cid:88",
+        "pid:545766238 ecl:hzl",
+        "eyr:2022",
+        "",
+        "iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719",
+    ]
+    .into_iter()
+    .collect::<Input>();
+    assert_eq!(exercise_2(&valid), 4);
 }
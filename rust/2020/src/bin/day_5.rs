@@ -1,46 +1,65 @@
 use std::io;
 use std::io::BufRead;
 
-fn parse_boarding_pass(boarding_pass: Vec<char>) -> u32 {
-    let mut code = 0;
-    for i in 0..10 {
-        if boarding_pass[i] == 'B' {
-            code += 1;
-        }
-        code <<= 1;
-    }
-    code >>= 1;
-    code
+use advent_of_code_common::bits::{Alphabet, BitsError, Pattern};
+
+// Row then column, both binary
+const SEAT: Alphabet = Alphabet {
+    zeros: "FL",
+    ones: "BR",
+    wildcards: "",
+};
+
+fn parse_boarding_pass(boarding_pass: &str) -> Result<u32, BitsError> {
+    Ok(Pattern::parse(boarding_pass, &SEAT)?.value as u32)
+}
+
+pub fn exercise_1(codes: &[u32]) -> Option<u32> {
+    codes.iter().copied().max()
 }
 
-pub fn exercise_1(codes: &[u32]) {
-    println!("{}", codes.iter().max().unwrap());
+pub fn exercise_2(codes: &[u32]) -> Option<u32> {
+    let mut codes = codes.to_vec();
+    codes.sort_unstable();
+    codes
+        .windows(2)
+        .find(|pair| pair[1] - pair[0] > 1)
+        .map(|pair| pair[0] + 1)
 }
 
-pub fn exercise_2(codes: &mut [u32]) {
-    codes.sort();
-    let mut prev = codes[0];
-    for i in 1..codes.len() {
-        if codes[i] - prev > 1 {
-            println!("{}", prev + 1);
-        }
-        prev = codes[i];
+fn main() -> anyhow::Result<()> {
+    let mut codes: Vec<u32> = vec![];
+    for line in io::stdin().lock().lines() {
+        codes.push(parse_boarding_pass(&line?)?);
     }
+    println!(
+        "{}",
+        exercise_1(&codes).ok_or_else(|| anyhow::anyhow!("no boarding pass"))?
+    );
+    println!(
+        "{}",
+        exercise_2(&codes).ok_or_else(|| anyhow::anyhow!("no free seat between two passes"))?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_boarding_pass() {
+    assert_eq!(parse_boarding_pass("FBFBBFFRLR"), Ok(357));
+    assert_eq!(parse_boarding_pass("BFFFBBFRRR"), Ok(567));
+    assert_eq!(parse_boarding_pass("FFFBBBFRRR"), Ok(119));
+    assert_eq!(parse_boarding_pass("BBFFBBFRLL"), Ok(820));
+    assert!(parse_boarding_pass("BBFFBBFRLX").is_err());
+}
+
+#[test]
+fn test_exo_1() {
+    assert_eq!(exercise_1(&[357, 567, 119, 820]), Some(820));
+    assert_eq!(exercise_1(&[]), None);
 }
 
-fn main() {
-    let mut codes: Vec<u32> = io::stdin()
-        .lock()
-        .lines()
-        .map(|line| {
-            parse_boarding_pass(
-                line.unwrap()
-                    .chars()
-                    .map(|x| if x == 'R' { 'B' } else { x })
-                    .collect(),
-            )
-        })
-        .collect();
-    exercise_1(&codes);
-    exercise_2(&mut codes);
+#[test]
+fn test_exo_2() {
+    assert_eq!(exercise_2(&[10, 8, 7, 11]), Some(9));
+    assert_eq!(exercise_2(&[8, 7, 9]), None);
 }
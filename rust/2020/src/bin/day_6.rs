@@ -1,64 +1,63 @@
 use std::collections::HashMap;
-use std::io;
-use std::io::BufRead;
 
-pub fn exercise_1(lines: &[String]) {
-    let mut hash_questions: HashMap<char, bool> = HashMap::new();
-    let mut counter: i32 = 0;
-    for line in lines {
-        if line == "" {
-            counter += hash_questions.keys().len() as i32;
-            hash_questions.clear();
+use advent_of_code_common::input::Input;
+
+// Number of people of the group who answered each question
+fn answers(group: &[&str]) -> HashMap<char, usize> {
+    let mut hash_questions: HashMap<char, usize> = HashMap::new();
+    for line in group {
+        for question in line.chars() {
+            *hash_questions.entry(question).or_insert(0) += 1;
         }
-        let _: Vec<Option<bool>> = line
-            .chars()
-            .map(|x| hash_questions.insert(x, true))
-            .collect();
     }
-    counter += hash_questions.keys().len() as i32;
-    hash_questions.clear();
+    hash_questions
+}
 
-    println!("{}", counter);
+pub fn exercise_1(input: &Input) -> usize {
+    input
+        .paragraphs()
+        .iter()
+        .map(|group| answers(group).len())
+        .sum()
 }
 
-pub fn exercise_2(lines: &[String]) {
-    let mut hash_questions: HashMap<char, u32> = HashMap::new();
-    let mut counter: i32 = 0;
-    let mut number_attending = 0;
+pub fn exercise_2(input: &Input) -> usize {
+    input
+        .paragraphs()
+        .iter()
+        .map(|group| {
+            answers(group)
+                .values()
+                .filter(|count| **count == group.len())
+                .count()
+        })
+        .sum()
+}
 
-    let mut incr_counter = |hash_questions: &HashMap<char, u32>, number_attending| {
-        for (_, val) in hash_questions.iter() {
-            if val == &number_attending {
-                counter += 1;
-            }
-        }
-    };
+fn main() -> anyhow::Result<()> {
+    let input = Input::from_stdin()?;
 
-    for line in lines {
-        if line == "" {
-            incr_counter(&hash_questions, number_attending);
-            number_attending = 0;
-            hash_questions.clear();
-        } else {
-            number_attending += 1;
-            let _: Vec<()> = line
-                .chars()
-                .map(|x| {
-                    let count = hash_questions.entry(x).or_insert(0);
-                    *count += 1;
-                })
-                .collect();
-        }
-    }
-    incr_counter(&hash_questions, number_attending);
-    hash_questions.clear();
+    println!("{}", exercise_1(&input));
+    println!("{}", exercise_2(&input));
+    Ok(())
+}
 
-    println!("{}", counter);
+#[cfg(test)]
+fn lines_test() -> Input {
+    vec![
+        "abc", "", "a", "b", "c", "", "ab", "ac", "", "a", "a", "a", "a", "", "b",
+    ]
+    .into_iter()
+    .collect()
 }
 
-fn main() {
-    let lines: Vec<String> = io::stdin().lock().lines().map(|x| x.unwrap()).collect();
+#[test]
+fn test_exo_1() {
+    assert_eq!(exercise_1(&lines_test()), 11);
+}
 
-    exercise_1(&lines);
-    exercise_2(&lines);
+#[test]
+fn test_exo_2() {
+    assert_eq!(exercise_2(&lines_test()), 6);
+    assert_eq!(exercise_2(&Input::new("ab\r\nb\r\n\r\n\r\n")), 1);
 }
@@ -1,84 +1,113 @@
 use std::io::{stdin, BufRead};
 
+use advent_of_code_common::interner::{Id, Interner};
 use petgraph::{
     graph::{Graph, NodeIndex},
     visit::EdgeRef,
 };
 
-use std::collections::{HashMap, LinkedList};
+use std::collections::HashMap;
+use std::fmt::Write;
 
-peg::parser! {
-  grammar edge_parser() for str {
-    rule number() -> u32
-      = n:$(['0'..='9']+) { n.parse().unwrap() }
+advent_of_code_common::parser! {
+grammar edge_parser() for str {
+    pub rule bag() -> Bag<'input>
+      = name:$(['a'..='z']+ " " ['a'..='z']+) " " ("bags"/"bag"){ Bag(name) }
 
-    pub rule bag() -> Bag
-      = adj:$(['a'..='z']+) " " color:$(['a'..='z']+) " " ("bags"/"bag"){ Bag(adj.into(), color.into()) }
+    rule bag_link() -> (Bag<'input>, u32)
+      = number:u32() " " bag:bag() {(bag, number)}
 
-    rule bag_link() -> (Bag, u32)
-      = number:number() " " bag:bag() ", "? {(bag, number)}
-
-    rule bag_empty() -> BagEdges
+    rule bag_empty() -> BagEdges<'input>
       = b1:bag() " contain no other bags." {BagEdges(b1, vec![])}
 
-    rule bag_non_empty() -> BagEdges
-      = b1:bag() " contain " l:bag_link()* "." {BagEdges(b1, l)}
+    rule bag_non_empty() -> BagEdges<'input>
+      = b1:bag() " contain " l:comma_list(<bag_link()>) "." {BagEdges(b1, l)}
 
-    pub rule bag_edge() -> BagEdges
+    pub rule bag_edge() -> BagEdges<'input>
       = edge:(bag_empty()/bag_non_empty()) {edge}
-  }
+}
 }
 
-#[derive(Debug, PartialEq, Hash, Eq, Clone)]
-pub struct Bag(String, String);
+#[derive(Debug, PartialEq, Hash, Eq, Clone, Copy)]
+pub struct Bag<'a>(&'a str);
+
+impl std::fmt::Display for Bag<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 #[derive(Debug, PartialEq)]
-pub struct BagEdges(Bag, Vec<(Bag, u32)>);
+pub struct BagEdges<'a>(Bag<'a>, Vec<(Bag<'a>, u32)>);
 
-fn lines_to_graph(lines: Vec<String>) -> (HashMap<Bag, NodeIndex>, Graph<Bag, u32>) {
-    let mut bag_graph = Graph::<Bag, u32>::new();
-    let mut bag_hashmap: HashMap<Bag, NodeIndex> = HashMap::new();
-    let _ = lines
-        .into_iter()
-        // Pour chaques lignes, on parse
-        .map(|line| edge_parser::bag_edge(line.as_str()).unwrap())
-        // On met en mémoire le noeud
-        .map(|edges| {
-            let node_index: NodeIndex = bag_graph.add_node(edges.0.clone());
-            bag_hashmap.insert(edges.0, node_index);
-            (node_index, edges.1)
-        })
-        // On le fait pour tous les noeuds avant d'aller plus loin
-        .collect::<LinkedList<_>>()
+type BagGraph = Graph<Id, u32>;
+
+// Each node holds the id of its bag
+struct Bags {
+    names: Interner,
+    // Node of each id
+    nodes: Vec<NodeIndex>,
+    graph: BagGraph,
+}
+
+impl Bags {
+    fn find(&self, name: &str) -> Option<NodeIndex> {
+        self.names.get(name).map(|id| self.nodes[id.index()])
+    }
+
+    fn name(&self, node: NodeIndex) -> &str {
+        self.names.name(self.graph[node])
+    }
+}
+
+fn lines_to_graph(lines: Vec<String>) -> anyhow::Result<Bags> {
+    let rules = lines
         .iter()
-        // On rajoute les fils
-        .map(|(node_index, edges)| {
-            let _ = edges
-                .iter()
-                .map(|(bag, weight)| {
-                    let son_index: &NodeIndex = bag_hashmap.get(&bag).unwrap();
-                    bag_graph.add_edge(*node_index, *son_index, *weight);
-                })
-                .collect::<Vec<_>>();
-        })
-        // On collecte le tout
-        .collect::<Vec<_>>();
-    (bag_hashmap, bag_graph)
+        .map(|line| Ok(edge_parser::bag_edge(line)?))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut bags = Bags {
+        names: Interner::new(),
+        nodes: vec![],
+        graph: BagGraph::new(),
+    };
+    for BagEdges(bag, _) in &rules {
+        let id = bags.names.intern(bag.0);
+        if id.index() != bags.nodes.len() {
+            anyhow::bail!("the bag \"{}\" has two rules", bag);
+        }
+        bags.nodes.push(bags.graph.add_node(id));
+    }
+    // The rules being interned first, the node of the i-th rule is the i-th node
+    for (BagEdges(_, sons), &node) in rules.iter().zip(&bags.nodes) {
+        for (son, weight) in sons {
+            let son_node = bags
+                .find(son.0)
+                .ok_or_else(|| anyhow::anyhow!("the bag \"{}\" has no rule", son))?;
+            bags.graph.add_edge(node, son_node, *weight);
+        }
+    }
+    Ok(bags)
 }
 
-fn exercise_1(target_node: NodeIndex, bag_graph: &Graph<Bag, u32>) -> i32 {
+fn containers(target_node: NodeIndex, bag_graph: &BagGraph) -> Vec<NodeIndex> {
     use petgraph::visit::DfsEvent;
 
     let reversed_graph = petgraph::visit::Reversed(bag_graph);
-    let mut count = -1;
+    let mut containers = vec![];
     petgraph::visit::depth_first_search(&reversed_graph, Some(target_node), |event| {
-        if let DfsEvent::Discover(_, _) = event {
-            count += 1;
+        if let DfsEvent::Discover(node, _) = event {
+            if node != target_node {
+                containers.push(node);
+            }
         }
     });
-    count
+    containers
+}
+
+fn exercise_1(target_node: NodeIndex, bag_graph: &BagGraph) -> i32 {
+    containers(target_node, bag_graph).len() as i32
 }
-fn exercise_2(begin_node: NodeIndex, bag_graph: &Graph<Bag, u32>) -> u32 {
+fn exercise_2(begin_node: NodeIndex, bag_graph: &BagGraph) -> u32 {
     let mut number_bags: HashMap<NodeIndex, u32> = HashMap::new();
 
     let mut dfs_postorder = petgraph::visit::DfsPostOrder::new(bag_graph, begin_node);
@@ -95,23 +124,216 @@ fn exercise_2(begin_node: NodeIndex, bag_graph: &Graph<Bag, u32>) -> u32 {
     *number_bags.get(&begin_node).unwrap() - 1
 }
 
-fn main() {
-    let (bag_hashmap, bag_graph) =
-        lines_to_graph(stdin().lock().lines().map(Result::unwrap).collect());
-    // On le fait pour tous les noeuds avant d'aller plus loin
-    // On collecte le tout
+// Contained bags of a node, in the order of the rule
+fn contained_bags(node: NodeIndex, bag_graph: &BagGraph) -> Vec<(NodeIndex, u32)> {
+    let mut edges = bag_graph
+        .edges(node)
+        .map(|edge| (edge.target(), *edge.weight()))
+        .collect::<Vec<_>>();
+    edges.reverse();
+    edges
+}
 
-    let target_node = bag_hashmap
-        .get(&Bag("shiny".into(), "gold".into()))
-        .unwrap();
+fn find_cycle(bag_graph: &BagGraph) -> Option<Vec<NodeIndex>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unseen,
+        InPath,
+        Done,
+    }
 
-    println!("{}", exercise_1(*target_node, &bag_graph));
-    println!("{}", exercise_2(*target_node, &bag_graph));
+    fn visit(
+        node: NodeIndex,
+        bag_graph: &BagGraph,
+        states: &mut HashMap<NodeIndex, State>,
+        path: &mut Vec<NodeIndex>,
+    ) -> Option<Vec<NodeIndex>> {
+        states.insert(node, State::InPath);
+        path.push(node);
+        for (son, _) in contained_bags(node, bag_graph) {
+            match states.get(&son).copied().unwrap_or(State::Unseen) {
+                State::InPath => {
+                    let start = path.iter().position(|x| *x == son).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(son);
+                    return Some(cycle);
+                }
+                State::Unseen => {
+                    if let Some(cycle) = visit(son, bag_graph, states, path) {
+                        return Some(cycle);
+                    }
+                }
+                State::Done => (),
+            }
+        }
+        path.pop();
+        states.insert(node, State::Done);
+        None
+    }
+
+    let mut states = HashMap::new();
+    for node in bag_graph.node_indices() {
+        if !states.contains_key(&node) {
+            if let Some(cycle) = visit(node, bag_graph, &mut states, &mut vec![]) {
+                return Some(cycle);
+            }
+        }
+    }
+    None
+}
+
+fn check_acyclic(bags: &Bags) -> anyhow::Result<()> {
+    if let Some(cycle) = find_cycle(&bags.graph) {
+        anyhow::bail!(
+            "the bag rules contain a cycle: {}",
+            cycle
+                .iter()
+                .map(|node| bags.name(*node))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+    }
+    Ok(())
+}
+
+// Number of levels of bags inside the node, the graph must be acyclic
+fn nesting_depth(
+    node: NodeIndex,
+    bag_graph: &BagGraph,
+    memo: &mut HashMap<NodeIndex, usize>,
+) -> usize {
+    if let Some(depth) = memo.get(&node) {
+        return *depth;
+    }
+    let depth = contained_bags(node, bag_graph)
+        .into_iter()
+        .map(|(son, _)| 1 + nesting_depth(son, bag_graph, memo))
+        .max()
+        .unwrap_or(0);
+    memo.insert(node, depth);
+    depth
+}
+
+fn contents_tree(
+    node: NodeIndex,
+    bags: &Bags,
+    indent: usize,
+    output: &mut String,
+) -> std::fmt::Result {
+    for (son, number) in contained_bags(node, &bags.graph) {
+        writeln!(
+            output,
+            "{:indent$}{} {}",
+            "",
+            number,
+            bags.name(son),
+            indent = indent
+        )?;
+        contents_tree(son, bags, indent + 2, output)?;
+    }
+    Ok(())
+}
+
+fn to_dot(root: Option<NodeIndex>, bags: &Bags) -> String {
+    let bag_graph = &bags.graph;
+    let nodes = match root {
+        Some(root) => {
+            let mut nodes = vec![];
+            let mut dfs = petgraph::visit::Dfs::new(bag_graph, root);
+            while let Some(node) = dfs.next(bag_graph) {
+                nodes.push(node);
+            }
+            nodes
+        }
+        None => bag_graph.node_indices().collect(),
+    };
+
+    let mut dot = String::from("digraph bags {\n");
+    for node in &nodes {
+        writeln!(dot, "    \"{}\";", bags.name(*node)).unwrap();
+    }
+    for node in &nodes {
+        for (son, number) in contained_bags(*node, bag_graph) {
+            writeln!(
+                dot,
+                "    \"{}\" -> \"{}\" [label={}];",
+                bags.name(*node),
+                bags.name(son),
+                number
+            )
+            .unwrap();
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+const USAGE: &str = "usage: day_7 [containers|contents|depth|dot] [<adjective> <color>] < input";
+
+fn run_command(args: &[String], bags: &Bags) -> anyhow::Result<String> {
+    let bag = match &args[1..] {
+        [] => None,
+        [adj, color] => {
+            let name = format!("{} {}", adj, color);
+            match bags.find(&name) {
+                Some(node) => Some(node),
+                None => anyhow::bail!("unknown bag: {}", name),
+            }
+        }
+        _ => anyhow::bail!(USAGE),
+    };
+
+    let mut output = String::new();
+    match (args[0].as_str(), bag) {
+        ("containers", Some(node)) => {
+            let mut names = containers(node, &bags.graph)
+                .into_iter()
+                .map(|node| bags.name(node))
+                .collect::<Vec<_>>();
+            names.sort();
+            for name in names {
+                writeln!(output, "{}", name)?;
+            }
+        }
+        ("contents", Some(node)) => {
+            writeln!(output, "{}", bags.name(node))?;
+            contents_tree(node, bags, 2, &mut output)?;
+        }
+        ("depth", Some(node)) => {
+            writeln!(
+                output,
+                "{}",
+                nesting_depth(node, &bags.graph, &mut HashMap::new())
+            )?;
+        }
+        ("dot", root) => output = to_dot(root, bags),
+        _ => anyhow::bail!(USAGE),
+    }
+    Ok(output)
+}
+
+fn main() -> anyhow::Result<()> {
+    let bags = lines_to_graph(stdin().lock().lines().collect::<Result<_, _>>()?)?;
+    check_acyclic(&bags)?;
+
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        print!("{}", run_command(&args, &bags)?);
+        return Ok(());
+    }
+
+    let target_node = bags
+        .find("shiny gold")
+        .ok_or_else(|| anyhow::anyhow!("no rule for the shiny gold bag"))?;
+
+    println!("{}", exercise_1(target_node, &bags.graph));
+    println!("{}", exercise_2(target_node, &bags.graph));
+    Ok(())
 }
 
 #[test]
 fn test_exo_1() {
-    let (bag_hashmap, bag_graph) = lines_to_graph(vec![
+    let bags = lines_to_graph(vec![
         "light red bags contain 1 bright white bag, 2 muted yellow bags.".into(),
         "dark orange bags contain 3 bright white bags, 4 muted yellow bags.".into(),
         "bright white bags contain 1 shiny gold bag.".into(),
@@ -121,16 +343,15 @@ fn test_exo_1() {
         "vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.".into(),
         "faded blue bags contain no other bags.".into(),
         "dotted black bags contain no other bags.".into(),
-    ]);
-    let target_node = bag_hashmap
-        .get(&Bag("shiny".into(), "gold".into()))
-        .unwrap();
-    assert_eq!(exercise_1(*target_node, &bag_graph), 4);
+    ])
+    .unwrap();
+    let target_node = bags.find("shiny gold").unwrap();
+    assert_eq!(exercise_1(target_node, &bags.graph), 4);
 }
 
 #[test]
 fn test_exo_2() {
-    let (bag_hashmap, bag_graph) = lines_to_graph(vec![
+    let bags = lines_to_graph(vec![
         "light red bags contain 1 bright white bag, 2 muted yellow bags.".into(),
         "dark orange bags contain 3 bright white bags, 4 muted yellow bags.".into(),
         "bright white bags contain 1 shiny gold bag.".into(),
@@ -140,14 +361,13 @@ fn test_exo_2() {
         "vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.".into(),
         "faded blue bags contain no other bags.".into(),
         "dotted black bags contain no other bags.".into(),
-    ]);
-    let target_node = bag_hashmap
-        .get(&Bag("shiny".into(), "gold".into()))
-        .unwrap();
+    ])
+    .unwrap();
+    let target_node = bags.find("shiny gold").unwrap();
 
-    assert_eq!(exercise_2(*target_node, &bag_graph), 32);
+    assert_eq!(exercise_2(target_node, &bags.graph), 32);
 
-    let (bag_hashmap, bag_graph) = lines_to_graph(vec![
+    let bags = lines_to_graph(vec![
         "shiny gold bags contain 2 dark red bags.".into(),
         "dark red bags contain 2 dark orange bags.".into(),
         "dark orange bags contain 2 dark yellow bags.".into(),
@@ -155,22 +375,21 @@ fn test_exo_2() {
         "dark green bags contain 2 dark blue bags.".into(),
         "dark blue bags contain 2 dark violet bags.".into(),
         "dark violet bags contain no other bags.".into(),
-    ]);
-    let target_node = bag_hashmap
-        .get(&Bag("shiny".into(), "gold".into()))
-        .unwrap();
-    assert_eq!(exercise_2(*target_node, &bag_graph), 126);
+    ])
+    .unwrap();
+    let target_node = bags.find("shiny gold").unwrap();
+    assert_eq!(exercise_2(target_node, &bags.graph), 126);
 }
 
 #[test]
 fn test_bag_reader() {
     assert_eq!(
         edge_parser::bag("light orange bag").unwrap(),
-        Bag("light".into(), "orange".into())
+        Bag("light orange")
     );
     assert_eq!(
         edge_parser::bag("light orange bags").unwrap(),
-        Bag("light".into(), "orange".into())
+        Bag("light orange")
     );
 }
 
@@ -182,23 +401,105 @@ fn test_edge_reader() {
         )
         .unwrap(),
         BagEdges(
-            Bag("dim".into(), "chartreuse".into()),
+            Bag("dim chartreuse"),
             vec![
-                (Bag("wavy".into(), "plum".into()), 2),
-                (Bag("wavy".into(), "teal".into()), 4),
-                (Bag("dark".into(), "crimson".into()), 1),
+                (Bag("wavy plum"), 2),
+                (Bag("wavy teal"), 4),
+                (Bag("dark crimson"), 1),
             ]
         )
     );
     assert_eq!(
         edge_parser::bag_edge("dim chartreuse bags contain 2 wavy plum bags.").unwrap(),
-        BagEdges(
-            Bag("dim".into(), "chartreuse".into()),
-            vec![(Bag("wavy".into(), "plum".into()), 2),]
-        )
+        BagEdges(Bag("dim chartreuse"), vec![(Bag("wavy plum"), 2),])
     );
     assert_eq!(
         edge_parser::bag_edge("bright fuchsia bags contain no other bags.").unwrap(),
-        BagEdges(Bag("bright".into(), "fuchsia".into()), vec![])
+        BagEdges(Bag("bright fuchsia"), vec![])
+    );
+    assert!(
+        edge_parser::bag_edge("dim chartreuse bags contain 4294967296 wavy plum bags.").is_err()
+    );
+}
+
+#[test]
+fn test_commands() -> anyhow::Result<()> {
+    let bags = lines_to_graph(vec![
+        "light red bags contain 1 bright white bag, 2 muted yellow bags.".into(),
+        "dark orange bags contain 3 bright white bags, 4 muted yellow bags.".into(),
+        "bright white bags contain 1 shiny gold bag.".into(),
+        "muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.".into(),
+        "shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.".into(),
+        "dark olive bags contain 3 faded blue bags, 4 dotted black bags.".into(),
+        "vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.".into(),
+        "faded blue bags contain no other bags.".into(),
+        "dotted black bags contain no other bags.".into(),
+    ])
+    .unwrap();
+    check_acyclic(&bags)?;
+    let run = |command: &str| {
+        let args = command.split(' ').map(str::to_string).collect::<Vec<_>>();
+        run_command(&args, &bags)
+    };
+
+    assert_eq!(
+        run("containers shiny gold")?,
+        "bright white\ndark orange\nlight red\nmuted yellow\n"
+    );
+    assert_eq!(
+        run("contents shiny gold")?,
+        "shiny gold\n  1 dark olive\n    3 faded blue\n    4 dotted black\n  \
+         2 vibrant plum\n    5 faded blue\n    6 dotted black\n"
+    );
+    assert_eq!(run("depth shiny gold")?, "2\n");
+    assert_eq!(run("depth light red")?, "4\n");
+    assert_eq!(run("depth faded blue")?, "0\n");
+
+    let dot = run("dot dark olive")?;
+    assert!(dot.starts_with("digraph bags {\n"));
+    assert!(dot.contains("    \"dark olive\" -> \"dotted black\" [label=4];\n"));
+    assert!(!dot.contains("shiny gold"));
+    assert_eq!(run("dot")?.matches("->").count(), 13);
+
+    assert!(run("containers crimson red").is_err());
+    assert!(run("depth").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_cycle() {
+    let bags = lines_to_graph(vec![
+        "shiny gold bags contain 2 dark red bags.".into(),
+        "dark red bags contain 2 dark orange bags, 1 faded blue bag.".into(),
+        "dark orange bags contain 1 shiny gold bag.".into(),
+        "faded blue bags contain no other bags.".into(),
+    ])
+    .unwrap();
+    assert_eq!(
+        check_acyclic(&bags).unwrap_err().to_string(),
+        "the bag rules contain a cycle: shiny gold -> dark red -> dark orange -> shiny gold"
+    );
+}
+
+#[test]
+fn test_invalid_rules() {
+    let error = |lines: &[&str]| {
+        lines_to_graph(lines.iter().map(|line| line.to_string()).collect())
+            .err()
+            .unwrap()
+            .to_string()
+    };
+    assert_eq!(
+        error(&[
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain no other bags.",
+            "shiny gold bags contain no other bags.",
+        ]),
+        "the bag \"shiny gold\" has two rules"
+    );
+    assert_eq!(
+        error(&["shiny gold bags contain 2 dark red bags."]),
+        "the bag \"dark red\" has no rule"
     );
+    assert!(lines_to_graph(vec!["shiny gold bags contain bags.".into()]).is_err());
 }
@@ -1,16 +1,23 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::io::BufRead;
 
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::{
+    integer::{run_checked, Integer, Solver},
+    search,
+};
 use itertools::Itertools;
-use petgraph::{algo::astar, graph::NodeIndex};
 
-enum ProgramResult {
-    Loop(i32),
-    Finished(i32),
+type Int = i32;
+
+enum ProgramResult<I> {
+    Loop(I),
+    Finished(I),
 }
 
-fn run_program_until_loop_or_end(program: &Program) -> ProgramResult {
-    let mut counter = 0;
+fn run_program_until_loop_or_end<I: Integer>(program: &Program<I>) -> ProgramResult<I> {
+    let mut counter = I::zero();
     let mut current_instruction: i32 = 0;
 
     let mut seen_instruction: HashSet<i32> = HashSet::new();
@@ -22,19 +29,19 @@ fn run_program_until_loop_or_end(program: &Program) -> ProgramResult {
         seen_instruction.insert(current_instruction);
         let instruction = &program.0[current_instruction as usize];
 
-        match *instruction {
+        match instruction {
             Instruction::Acc(x) => {
-                counter += x;
+                counter += x.clone();
                 current_instruction += 1
             }
-            Instruction::Jmp(x) => current_instruction += x,
+            Instruction::Jmp(x) => current_instruction += *x,
             _ => current_instruction += 1,
         }
     }
     ProgramResult::Finished(counter)
 }
 
-fn exercise_1(program: &Program) -> i32 {
+fn exercise_1<I: Integer>(program: &Program<I>) -> I {
     if let ProgramResult::Loop(x) = run_program_until_loop_or_end(program) {
         x
     } else {
@@ -42,114 +49,55 @@ fn exercise_1(program: &Program) -> i32 {
     }
 }
 
-type Graph = petgraph::graph::Graph<i32, ()>;
-
-fn construct_graph_program(
-    program: &Program,
-) -> (Graph, NodeIndex, NodeIndex, HashMap<NodeIndex, bool>) {
-    let n = program.0.len() as i32;
-
-    let instruction_range = 0..n;
-
-    let mut nodes_part_1: HashMap<i32, NodeIndex> = HashMap::new();
-    let mut nodes_part_2: HashMap<i32, NodeIndex> = HashMap::new();
-    let mut node_to_part: HashMap<NodeIndex, bool> = HashMap::new();
+// Position in the program, and whether an instruction was already swapped on the way
+type State = (i32, bool);
 
-    let mut graph: Graph = Graph::new();
-
-    let initial_node = graph.add_node(-1);
-    nodes_part_1.insert(-1, initial_node);
-    node_to_part.insert(initial_node, false);
-    let final_node = graph.add_node(-2);
-    nodes_part_2.insert(-1, final_node);
-    node_to_part.insert(final_node, true);
-
-    for index in instruction_range.clone() {
-        let node_index_1 = graph.add_node(index);
-        let node_index_2 = graph.add_node(index);
-        nodes_part_1.insert(index, node_index_1);
-        nodes_part_2.insert(index, node_index_2);
-        node_to_part.insert(node_index_1, false);
-        node_to_part.insert(node_index_2, true);
+fn swapped<I: Clone>(instruction: &Instruction<I>) -> Option<Instruction<I>> {
+    match instruction {
+        Instruction::Nop(x) => Some(Instruction::Jmp(*x)),
+        Instruction::Jmp(x) => Some(Instruction::Nop(*x)),
+        Instruction::Acc(_) => None,
     }
+}
 
-    graph.add_edge(initial_node, *nodes_part_1.get(&0).unwrap(), ());
-
-    let _ = program
-        .0
-        .iter()
-        .enumerate()
-        .map(|(index, instr)| {
-            let index = index as i32;
-            let node_index_1 = nodes_part_1.get(&index).unwrap();
-            let node_index_2 = nodes_part_2.get(&index).unwrap();
-
-            let index_n_plus_one_1 = nodes_part_1.get(&(index + 1)).unwrap_or(&final_node);
-            let index_n_plus_one_2 = nodes_part_2.get(&(index + 1)).unwrap_or(&final_node);
-
-            match instr {
-                Instruction::Nop(x) => {
-                    // On ajoute le "next"
-                    graph.add_edge(*node_index_1, *index_n_plus_one_1, ());
-                    graph.add_edge(*node_index_2, *index_n_plus_one_2, ());
-
-                    // On saute sur l'autre partie (transformation en jmp)
-                    let node_target = nodes_part_2.get(&(index + x)).unwrap_or(&final_node);
-                    graph.add_edge(*node_index_1, *node_target, ());
-                }
-                Instruction::Acc(_) => {
-                    // On ajoute le "next"
-                    graph.add_edge(*node_index_1, *index_n_plus_one_1, ());
-                    graph.add_edge(*node_index_2, *index_n_plus_one_2, ());
-                }
-                Instruction::Jmp(x) => {
-                    // On ajoute le jump
-                    let node_target_1 = nodes_part_1.get(&(index + x)).unwrap_or(&final_node);
-                    graph.add_edge(*node_index_1, *node_target_1, ());
-                    let node_target_2 = nodes_part_2.get(&(index + x)).unwrap_or(&final_node);
-                    graph.add_edge(*node_index_2, *node_target_2, ());
-
-                    // On saute à la suivant dans l'autre partie (transformation en Nop)
-                    graph.add_edge(*node_index_1, *index_n_plus_one_2, ());
-                }
-            }
-        })
-        .collect::<Vec<_>>();
-    (graph, initial_node, final_node, node_to_part)
+fn next_position<I>(position: i32, instruction: &Instruction<I>) -> i32 {
+    match instruction {
+        Instruction::Jmp(x) => position + x,
+        _ => position + 1,
+    }
 }
 
-fn find_changing_node_index(program: &Program) -> usize {
-    let (graph, initial_node, final_node, node_to_part) = construct_graph_program(program);
-    // use petgraph::dot::Dot;
-    // println!("{:?}", Dot::with_config(&graph, &[]));
+fn find_changing_node_index<I: Integer>(program: &Program<I>) -> usize {
+    let n = program.0.len() as i32;
+    let successors = |&(position, swapped_before): &State| {
+        let mut next = vec![];
+        if let Some(instruction) = program.0.get(position as usize) {
+            next.push((next_position(position, instruction), swapped_before));
+            if let (false, Some(other)) = (swapped_before, swapped(instruction)) {
+                next.push((next_position(position, &other), true));
+            }
+        }
+        next
+    };
 
-    let (_, path) = astar(
-        &graph,
-        initial_node,
-        |finish| finish == final_node,
-        |_| 1,
-        |_| 0,
-    )
-    .expect("No path between initial and final node");
+    let path = search::bfs((0, false), successors, |(position, _)| *position == n)
+        .path
+        .expect("No path between initial and final node")
+        .nodes;
 
     let (position, _) = path
         .iter()
-        .find_position(|x| *node_to_part.get(&x).unwrap())
+        .find_position(|(_, swapped_before)| *swapped_before)
         .expect("No jumping");
 
-    let node_to_change = path[position - 1];
-    *graph.node_weight(node_to_change).unwrap() as usize
+    path[position - 1].0 as usize
 }
 
-fn exercise_2(program: &mut Program) -> i32 {
+fn exercise_2<I: Integer>(program: &mut Program<I>) -> I {
     let node_to_change_index = find_changing_node_index(program);
-    use Instruction::*;
 
-    let new_instruction = match program.0[node_to_change_index] {
-        Nop(x) => Jmp(x),
-        Jmp(x) => Nop(x),
-        _ => panic!("Jump in something else than nop or jmp"),
-    };
+    let new_instruction =
+        swapped(&program.0[node_to_change_index]).expect("Jump in something else than nop or jmp");
     program.0[node_to_change_index] = new_instruction;
 
     if let ProgramResult::Finished(x) = run_program_until_loop_or_end(program) {
@@ -159,91 +107,110 @@ fn exercise_2(program: &mut Program) -> i32 {
     }
 }
 
+// Jumps are offsets in the program, only the accumulator uses I
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
-pub enum Instruction {
+pub enum Instruction<I> {
     Nop(i32),
-    Acc(i32),
+    Acc(I),
     Jmp(i32),
 }
 
-peg::parser! {
+// Reads the increments of the accumulator
+type ReadInteger<I> = fn(&str) -> Option<I>;
+
+advent_of_code_common::parser! {
 grammar instruction_parser() for str {
-    rule base_number() -> i32
-        = n:$(['0'..='9']+) { n.parse().unwrap() }
-    rule positive_number() -> i32
-        = "+" n:base_number() { n }
-    rule neg_number() -> i32
-        = "-" n:base_number() { -n }
-    rule number() -> i32
-        = n:(neg_number() / positive_number())
-
-    rule nop() -> Instruction
-        = "nop " n:number() {Instruction::Nop(n)}
-
-    rule acc() -> Instruction
-        = "acc " n:number() {Instruction::Acc(n)}
-
-    rule jmp() -> Instruction
-        = "jmp " n:number() {Instruction::Jmp(n)}
-
-    pub rule instruction() -> Instruction
-        = instr:(nop()/acc()/jmp())
+    rule nop<I>() -> Instruction<I>
+        = "nop " n:i32() {Instruction::Nop(n)}
+
+    rule acc<I>(integer: ReadInteger<I>) -> Instruction<I>
+        = "acc " n:$(['+' | '-']? ['0'..='9']+) {?
+            integer(n).map(Instruction::Acc).ok_or("an accumulator increment")
+        }
+
+    rule jmp<I>() -> Instruction<I>
+        = "jmp " n:i32() {Instruction::Jmp(n)}
+
+    pub rule instruction<I>(integer: ReadInteger<I>) -> Instruction<I>
+        = instr:(nop()/acc(integer)/jmp())
     }
 }
 
-struct Program(Vec<Instruction>);
+fn parse_instruction<I: Integer>(
+    line: &str,
+) -> Result<Instruction<I>, peg::error::ParseError<peg::str::LineCol>> {
+    instruction_parser::instruction(line, |n| n.parse().ok())
+}
 
-fn main() {
-    let mut program = Program(
-        std::io::stdin()
-            .lock()
-            .lines()
-            .map(Result::unwrap)
-            .map(|line| instruction_parser::instruction(line.as_str()).unwrap())
-            .collect(),
-    );
+struct Program<I>(Vec<Instruction<I>>);
 
-    println!("{}", exercise_1(&program));
-    println!("{}", exercise_2(&mut program));
+fn main() -> anyhow::Result<()> {
+    run_checked::<Int, _>(Solve)
+}
+
+struct Solve;
+
+impl Solver for Solve {
+    type Output = anyhow::Result<()>;
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let mut program: Program<I> = Program(
+            std::io::stdin()
+                .lock()
+                .lines()
+                .map(|line| Ok(parse_instruction(&line?)?))
+                .collect::<anyhow::Result<_>>()?,
+        );
+
+        println!("{}", exercise_1(&program));
+        println!("{}", exercise_2(&mut program));
+        Ok(())
+    }
 }
 
 #[test]
 fn test_parser() {
     assert_eq!(
-        instruction_parser::instruction("nop +20").unwrap(),
+        parse_instruction::<Int>("nop +20").unwrap(),
         Instruction::Nop(20)
     );
     assert_eq!(
-        instruction_parser::instruction("nop -99").unwrap(),
+        parse_instruction::<Int>("nop -99").unwrap(),
         Instruction::Nop(-99)
     );
     assert_eq!(
-        instruction_parser::instruction("acc +10").unwrap(),
+        parse_instruction::<Int>("acc +10").unwrap(),
         Instruction::Acc(10)
     );
     assert_eq!(
-        instruction_parser::instruction("acc -20").unwrap(),
+        parse_instruction::<Int>("acc -20").unwrap(),
         Instruction::Acc(-20)
     );
     assert_eq!(
-        instruction_parser::instruction("jmp +42").unwrap(),
+        parse_instruction::<Int>("jmp +42").unwrap(),
         Instruction::Jmp(42)
     );
     assert_eq!(
-        instruction_parser::instruction("jmp -1337").unwrap(),
+        parse_instruction::<Int>("jmp -1337").unwrap(),
         Instruction::Jmp(-1337)
     );
+    assert!(parse_instruction::<Int>("jmp +2147483648").is_err());
+    assert_eq!(
+        parse_instruction::<i64>("acc +2147483648").unwrap(),
+        Instruction::Acc(2147483648)
+    );
+    assert!(parse_instruction::<u8>("acc -1").is_err());
 }
 
 #[test]
 fn test_exo_1() {
-    let program = Program(
+    let program: Program<Int> = Program(
         vec![
             "nop +0", "acc +1", "jmp +4", "acc +3", "jmp -3", "acc -99", "acc +1", "jmp -4",
             "acc +6",
         ]
         .iter()
-        .map(|line| instruction_parser::instruction(line).unwrap())
+        .map(|line| parse_instruction(line).unwrap())
         .collect(),
     );
     assert_eq!(exercise_1(&program), 5);
@@ -251,14 +218,26 @@ fn test_exo_1() {
 
 #[test]
 fn test_exo_2() {
-    let mut program = Program(
+    let mut program: Program<Int> = Program(
         vec![
             "nop +0", "acc +1", "jmp +4", "acc +3", "jmp -3", "acc -99", "acc +1", "jmp -4",
             "acc +6",
         ]
         .iter()
-        .map(|line| instruction_parser::instruction(line).unwrap())
+        .map(|line| parse_instruction(line).unwrap())
         .collect(),
     );
     assert_eq!(exercise_2(&mut program), 8);
 }
+
+#[test]
+#[should_panic(expected = "100 + 100 overflows i8")]
+fn test_checked() {
+    let program: Program<Checked<i8>> = Program(
+        ["acc +100", "acc +100"]
+            .iter()
+            .map(|line| parse_instruction(line).unwrap())
+            .collect(),
+    );
+    run_program_until_loop_or_end(&program);
+}
@@ -1,38 +1,75 @@
+use std::collections::HashMap;
 use std::io::BufRead;
 
 type Range = std::ops::Range<usize>;
 
-// use advent_of_code_2020::RingVec;
+use advent_of_code_2020::RingVec;
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::integer::{run_checked, Integer, Solver};
 
 type Int = i64;
 
-fn contains(tab: &[Int], elt: &Int) -> bool {
-    tab.iter().filter(|x| *x == elt).count() > 0
+// Keeps only the last `size_prelude` numbers and how many times each one appears
+struct XmasValidator<I> {
+    window: RingVec<I>,
+    counts: HashMap<I, usize>,
 }
 
-fn exercise_1(numbers: &[Int], size_prelude: usize) -> Option<Int> {
-    let mut combinations: Vec<Vec<Int>> = Vec::new();
+impl<I: Integer> XmasValidator<I> {
+    fn new(size_prelude: usize) -> Self {
+        XmasValidator {
+            window: RingVec::with_capacity(size_prelude),
+            counts: HashMap::new(),
+        }
+    }
 
-    for i in 0..numbers.len() {
-        combinations.push(Vec::new());
-        let current_number = numbers[i];
-        let mut is_sum = i < size_prelude;
-        for j in 1..(size_prelude + 1).min(i + 1) {
-            let index = i - j;
-            is_sum |= contains(&combinations[index], &current_number);
+    // A partner out of the range of I cannot be in the window
+    fn is_sum_of_two(&self, number: &I) -> bool {
+        self.window.iter().any(|x| match number.checked_sub(x) {
+            Some(other) => {
+                let needed = if other == *x { 2 } else { 1 };
+                self.counts.get(&other).copied().unwrap_or(0) >= needed
+            }
+            None => false,
+        })
+    }
 
-            combinations[index].push(numbers[i - j] + current_number);
-        }
-        if !is_sum {
-            return Some(current_number);
+    // Validates the number against the window and then adds it to the window
+    fn push(&mut self, number: I) -> bool {
+        let valid = !self.window.is_full() || self.is_sum_of_two(&number);
+        if let Some(evicted) = self.window.push(number.clone()) {
+            let count = self.counts.get_mut(&evicted).unwrap();
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&evicted);
+            }
         }
+        *self.counts.entry(number).or_insert(0) += 1;
+        valid
     }
+}
 
-    None
+// (index, number) of every number which is not the sum of two of the previous ones
+fn invalid_numbers<I: Integer>(
+    numbers: impl IntoIterator<Item = I>,
+    size_prelude: usize,
+) -> impl Iterator<Item = (usize, I)> {
+    let mut validator = XmasValidator::new(size_prelude);
+    numbers
+        .into_iter()
+        .enumerate()
+        .filter(move |(_, number)| !validator.push(number.clone()))
+}
+
+fn exercise_1<I: Integer>(numbers: &[I], size_prelude: usize) -> Option<I> {
+    invalid_numbers(numbers.iter().cloned(), size_prelude)
+        .next()
+        .map(|(_, number)| number)
 }
 
-fn find_range(numbers: &[Int], target: Int) -> Option<Range> {
-    let mut current_sum = numbers[0] + numbers[1];
+fn find_range<I: Integer>(numbers: &[I], target: I) -> Option<Range> {
+    let mut current_sum = numbers[0].clone() + numbers[1].clone();
 
     let mut current_range = Range { start: 0, end: 2 };
 
@@ -40,11 +77,11 @@ fn find_range(numbers: &[Int], target: Int) -> Option<Range> {
         match target.cmp(&current_sum) {
             std::cmp::Ordering::Equal => return Some(current_range),
             std::cmp::Ordering::Greater => {
-                current_sum += numbers[current_range.end];
+                current_sum += numbers[current_range.end].clone();
                 current_range.end += 1;
             }
             std::cmp::Ordering::Less => {
-                current_sum -= numbers[current_range.start];
+                current_sum -= numbers[current_range.start].clone();
                 current_range.start += 1;
             }
         }
@@ -53,24 +90,46 @@ fn find_range(numbers: &[Int], target: Int) -> Option<Range> {
     None
 }
 
-fn exercise_2(numbers: &[Int], size_prelude: usize) -> Option<Int> {
+fn exercise_2<I: Integer>(numbers: &[I], size_prelude: usize) -> Option<I> {
     let target = exercise_1(numbers, size_prelude)?;
     let range = find_range(numbers, target)?;
 
-    Some(numbers[range.clone()].iter().copied().min()? + numbers[range].iter().copied().max()?)
+    Some(numbers[range.clone()].iter().min()?.clone() + numbers[range].iter().max()?.clone())
 }
 
 fn main() {
-    let numbers: Vec<Int> = std::io::stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .map(|x| x.parse::<Int>())
-        .map(Result::unwrap)
-        .collect();
-
-    println!("{}", exercise_1(&numbers, 25).unwrap());
-    println!("{}", exercise_2(&numbers, 25).unwrap());
+    run_checked::<Int, _>(Solve)
+}
+
+struct Solve;
+
+impl Solver for Solve {
+    type Output = ();
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        if std::env::args().any(|arg| arg == "--validate") {
+            let stdin = std::io::stdin();
+            let numbers = stdin
+                .lock()
+                .lines()
+                .map(|x| x.unwrap().parse::<I>().unwrap());
+            for (index, number) in invalid_numbers(numbers, 25) {
+                println!("{}: {}", index, number);
+            }
+            return;
+        }
+
+        let numbers: Vec<I> = std::io::stdin()
+            .lock()
+            .lines()
+            .map(Result::unwrap)
+            .map(|x| x.parse::<I>())
+            .map(Result::unwrap)
+            .collect();
+
+        println!("{}", exercise_1(&numbers, 25).unwrap());
+        println!("{}", exercise_2(&numbers, 25).unwrap());
+    }
 }
 
 #[test]
@@ -87,3 +146,54 @@ fn test_exo_2() {
     ];
     assert_eq!(exercise_2(&numbers, 5), Some(62));
 }
+
+#[test]
+fn test_invalid_numbers() {
+    let numbers = vec![
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ];
+    assert_eq!(
+        invalid_numbers(numbers, 5).collect::<Vec<_>>(),
+        vec![(14, 127)]
+    );
+
+    // 20 is the sum of the two 10, but there is only one 20 to make 40
+    assert_eq!(
+        invalid_numbers(vec![10, 10, 20, 30, 40, 60], 2).collect::<Vec<_>>(),
+        vec![(4, 40), (5, 60)]
+    );
+}
+
+#[test]
+fn test_unsigned() {
+    // The window holds numbers larger than the one checked
+    let numbers = [5, 1, 3, 4].map(Checked::<u8>);
+    assert_eq!(
+        invalid_numbers(numbers, 2).collect::<Vec<_>>(),
+        vec![(2, Checked(3))]
+    );
+    let numbers = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ]
+    .map(Checked::<u64>);
+    assert_eq!(exercise_1(&numbers, 5), Some(Checked(127)));
+    assert_eq!(exercise_2(&numbers, 5), Some(Checked(62)));
+}
+
+#[test]
+fn test_bignum() {
+    use advent_of_code_common::integer::BigUint;
+    let numbers = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ]
+    .map(|n: u32| BigUint::from(n));
+    assert_eq!(exercise_1(&numbers, 5), Some(BigUint::from(127u32)));
+    assert_eq!(exercise_2(&numbers, 5), Some(BigUint::from(62u32)));
+}
+
+#[test]
+#[should_panic(expected = "9223372036854775807 + 1 overflows i64")]
+fn test_checked() {
+    let numbers = [i64::MAX, 1, 5].map(Checked);
+    exercise_2(&numbers, 2);
+}
@@ -1 +1,4 @@
+pub mod matching;
+mod ring_vec;
 
+pub use ring_vec::RingVec;
@@ -0,0 +1,367 @@
+use std::collections::VecDeque;
+
+const FREE: usize = usize::MAX;
+
+/// Assignment of every left element to a distinct right element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matching {
+    pub left_to_right: Vec<usize>,
+    pub unique: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchingError {
+    /// A candidate points outside of `0..right_size`
+    InvalidCandidate { left: usize, right: usize },
+    /// No assignment can give a right element to every left one, those left elements stay alone
+    NoPerfectMatching { unmatched: Vec<usize> },
+}
+
+impl std::fmt::Display for MatchingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchingError::InvalidCandidate { left, right } => {
+                write!(
+                    f,
+                    "left element {} has an invalid candidate {}",
+                    left, right
+                )
+            }
+            MatchingError::NoPerfectMatching { unmatched } => write!(
+                f,
+                "no consistent assignment exists, {} element(s) cannot be matched: {:?}",
+                unmatched.len(),
+                unmatched
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MatchingError {}
+
+/// Maximum matching of the bipartite graph `left -> candidates[left]` with Hopcroft-Karp.
+pub fn hopcroft_karp(candidates: &[Vec<usize>], right_size: usize) -> Vec<Option<usize>> {
+    let left_size = candidates.len();
+    let mut left_match = vec![FREE; left_size];
+    let mut right_match = vec![FREE; right_size];
+    let mut layer = vec![0; left_size];
+
+    while bfs_layers(candidates, &left_match, &right_match, &mut layer) {
+        for left in 0..left_size {
+            if left_match[left] == FREE {
+                augment(
+                    left,
+                    candidates,
+                    &mut left_match,
+                    &mut right_match,
+                    &mut layer,
+                );
+            }
+        }
+    }
+
+    left_match
+        .into_iter()
+        .map(|right| if right == FREE { None } else { Some(right) })
+        .collect()
+}
+
+// Layers the free left vertices at 0, returns true if an augmenting path exists
+fn bfs_layers(
+    candidates: &[Vec<usize>],
+    left_match: &[usize],
+    right_match: &[usize],
+    layer: &mut [usize],
+) -> bool {
+    let mut queue = VecDeque::new();
+    for (left, right) in left_match.iter().enumerate() {
+        if *right == FREE {
+            layer[left] = 0;
+            queue.push_back(left);
+        } else {
+            layer[left] = usize::MAX;
+        }
+    }
+
+    let mut found = false;
+    while let Some(left) = queue.pop_front() {
+        for &right in &candidates[left] {
+            let next = right_match[right];
+            if next == FREE {
+                found = true;
+            } else if layer[next] == usize::MAX {
+                layer[next] = layer[left] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    found
+}
+
+fn augment(
+    left: usize,
+    candidates: &[Vec<usize>],
+    left_match: &mut [usize],
+    right_match: &mut [usize],
+    layer: &mut [usize],
+) -> bool {
+    for &right in &candidates[left] {
+        let next = right_match[right];
+        if next == FREE
+            || (layer[next] == layer[left] + 1
+                && augment(next, candidates, left_match, right_match, layer))
+        {
+            left_match[left] = right;
+            right_match[right] = left;
+            return true;
+        }
+    }
+    // Dead end, no need to come back here during this phase
+    layer[left] = usize::MAX;
+    false
+}
+
+/// Finds a matching covering every left element and tells if it is the only one.
+pub fn perfect_matching(
+    candidates: &[Vec<usize>],
+    right_size: usize,
+) -> Result<Matching, MatchingError> {
+    for (left, rights) in candidates.iter().enumerate() {
+        if let Some(&right) = rights.iter().find(|right| **right >= right_size) {
+            return Err(MatchingError::InvalidCandidate { left, right });
+        }
+    }
+
+    let matching = hopcroft_karp(candidates, right_size);
+    let unmatched: Vec<usize> = matching
+        .iter()
+        .enumerate()
+        .filter(|(_, right)| right.is_none())
+        .map(|(left, _)| left)
+        .collect();
+    if !unmatched.is_empty() {
+        return Err(MatchingError::NoPerfectMatching { unmatched });
+    }
+
+    let left_to_right: Vec<usize> = matching.into_iter().map(Option::unwrap).collect();
+    let unique = is_unique(candidates, &left_to_right, right_size);
+    Ok(Matching {
+        left_to_right,
+        unique,
+    })
+}
+
+// Another perfect matching exists iff a left element can move to a free right element,
+// or the "owner of r can move to r'" graph over the right elements has a cycle.
+fn is_unique(candidates: &[Vec<usize>], left_to_right: &[usize], right_size: usize) -> bool {
+    let mut owner = vec![FREE; right_size];
+    for (left, right) in left_to_right.iter().enumerate() {
+        owner[*right] = left;
+    }
+
+    if candidates
+        .iter()
+        .flatten()
+        .any(|right| owner[*right] == FREE)
+    {
+        return false;
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Color {
+        White,
+        Grey,
+        Black,
+    }
+    let mut color = vec![Color::White; right_size];
+
+    for start in left_to_right {
+        if color[*start] != Color::White {
+            continue;
+        }
+        // (right element, index of the next candidate to explore)
+        let mut stack = vec![(*start, 0)];
+        color[*start] = Color::Grey;
+        while let Some((right, next_candidate)) = stack.pop() {
+            let left = owner[right];
+            match candidates[left].get(next_candidate) {
+                None => color[right] = Color::Black,
+                Some(&next) => {
+                    stack.push((right, next_candidate + 1));
+                    if next == right {
+                        continue;
+                    }
+                    match color[next] {
+                        Color::Grey => return false,
+                        Color::White => {
+                            color[next] = Color::Grey;
+                            stack.push((next, 0));
+                        }
+                        Color::Black => (),
+                    }
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Assignments covering the left elements
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct Matchings {
+    /// The first assignments in lexicographic order of `left_to_right`
+    pub listed: Vec<Vec<usize>>,
+    /// Every assignment, listed or not
+    pub count: u64,
+}
+
+/// Counts every assignment covering the left elements, listing at most `limit` of them
+/// (all of them without a limit).
+pub fn all_perfect_matchings(
+    candidates: &[Vec<usize>],
+    right_size: usize,
+    limit: Option<usize>,
+) -> Matchings {
+    let mut matchings = Matchings::default();
+    let mut current = Vec::with_capacity(candidates.len());
+    let mut taken = vec![false; right_size];
+    enumerate_matchings(
+        candidates,
+        &mut current,
+        &mut taken,
+        &mut matchings,
+        limit.unwrap_or(usize::MAX),
+    );
+    matchings
+}
+
+// Only goes down branches that still lead to an assignment, so the time is polynomial
+// in the number of assignments found
+fn enumerate_matchings(
+    candidates: &[Vec<usize>],
+    current: &mut Vec<usize>,
+    taken: &mut [bool],
+    matchings: &mut Matchings,
+    limit: usize,
+) {
+    let left = current.len();
+    if left == candidates.len() {
+        matchings.count = matchings.count.saturating_add(1);
+        if matchings.listed.len() < limit {
+            matchings.listed.push(current.clone());
+        }
+        return;
+    }
+    let mut rights = candidates[left].clone();
+    rights.sort_unstable();
+    for right in rights {
+        if right < taken.len() && !taken[right] {
+            taken[right] = true;
+            if can_complete(&candidates[left + 1..], taken) {
+                current.push(right);
+                enumerate_matchings(candidates, current, taken, matchings, limit);
+                current.pop();
+            }
+            taken[right] = false;
+        }
+    }
+}
+
+fn can_complete(candidates: &[Vec<usize>], taken: &[bool]) -> bool {
+    let free = candidates
+        .iter()
+        .map(|rights| {
+            rights
+                .iter()
+                .copied()
+                .filter(|right| *right < taken.len() && !taken[*right])
+                .collect()
+        })
+        .collect::<Vec<_>>();
+    hopcroft_karp(&free, taken.len())
+        .iter()
+        .all(Option::is_some)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unique_matching() {
+        let candidates = vec![vec![0, 1, 2], vec![1], vec![1, 2]];
+        assert_eq!(
+            perfect_matching(&candidates, 3),
+            Ok(Matching {
+                left_to_right: vec![0, 1, 2],
+                unique: true
+            })
+        );
+    }
+
+    #[test]
+    fn test_ambiguous_matching() {
+        let candidates = vec![vec![0, 1], vec![0, 1], vec![2]];
+        let matching = perfect_matching(&candidates, 3).unwrap();
+        assert!(!matching.unique);
+        assert_eq!(matching.left_to_right[2], 2);
+
+        // The first element can also take the spare right element
+        let candidates = vec![vec![0, 2], vec![1]];
+        assert!(!perfect_matching(&candidates, 3).unwrap().unique);
+
+        let candidates = vec![vec![0], vec![1]];
+        assert!(perfect_matching(&candidates, 3).unwrap().unique);
+    }
+
+    #[test]
+    fn test_all_perfect_matchings() {
+        let candidates = vec![vec![1, 0], vec![0, 1], vec![2]];
+        assert_eq!(
+            all_perfect_matchings(&candidates, 3, None),
+            Matchings {
+                listed: vec![vec![0, 1, 2], vec![1, 0, 2]],
+                count: 2
+            }
+        );
+        assert_eq!(
+            all_perfect_matchings(&candidates, 3, Some(1)),
+            Matchings {
+                listed: vec![vec![0, 1, 2]],
+                count: 2
+            }
+        );
+        assert_eq!(
+            all_perfect_matchings(&[vec![0], vec![0]], 1, None),
+            Matchings::default()
+        );
+        // 2^10 assignments, only the first ones are listed
+        let pairs = (0..20)
+            .map(|left| vec![left / 2 * 2, left / 2 * 2 + 1])
+            .collect::<Vec<_>>();
+        let matchings = all_perfect_matchings(&pairs, 20, Some(3));
+        assert_eq!((matchings.listed.len(), matchings.count), (3, 1 << 10));
+    }
+
+    #[test]
+    fn test_no_matching() {
+        let candidates = vec![vec![0], vec![0], vec![0, 1, 2]];
+        assert_eq!(
+            perfect_matching(&candidates, 3),
+            Err(MatchingError::NoPerfectMatching { unmatched: vec![1] })
+        );
+        assert_eq!(
+            perfect_matching(&[vec![3]], 3),
+            Err(MatchingError::InvalidCandidate { left: 0, right: 3 })
+        );
+    }
+
+    #[test]
+    fn test_hopcroft_karp_needs_augmenting_paths() {
+        // Greedy would give 0 -> 0 and get stuck
+        let candidates = vec![vec![0, 1], vec![0], vec![1, 2], vec![2, 3]];
+        let matching = hopcroft_karp(&candidates, 4);
+        assert!(matching.iter().all(Option::is_some));
+        assert_eq!(matching[1], Some(0));
+    }
+}
@@ -0,0 +1,94 @@
+/// Fixed capacity buffer keeping the last `capacity` pushed values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RingVec<T> {
+    data: Vec<T>,
+    // Index of the oldest value once the buffer is full
+    start: usize,
+    capacity: usize,
+}
+
+impl<T> RingVec<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        RingVec {
+            data: Vec::with_capacity(capacity),
+            start: 0,
+            capacity,
+        }
+    }
+
+    /// Adds a value, returns the one it evicts if the buffer was full.
+    pub fn push(&mut self, value: T) -> Option<T> {
+        if self.capacity == 0 {
+            return Some(value);
+        }
+        if self.data.len() < self.capacity {
+            self.data.push(value);
+            return None;
+        }
+        let evicted = std::mem::replace(&mut self.data[self.start], value);
+        self.start = (self.start + 1) % self.capacity;
+        Some(evicted)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.data.len() == self.capacity
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The `index`-th value, starting from the oldest one.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.data.len() {
+            Some(&self.data[(self.start + index) % self.data.len()])
+        } else {
+            None
+        }
+    }
+
+    /// Iterates from the oldest value to the newest one.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let (newest, oldest) = self.data.split_at(self.start);
+        oldest.iter().chain(newest.iter())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_push() {
+        let mut ring = RingVec::with_capacity(3);
+        assert!(ring.is_empty());
+        assert_eq!(ring.push(1), None);
+        assert_eq!(ring.push(2), None);
+        assert!(!ring.is_full());
+        assert_eq!(ring.push(3), None);
+        assert!(ring.is_full());
+        assert_eq!(ring.push(4), Some(1));
+        assert_eq!(ring.push(5), Some(2));
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(ring.get(0), Some(&3));
+        assert_eq!(ring.get(2), Some(&5));
+        assert_eq!(ring.get(3), None);
+    }
+
+    #[test]
+    fn test_empty_capacity() {
+        let mut ring = RingVec::with_capacity(0);
+        assert_eq!(ring.push(1), Some(1));
+        assert!(ring.is_empty());
+        assert_eq!(ring.iter().count(), 0);
+    }
+}
@@ -1,7 +1,5 @@
 use std::io::{stdin, BufRead};
 
-use itertools::Itertools;
-
 #[derive(Clone)]
 pub enum Movement {
     Horizontal(i64),
@@ -29,31 +27,29 @@ fn ex2(movements: &[Movement]) -> i64 {
     x * y
 }
 
-peg::parser!(
+advent_of_code_common::parser!(
     grammar movement_parser() for str {
-        rule number() -> i64
-        = n:$(['0'..='9']+) {? n.parse().or(Err("i64")) }
         rule forward() -> Movement
-            = "forward " n:number() {Movement::Horizontal(n)}
+            = "forward " n:i64() {Movement::Horizontal(n)}
         rule up() -> Movement
-            = "up " n:number() {Movement::Vertical(-n)}
+            = "up " n:i64() {Movement::Vertical(-n)}
         rule down() -> Movement
-            = "down " n:number() {Movement::Vertical(n)}
+            = "down " n:i64() {Movement::Vertical(n)}
 
         pub rule movement() -> Movement = m:(forward() / up() / down()) {m}
     }
 );
 
-fn main() {
-    let instructions = stdin()
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let instructions: Vec<Movement> = stdin()
         .lock()
         .lines()
-        .map(Result::unwrap)
-        .map(|x| movement_parser::movement(&x).unwrap())
-        .collect_vec();
+        .map(|x| Ok(movement_parser::movement(&x?)?))
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
 
     println!("{}", ex1(&instructions));
     println!("{}", ex2(&instructions));
+    Ok(())
 }
 
 #[test]
@@ -1,85 +1,257 @@
-use std::io::{stdin, BufRead};
+use std::{
+    fmt,
+    io::{stdin, BufRead},
+};
 
+use advent_of_code_common::bits::{BitVec, Columns, BINARY};
 use itertools::Itertools;
+use num::BigUint;
 
-fn bool_to_u128(data: &[bool]) -> u128 {
-    let mut x = 0;
-    for b in data {
-        x <<= 1;
-        x += *b as u128;
-    }
-    x
+// The first bit of the row is the most significant one
+fn value(row: &BitVec) -> BigUint {
+    let digits = row.iter().map(|one| one as u8).collect_vec();
+    BigUint::from_radix_be(&digits, 2).unwrap_or_default()
 }
 
-fn ex2(data: &[Vec<bool>]) -> u128 {
-    let mut data_oxygen = data.to_vec();
-    let mut i = 0;
+// Rows as read, and also by column to count the ones among the survivors
+struct Diagnostic {
+    rows: Vec<BitVec>,
+    columns: Columns,
+}
 
-    while data_oxygen.len() > 1 {
-        let count: usize = data_oxygen.iter().map(|b| b[i] as usize).sum();
-        let filter = 2 * count >= data_oxygen.len();
-        data_oxygen = data_oxygen.into_iter().filter(|b| b[i] == filter).collect();
-        i += 1;
+#[derive(Debug, PartialEq)]
+enum ReportError {
+    Empty,
+    // Every row was filtered out at this bit position
+    NoSurvivor { position: usize },
+    // The rows left after the last bit position are all equal
+    SeveralSurvivors { survivors: usize },
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Empty => write!(f, "no diagnostic row"),
+            ReportError::NoSurvivor { position } => {
+                write!(f, "no row survives the filter on bit {}", position)
+            }
+            ReportError::SeveralSurvivors { survivors } => {
+                write!(f, "{} identical rows survive every bit position", survivors)
+            }
+        }
     }
-    let oxygen = bool_to_u128(&data_oxygen[0]);
+}
+
+impl std::error::Error for ReportError {}
 
-    let mut data_co2 = data.to_vec();
-    let mut i = 0;
+fn parse_rows(lines: &[String]) -> Result<Diagnostic, String> {
+    let rows: Vec<BitVec> = lines
+        .iter()
+        .map(|line| BitVec::parse(line, &BINARY).map_err(|e| format!("{:?}: {}", line, e)))
+        .try_collect()?;
+    let columns = Columns::new(&rows).map_err(|e| e.to_string())?;
+    Ok(Diagnostic { rows, columns })
+}
 
-    while data_co2.len() > 1 {
-        let count: usize = data_co2.iter().map(|b| b[i] as usize).sum();
-        let filter = 2 * count >= data_co2.len();
-        data_co2 = data_co2.into_iter().filter(|b| b[i] != filter).collect();
-        i += 1;
+fn width(diagnostic: &Diagnostic) -> Result<usize, ReportError> {
+    if diagnostic.rows.is_empty() {
+        return Err(ReportError::Empty);
     }
-    let co2 = bool_to_u128(&data_co2[0]);
-    oxygen * co2
+    Ok(diagnostic.columns.width())
+}
+
+#[derive(Clone, Copy)]
+enum Keep {
+    MostCommon,
+    LeastCommon,
 }
 
-fn ex1(data: &[Vec<bool>]) -> u128 {
-    let mut epsilon = 0;
-    let mut gamma = 0;
+#[derive(Clone, Copy)]
+struct Criterion {
+    keep: Keep,
+    // Bit kept when there are as many 0 as 1
+    on_tie: bool,
+}
 
-    let mut counter = Vec::new();
-    counter.resize(data[0].len(), 0);
+const OXYGEN: Criterion = Criterion {
+    keep: Keep::MostCommon,
+    on_tie: true,
+};
+const CO2: Criterion = Criterion {
+    keep: Keep::LeastCommon,
+    on_tie: false,
+};
 
-    for str in data {
-        for i in 0..str.len() {
-            counter[i] += str[i] as usize;
+impl Criterion {
+    fn bit(&self, ones: usize, total: usize) -> bool {
+        let zeros = total - ones;
+        if ones == zeros {
+            return self.on_tie;
+        }
+        match self.keep {
+            Keep::MostCommon => ones > zeros,
+            Keep::LeastCommon => ones < zeros,
         }
     }
-    let n = data.len();
-    for i in counter {
-        epsilon <<= 1;
-        gamma <<= 1;
-        if i < n / 2 {
-            epsilon += 1;
+}
+
+#[derive(Debug, PartialEq)]
+struct Step {
+    position: usize,
+    kept: bool,
+    survivors: usize,
+}
+
+// Filters the rows bit after bit until only one is left
+fn reduce(
+    diagnostic: &Diagnostic,
+    criterion: Criterion,
+) -> Result<(&BitVec, Vec<Step>), ReportError> {
+    let width = width(diagnostic)?;
+    let columns = &diagnostic.columns;
+    let mut survivors = BitVec::ones(columns.height());
+    let mut steps = vec![];
+    for position in 0..width {
+        let total = survivors.count_ones();
+        if total == 1 {
+            break;
+        }
+        let kept = criterion.bit(columns.ones(position, &survivors), total);
+        survivors = if kept {
+            columns.column(position) & &survivors
         } else {
-            gamma += 1;
+            &!columns.column(position) & &survivors
+        };
+        let left = survivors.count_ones();
+        steps.push(Step {
+            position,
+            kept,
+            survivors: left,
+        });
+        if left == 0 {
+            return Err(ReportError::NoSurvivor { position });
+        }
+    }
+    match survivors.iter_ones().collect_vec()[..] {
+        [row] => Ok((&diagnostic.rows[row], steps)),
+        _ => Err(ReportError::SeveralSurvivors {
+            survivors: survivors.count_ones(),
+        }),
+    }
+}
+
+// Most common bit of every column, then its complement
+fn gamma_epsilon(diagnostic: &Diagnostic) -> Result<(BitVec, BitVec), ReportError> {
+    let width = width(diagnostic)?;
+    let columns = &diagnostic.columns;
+    let all = BitVec::ones(columns.height());
+    let mut gamma = BitVec::new(width);
+    for position in 0..width {
+        gamma.set(
+            position,
+            OXYGEN.bit(columns.ones(position, &all), columns.height()),
+        );
+    }
+    let epsilon = !&gamma;
+    Ok((gamma, epsilon))
+}
+
+fn ex1(diagnostic: &Diagnostic) -> Result<BigUint, ReportError> {
+    let (gamma, epsilon) = gamma_epsilon(diagnostic)?;
+    Ok(value(&gamma) * value(&epsilon))
+}
+
+fn ex2(diagnostic: &Diagnostic) -> Result<BigUint, ReportError> {
+    let (oxygen, _) = reduce(diagnostic, OXYGEN)?;
+    let (co2, _) = reduce(diagnostic, CO2)?;
+    Ok(value(oxygen) * value(co2))
+}
+
+fn report(diagnostic: &Diagnostic) -> Result<String, ReportError> {
+    let (gamma, epsilon) = gamma_epsilon(diagnostic)?;
+    let mut report = format!("gamma {} = {}\n", gamma, value(&gamma));
+    report += &format!("epsilon {} = {}\n", epsilon, value(&epsilon));
+    for (name, criterion) in [("oxygen", OXYGEN), ("co2", CO2)] {
+        report += &format!("{}: {} rows\n", name, diagnostic.rows.len());
+        let (row, steps) = reduce(diagnostic, criterion)?;
+        for step in steps {
+            report += &format!(
+                "  bit {}: keep {}, {} left\n",
+                step.position, step.kept as u8, step.survivors
+            );
         }
+        report += &format!("  {} = {}\n", row, value(row));
+    }
+    Ok(report)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let lines = stdin().lock().lines().map(Result::unwrap).collect_vec();
+    let diagnostic = parse_rows(&lines)?;
+    if std::env::args().any(|arg| arg == "--report") {
+        print!("{}", report(&diagnostic)?);
+        return Ok(());
     }
-    epsilon * gamma
+    println!("{}", ex1(&diagnostic)?);
+    println!("{}", ex2(&diagnostic)?);
+    Ok(())
 }
 
-fn main() {
-    let data = stdin()
-        .lock()
-        .lines()
-        .map(Result::unwrap)
-        .map(|x| x.chars().map(|y| y == '1').collect_vec())
-        .collect_vec();
-    println!("{}", ex1(&data));
-    println!("{}", ex2(&data));
+#[cfg(test)]
+fn rows(lines: &[&str]) -> Diagnostic {
+    parse_rows(&lines.iter().map(|x| x.to_string()).collect_vec()).unwrap()
 }
 
 #[test]
 fn test_ex() {
-    let data = [
+    let data = rows(&[
         "00100", "11110", "10110", "10111", "10101", "01111", "00111", "11100", "10000", "11001",
         "00010", "01010",
-    ]
-    .map(|x| x.chars().map(|y| y == '1').collect_vec());
+    ]);
+
+    assert_eq!(ex1(&data), Ok(BigUint::from(198u32)));
+    assert_eq!(ex2(&data), Ok(BigUint::from(230u32)));
+
+    let (oxygen, steps) = reduce(&data, OXYGEN).unwrap();
+    assert_eq!(oxygen.to_string(), "10111");
+    assert_eq!(
+        steps.iter().map(|step| step.survivors).collect_vec(),
+        vec![7, 4, 3, 2, 1]
+    );
+    let (co2, steps) = reduce(&data, CO2).unwrap();
+    assert_eq!(co2.to_string(), "01010");
+    assert_eq!(
+        steps[0],
+        Step {
+            position: 0,
+            kept: false,
+            survivors: 5
+        }
+    );
+    assert_eq!(steps.len(), 3);
+}
 
-    assert_eq!(ex1(&data), 198);
-    assert_eq!(ex2(&data), 230);
+#[test]
+fn test_wide_rows() {
+    let ones = "1".repeat(130);
+    let data = rows(&[&ones, &"0".repeat(130)]);
+    let (oxygen, _) = reduce(&data, OXYGEN).unwrap();
+    assert_eq!(value(oxygen), (BigUint::from(1u32) << 130usize) - 1u32);
+    assert_eq!(ex1(&data), Ok(BigUint::from(0u32)));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(ex1(&rows(&[])), Err(ReportError::Empty));
+    assert_eq!(ex2(&rows(&[])), Err(ReportError::Empty));
+    assert_eq!(
+        reduce(&rows(&["101", "101"]), OXYGEN),
+        Err(ReportError::SeveralSurvivors { survivors: 2 })
+    );
+    assert_eq!(
+        reduce(&rows(&["101", "111"]), CO2),
+        Err(ReportError::NoSurvivor { position: 0 })
+    );
+    assert!(parse_rows(&["101".to_string(), "10".to_string()]).is_err());
+    assert!(parse_rows(&["1x1".to_string()]).is_err());
 }
@@ -1,144 +1,176 @@
-use std::io::{stdin, BufRead};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{stdin, BufRead},
+};
 
+use advent_of_code_common::grid::{Grid, Position};
 use itertools::Itertools;
 
 #[derive(Clone)]
-enum GridContent {
-    Open(u32),
-    Closed(u32),
+struct Board {
+    numbers: Grid<u32>,
+    marked: Grid<bool>,
+    marked_in_row: Vec<usize>,
+    marked_in_column: Vec<usize>,
+    // Main diagonal and anti-diagonal, only used on square boards
+    marked_in_diagonal: [usize; 2],
+    unmarked_sum: u64,
 }
 
-#[derive(Clone)]
-struct Grid {
-    matrix: Vec<Vec<GridContent>>,
-}
-
-impl Grid {
-    fn from_str(data: &[String]) -> Self {
-        Grid {
-            matrix: data
-                .iter()
-                .map(|str| {
-                    str.split_ascii_whitespace()
-                        .map(str::parse::<u32>)
-                        .map(Result::unwrap)
-                        .map(GridContent::Open)
-                        .collect_vec()
-                })
-                .collect_vec(),
-        }
+impl Board {
+    fn from_str(data: &[String]) -> Result<Self, String> {
+        let rows = data
+            .iter()
+            .map(|str| {
+                str.split_ascii_whitespace()
+                    .map(str::parse::<u32>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|error| format!("{:?}: {}", str, error))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let numbers = Grid::from_rows(rows).map_err(|error| format!("{:?}: {}", data, error))?;
+        Ok(Board {
+            marked: numbers.map(|_| false),
+            marked_in_row: vec![0; numbers.height()],
+            marked_in_column: vec![0; numbers.width()],
+            marked_in_diagonal: [0; 2],
+            unmarked_sum: numbers.values().map(|x| *x as u64).sum(),
+            numbers,
+        })
     }
 
-    fn is_won(&self) -> bool {
-        for i in 0..self.matrix.len() {
-            let mut won_segment = true;
-            for j in 0..self.matrix.len() {
-                if let GridContent::Open(_) = self.matrix[i][j] {
-                    won_segment = false;
-                }
-            }
-            if won_segment {
-                return true;
-            }
+    // Marks a cell and tells if it completes a line
+    fn mark(&mut self, cell: Position, diagonals: bool) -> bool {
+        if self.marked[cell] {
+            return false;
         }
-        for j in 0..self.matrix.len() {
-            let mut won_segment = true;
-            for i in 0..self.matrix.len() {
-                if let GridContent::Open(_) = self.matrix[i][j] {
-                    won_segment = false;
+        self.marked[cell] = true;
+        self.unmarked_sum -= self.numbers[cell] as u64;
+        let (i, j) = cell;
+        let (width, height) = (self.numbers.width(), self.numbers.height());
+        self.marked_in_row[i] += 1;
+        self.marked_in_column[j] += 1;
+        let mut won = self.marked_in_row[i] == width || self.marked_in_column[j] == height;
+        if diagonals && width == height {
+            for (diagonal, on_it) in [i == j, i + j == width - 1].into_iter().enumerate() {
+                if on_it {
+                    self.marked_in_diagonal[diagonal] += 1;
+                    won |= self.marked_in_diagonal[diagonal] == width;
                 }
             }
-            if won_segment {
-                return true;
-            }
         }
-        false
+        won
     }
-    fn all_unmarked(&self) -> Vec<u32> {
-        self.matrix
-            .iter()
-            .cloned()
-            .concat()
-            .iter()
-            .filter(|x| matches!(x, GridContent::Open(_)))
-            .map(|x| match x {
-                GridContent::Open(x) | GridContent::Closed(x) => *x,
-            })
-            .collect_vec()
+}
+
+#[derive(Debug, PartialEq)]
+struct Win {
+    board: usize,
+    number: u32,
+    score: u64,
+}
+
+struct Bingo {
+    boards: Vec<Board>,
+    // number -> (board, cell) for every cell holding it
+    positions: HashMap<u32, Vec<(usize, Position)>>,
+    won: Vec<bool>,
+    diagonals: bool,
+}
+
+impl Bingo {
+    fn new(boards: Vec<Board>, diagonals: bool) -> Self {
+        let mut positions: HashMap<u32, Vec<(usize, Position)>> = HashMap::new();
+        for (b, board) in boards.iter().enumerate() {
+            for (cell, number) in board.numbers.iter() {
+                positions.entry(*number).or_default().push((b, cell));
+            }
+        }
+        Bingo {
+            won: vec![false; boards.len()],
+            boards,
+            positions,
+            diagonals,
+        }
     }
-    fn remove_number(&mut self, x: u32) {
-        for i in 0..self.matrix.len() {
-            for j in 0..self.matrix.len() {
-                if let GridContent::Open(y) = self.matrix[i][j] {
-                    if y == x {
-                        self.matrix[i][j] = GridContent::Closed(x)
-                    }
-                }
+
+    // Boards winning with this number, in board order. A board wins only once, and is
+    // scored once every cell holding the number is marked.
+    fn call(&mut self, number: u32) -> Vec<Win> {
+        let mut winners = BTreeSet::new();
+        for (b, cell) in self.positions.get(&number).into_iter().flatten() {
+            if !self.won[*b] && self.boards[*b].mark(*cell, self.diagonals) {
+                winners.insert(*b);
             }
         }
+        winners
+            .into_iter()
+            .map(|b| {
+                self.won[b] = true;
+                Win {
+                    board: b,
+                    number,
+                    score: number as u64 * self.boards[b].unmarked_sum,
+                }
+            })
+            .collect()
+    }
+
+    fn wins(mut self, numbers: &[u32]) -> impl Iterator<Item = Win> + '_ {
+        numbers.iter().flat_map(move |number| self.call(*number))
     }
 }
 
-fn read_in(data: &[String]) -> (Vec<u32>, Vec<Grid>) {
-    let numbers = data[0]
+fn read_in(data: &[String]) -> Result<(Vec<u32>, Vec<Board>), String> {
+    let numbers = data
+        .first()
+        .ok_or("no drawn numbers")?
         .split(',')
         .map(str::parse::<u32>)
-        .map(Result::unwrap)
-        .collect_vec();
-    let mut grids = Vec::new();
-    for i in 0..(data.len() - 2) / 6 {
-        grids.push(Grid::from_str(&data[(2 + i * 6)..(1 + (i + 1) * 6)]));
-    }
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("drawn numbers: {}", error))?;
+    let boards = data[1..]
+        .split(|line| line.trim().is_empty())
+        .filter(|rows| !rows.is_empty())
+        .map(Board::from_str)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((numbers, boards))
+}
 
-    (numbers, grids)
+// Scores of the first and the last winning boards
+fn ex1(wins: &[Win]) -> Option<u64> {
+    wins.first().map(|win| win.score)
 }
 
-fn ex1(numbers: &[u32], mut grids: Vec<Grid>) -> u32 {
-    for number in numbers {
-        grids
-            .iter_mut()
-            .for_each(|grid| grid.remove_number(*number));
-        for grid in grids.iter() {
-            if grid.is_won() {
-                return number * (grid.all_unmarked().into_iter().sum::<u32>()) as u32;
-            }
-        }
-    }
-    unreachable!();
+fn ex2(wins: &[Win]) -> Option<u64> {
+    wins.last().map(|win| win.score)
 }
 
-fn ex2(numbers: &[u32], mut grids: Vec<Grid>) -> u32 {
-    for number in numbers {
-        grids
-            .iter_mut()
-            .for_each(|grid| grid.remove_number(*number));
-        grids = grids
-            .into_iter()
-            .filter(|grid| !grid.is_won())
-            .collect_vec();
-        if grids.len() == 1 {
-            break;
-        }
-    }
-    for number in numbers {
-        grids[0].remove_number(*number);
-        if grids[0].is_won() {
-            return number * (grids[0].all_unmarked().into_iter().sum::<u32>()) as u32;
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let data = stdin().lock().lines().collect::<Result<Vec<_>, _>>()?;
+    let (numbers, boards) = read_in(&data)?;
+    let args = std::env::args().skip(1).collect_vec();
+    let wins = Bingo::new(boards, args.iter().any(|arg| arg == "--diagonals"))
+        .wins(&numbers)
+        .collect_vec();
+
+    if args.iter().any(|arg| arg == "--events") {
+        for win in &wins {
+            println!(
+                "board {} wins on {} with score {}",
+                win.board, win.number, win.score
+            );
         }
+        return Ok(());
     }
-    unreachable!();
+    println!("{}", ex1(&wins).ok_or("no board wins")?);
+    println!("{}", ex2(&wins).ok_or("no board wins")?);
+    Ok(())
 }
 
-fn main() {
-    let data = stdin().lock().lines().map(Result::unwrap).collect_vec();
-    let (numbers, grids) = read_in(&data);
-    println!("{}", ex1(&numbers, grids.clone()));
-    println!("{}", ex2(&numbers, grids));
-}
-
-#[test]
-fn test_ex() {
-    let (numbers, grids) = read_in(
+#[cfg(test)]
+fn example() -> (Vec<u32>, Vec<Board>) {
+    read_in(
         &[
             "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1",
             "",
@@ -162,7 +194,74 @@ fn test_ex() {
             "",
         ]
         .map(str::to_string),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_ex() {
+    let (numbers, boards) = example();
+    let wins = Bingo::new(boards, false).wins(&numbers).collect_vec();
+    assert_eq!(ex1(&wins), Some(4512));
+    assert_eq!(ex2(&wins), Some(1924));
+    assert_eq!(
+        wins.iter().map(|win| (win.board, win.number)).collect_vec(),
+        vec![(2, 24), (0, 16), (1, 13)]
+    );
+}
+
+#[test]
+fn test_diagonals() {
+    // Main diagonal of the first board
+    let (_, boards) = example();
+    let wins = Bingo::new(boards.clone(), true)
+        .wins(&[14, 2, 22, 18, 19])
+        .collect_vec();
+    assert_eq!(
+        wins,
+        vec![Win {
+            board: 0,
+            number: 19,
+            score: 19 * (300 - 22 - 2 - 14 - 18 - 19)
+        }]
+    );
+    assert_eq!(
+        Bingo::new(boards, false).wins(&[14, 2, 22, 18, 19]).count(),
+        0
+    );
+}
+
+#[test]
+fn test_rectangular() {
+    let (_, boards) =
+        read_in(&["1".to_string(), "1 2 3".to_string(), "4 5 6".to_string()]).unwrap();
+    assert_eq!(boards[0].numbers.width(), 3);
+    let wins = Bingo::new(boards.clone(), true)
+        .wins(&[2, 5, 1])
+        .collect_vec();
+    assert_eq!(
+        wins,
+        vec![Win {
+            board: 0,
+            number: 5,
+            score: 5 * (1 + 3 + 4 + 6)
+        }]
+    );
+    assert_eq!(Bingo::new(boards, true).wins(&[1, 5]).count(), 0);
+
+    assert!(read_in(&["1".to_string(), "1 2".to_string(), "3".to_string()]).is_err());
+}
+
+#[test]
+fn test_repeated_number() {
+    // The second 2 is marked before scoring, even though the first one completes a row
+    let (_, boards) = read_in(&["2".to_string(), "2 2".to_string(), "3 2".to_string()]).unwrap();
+    assert_eq!(
+        Bingo::new(boards, false).wins(&[2]).collect_vec(),
+        vec![Win {
+            board: 0,
+            number: 2,
+            score: 2 * 3
+        }]
     );
-    assert_eq!(ex1(&numbers, grids.clone()), 4512);
-    assert_eq!(ex2(&numbers, grids), 1924)
 }
@@ -1,74 +1,314 @@
 use std::{
-    cmp::{max, min},
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     io::{stdin, BufRead},
 };
 
 use itertools::Itertools;
 
-pub struct Line(i32, i32, i32, i32);
+pub struct Line(i64, i64, i64, i64);
 
-peg::parser!(
+advent_of_code_common::parser!(
     grammar line_parser() for str {
-        rule number() -> i32
-        = n:$(['0'..='9']+) {? n.parse().or(Err("i32")) }
-
-        pub rule line() -> Line = a:number() "," b:number() " -> " c:number() "," d:number(){Line(a,b,c,d)}
+        pub rule line() -> Line = a:i64() "," b:i64() " -> " c:i64() "," d:i64(){Line(a,b,c,d)}
     }
 );
 
-fn points_in_line(line: &Line) -> Vec<(i32, i32)> {
-    let vector = (-(line.0 - line.2).signum(), -(line.1 - line.3).signum());
-    let (mut x, mut y) = (line.0, line.1);
-    let mut points = vec![(line.2, line.3)];
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Direction {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal,
+}
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Horizontal,
+    Direction::Vertical,
+    Direction::Diagonal,
+    Direction::AntiDiagonal,
+];
 
-    while (x, y) != (line.2, line.3) {
-        points.push((x, y));
-        x += vector.0;
-        y += vector.1;
+impl Direction {
+    // (a, b) such that a*x + b*y is the same for all the points of a line with this direction
+    fn key_coefficients(self) -> (i64, i64) {
+        match self {
+            Direction::Horizontal => (0, 1),
+            Direction::Vertical => (1, 0),
+            Direction::Diagonal => (1, -1),
+            Direction::AntiDiagonal => (1, 1),
+        }
+    }
+
+    fn key(self, (x, y): (i64, i64)) -> i64 {
+        let (a, b) = self.key_coefficients();
+        a * x + b * y
+    }
+
+    // Coordinate of a point along a line with this direction
+    fn position(self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Direction::Vertical => y,
+            _ => x,
+        }
+    }
+
+    fn point(self, key: i64, position: i64) -> (i64, i64) {
+        match self {
+            Direction::Horizontal => (position, key),
+            Direction::Vertical => (key, position),
+            Direction::Diagonal => (position, position - key),
+            Direction::AntiDiagonal => (position, key - position),
+        }
+    }
+}
+
+// Part of the line `direction.key(p) == key` between two positions (included)
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    direction: Direction,
+    key: i64,
+    from: i64,
+    to: i64,
+}
+
+impl TryFrom<&Line> for Segment {
+    type Error = String;
+
+    fn try_from(line: &Line) -> Result<Self, Self::Error> {
+        let (start, end) = ((line.0, line.1), (line.2, line.3));
+        let direction = match (line.2 - line.0, line.3 - line.1) {
+            (_, 0) => Direction::Horizontal,
+            (0, _) => Direction::Vertical,
+            (dx, dy) if dx == dy => Direction::Diagonal,
+            (dx, dy) if dx == -dy => Direction::AntiDiagonal,
+            _ => {
+                return Err(format!(
+                    "{},{} -> {},{} is neither horizontal, vertical nor diagonal",
+                    line.0, line.1, line.2, line.3
+                ))
+            }
+        };
+        let (from, to) = (direction.position(start), direction.position(end));
+        Ok(Segment {
+            direction,
+            key: direction.key(start),
+            from: from.min(to),
+            to: from.max(to),
+        })
     }
-    points
 }
 
-fn ex1(lines: &[Line]) -> usize {
-    let mut points = HashMap::new();
+// Maximal part of a line covered by the same number of segments
+struct Piece {
+    segment: Segment,
+    coverage: usize,
+}
 
-    for l in lines.iter().filter(|l| l.0 == l.2 || l.1 == l.3) {
-        for p in points_in_line(l) {
-            *points.entry(p).or_insert(0) += 1;
+impl Piece {
+    // Range of the keys of `direction` met along the piece
+    fn extent(&self, direction: Direction) -> (i64, i64) {
+        let segment = &self.segment;
+        let from = direction.key(segment.direction.point(segment.key, segment.from));
+        let to = direction.key(segment.direction.point(segment.key, segment.to));
+        (from.min(to), from.max(to))
+    }
+}
+
+// Sweeps each line along its positions: time in O(s log s) for s segments
+fn pieces(segments: &[Segment]) -> Vec<Piece> {
+    let mut lines: HashMap<(Direction, i64), Vec<(i64, i64)>> = HashMap::new();
+    for segment in segments {
+        let events = lines.entry((segment.direction, segment.key)).or_default();
+        events.push((segment.from, 1));
+        events.push((segment.to + 1, -1));
+    }
+
+    let mut pieces = vec![];
+    for ((direction, key), mut events) in lines {
+        events.sort_unstable();
+        let mut coverage = 0;
+        for (event, next) in events.iter().tuple_windows() {
+            coverage += event.1;
+            if coverage > 0 && event.0 < next.0 {
+                pieces.push(Piece {
+                    segment: Segment {
+                        direction,
+                        key,
+                        from: event.0,
+                        to: next.0 - 1,
+                    },
+                    coverage: coverage as usize,
+                });
+            }
         }
     }
+    pieces
+}
 
-    points.into_iter().filter(|(_, v)| v > &1).count()
+// Integer point at the crossing of two lines of different directions
+fn intersection(a: Direction, p: i64, b: Direction, q: i64) -> Option<(i64, i64)> {
+    let ((ax, ay), (bx, by)) = (a.key_coefficients(), b.key_coefficients());
+    let determinant = ax * by - ay * bx;
+    let (x, y) = (p * by - ay * q, ax * q - bx * p);
+    if x % determinant != 0 || y % determinant != 0 {
+        return None;
+    }
+    Some((x / determinant, y / determinant))
 }
 
-fn ex2(lines: &[Line]) -> usize {
-    let mut points = HashMap::new();
+// Pairs (horizontal, vertical) of crossing ranges, in O((n + i) log n) for i crossings.
+// Horizontals are (row, first column, last column), verticals (column, first row, last row).
+fn orthogonal_crossings(
+    horizontals: &[(i64, i64, i64)],
+    verticals: &[(i64, i64, i64)],
+) -> Vec<(usize, usize)> {
+    // At the same column: insertions, then queries, then removals
+    let mut events = vec![];
+    for (i, (_, first, last)) in horizontals.iter().enumerate() {
+        events.push((*first, 0, i));
+        events.push((*last, 2, i));
+    }
+    for (j, (column, _, _)) in verticals.iter().enumerate() {
+        events.push((*column, 1, j));
+    }
+    events.sort_unstable();
 
-    for l in lines {
-        for p in points_in_line(l) {
-            *points.entry(p).or_insert(0) += 1;
+    let mut active = BTreeSet::new();
+    let mut crossings = vec![];
+    for (_, kind, index) in events {
+        match kind {
+            0 => {
+                active.insert((horizontals[index].0, index));
+            }
+            1 => {
+                let (_, first, last) = verticals[index];
+                for (_, horizontal) in active.range((first, 0)..=(last, usize::MAX)) {
+                    crossings.push((*horizontal, index));
+                }
+            }
+            _ => {
+                active.remove(&(horizontals[index].0, index));
+            }
         }
     }
+    crossings
+}
 
-    points.into_iter().filter(|(_, v)| v > &1).count()
+// Points on lines of several directions, with the coverage of each direction
+fn crossings(pieces: &[Piece]) -> HashMap<(i64, i64), [usize; 4]> {
+    let mut points: HashMap<(i64, i64), [usize; 4]> = HashMap::new();
+    for (i, a) in DIRECTIONS.iter().enumerate() {
+        for b in &DIRECTIONS[i + 1..] {
+            let pieces_a = pieces
+                .iter()
+                .filter(|piece| piece.segment.direction == *a)
+                .collect_vec();
+            let pieces_b = pieces
+                .iter()
+                .filter(|piece| piece.segment.direction == *b)
+                .collect_vec();
+            let horizontals = pieces_a
+                .iter()
+                .map(|piece| {
+                    let (first, last) = piece.extent(*b);
+                    (piece.segment.key, first, last)
+                })
+                .collect_vec();
+            let verticals = pieces_b
+                .iter()
+                .map(|piece| {
+                    let (first, last) = piece.extent(*a);
+                    (piece.segment.key, first, last)
+                })
+                .collect_vec();
+
+            for (h, v) in orthogonal_crossings(&horizontals, &verticals) {
+                let (piece_a, piece_b) = (pieces_a[h], pieces_b[v]);
+                if let Some(point) = intersection(*a, piece_a.segment.key, *b, piece_b.segment.key)
+                {
+                    let coverages = points.entry(point).or_default();
+                    coverages[*a as usize] = piece_a.coverage;
+                    coverages[*b as usize] = piece_b.coverage;
+                }
+            }
+        }
+    }
+    points
 }
 
-fn main() {
-    let lines = stdin()
+// Number of points covered by at least k segments
+fn count_overlaps(segments: &[Segment], k: usize) -> u64 {
+    assert!(k > 0, "every point is covered at least 0 times");
+    let pieces = pieces(segments);
+    let mut count: u64 = pieces
+        .iter()
+        .filter(|piece| piece.coverage >= k)
+        .map(|piece| (piece.segment.to - piece.segment.from + 1) as u64)
+        .sum();
+
+    // Points on several lines were counted once per line reaching k alone
+    for coverages in crossings(&pieces).values() {
+        count -= coverages.iter().filter(|c| **c >= k).count() as u64;
+        if coverages.iter().sum::<usize>() >= k {
+            count += 1;
+        }
+    }
+    count
+}
+
+fn ex1(segments: &[Segment]) -> u64 {
+    let straight = segments
+        .iter()
+        .filter(|s| matches!(s.direction, Direction::Horizontal | Direction::Vertical))
+        .copied()
+        .collect_vec();
+    count_overlaps(&straight, 2)
+}
+
+fn ex2(segments: &[Segment]) -> u64 {
+    count_overlaps(segments, 2)
+}
+
+// The k of count_overlaps given on the command line
+fn overlap_threshold(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(0) => Err("the number of overlapping lines should be at least 1".to_string()),
+        Ok(k) => Ok(k),
+        Err(_) => Err(format!(
+            "expected a number of overlapping lines, got {:?}",
+            arg
+        )),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let segments: Vec<Segment> = stdin()
         .lock()
         .lines()
-        .map(Result::unwrap)
-        .map(|x| line_parser::line(&x).unwrap())
-        .collect_vec();
+        .map(|x| Ok(Segment::try_from(&line_parser::line(&x?)?)?))
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
 
-    println!("{}", ex1(&lines));
-    println!("{}", ex2(&lines));
+    match std::env::args().nth(1) {
+        Some(k) => println!("{}", count_overlaps(&segments, overlap_threshold(&k)?)),
+        None => {
+            println!("{}", ex1(&segments));
+            println!("{}", ex2(&segments));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn parse_segments(lines: &[&str]) -> Vec<Segment> {
+    lines
+        .iter()
+        .map(|x| Segment::try_from(&line_parser::line(x).unwrap()).unwrap())
+        .collect()
 }
 
 #[test]
 fn test_ex() {
-    let lines = [
+    let segments = parse_segments(&[
         "0,9 -> 5,9",
         "8,0 -> 0,8",
         "9,4 -> 3,4",
@@ -79,10 +319,67 @@ fn test_ex() {
         "3,4 -> 1,4",
         "0,0 -> 8,8",
         "5,5 -> 8,2",
-    ]
-    .map(line_parser::line)
-    .map(Result::unwrap);
+    ]);
+
+    assert_eq!(ex1(&segments), 5);
+    assert_eq!(ex2(&segments), 12);
+    assert_eq!(count_overlaps(&segments, 1), 39);
+    assert_eq!(count_overlaps(&segments, 3), 2);
+}
+
+#[test]
+fn test_against_every_point() {
+    // Pseudo-random segments in a small square, to get many crossings
+    let mut state = 12345u64;
+    let mut next = |bound: i64| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as i64 % bound
+    };
+    let mut lines = vec![];
+    for _ in 0..60 {
+        let (x, y, length) = (next(15), next(15), next(10));
+        let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1)][next(6) as usize];
+        lines.push(Line(x, y + 10, x + dx * length, y + 10 + dy * length));
+    }
+    let segments = lines
+        .iter()
+        .map(|line| Segment::try_from(line).unwrap())
+        .collect_vec();
+
+    let mut points: HashMap<(i64, i64), usize> = HashMap::new();
+    for segment in &segments {
+        for position in segment.from..=segment.to {
+            *points
+                .entry(segment.direction.point(segment.key, position))
+                .or_default() += 1;
+        }
+    }
+    for k in 1..6 {
+        let expected = points.values().filter(|count| **count >= k).count() as u64;
+        assert_eq!(count_overlaps(&segments, k), expected);
+    }
+}
 
-    assert_eq!(ex1(&lines), 5);
-    assert_eq!(ex2(&lines), 12);
+#[test]
+fn test_huge_coordinates() {
+    let segments = parse_segments(&[
+        "0,0 -> 1000000000,0",
+        "2000000000,0 -> 500,0",
+        "7,7 -> 7,0",
+        "0,2000000000 -> 2000000000,0",
+    ]);
+    assert_eq!(count_overlaps(&segments, 2), 999999503);
+    assert_eq!(count_overlaps(&segments, 3), 0);
+    assert!(Segment::try_from(&line_parser::line("0,0 -> 1,2").unwrap()).is_err());
+    assert!(line_parser::line("0,0 -> 1,9223372036854775808").is_err());
+}
+
+#[test]
+fn test_overlap_threshold() {
+    assert_eq!(overlap_threshold("3"), Ok(3));
+    assert!(overlap_threshold("0").is_err());
+    assert!(overlap_threshold("-1").is_err());
+    assert!(overlap_threshold("two").is_err());
 }
@@ -1,51 +1,280 @@
-use std::{collections::HashMap, hash::Hash, io::stdin};
+use std::{
+    io::stdin,
+    ops::{Add, Mul},
+};
 
-use itertools::Itertools;
-type Memoize = HashMap<(u128, u128), u128>;
+use num::{BigUint, Zero};
 
-fn memoized_lanternfish(remain: u128, time: u128, memoization: &mut Memoize) -> u128 {
-    if time <= remain {
-        return 1;
+type Matrix<T> = Vec<Vec<T>>;
+
+// Integers modulo `modulus`, so that huge day counts stay cheap
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Modular {
+    value: u64,
+    modulus: u64,
+}
+
+impl Modular {
+    fn new(value: u64, modulus: u64) -> Self {
+        Modular {
+            value: value % modulus,
+            modulus,
+        }
+    }
+}
+
+impl Add for &Modular {
+    type Output = Modular;
+
+    fn add(self, other: &Modular) -> Modular {
+        let value = (self.value as u128 + other.value as u128) % self.modulus as u128;
+        Modular::new(value as u64, self.modulus)
     }
-    if let Some(val) = memoization.get(&(remain, time)) {
-        return *val;
+}
+
+impl Mul for &Modular {
+    type Output = Modular;
+
+    fn mul(self, other: &Modular) -> Modular {
+        let value = (self.value as u128 * other.value as u128) % self.modulus as u128;
+        Modular::new(value as u64, self.modulus)
+    }
+}
+
+fn mat_mul<T>(a: &Matrix<T>, b: &Matrix<T>, zero: &T) -> Matrix<T>
+where
+    T: Clone,
+    for<'a> &'a T: Add<&'a T, Output = T> + Mul<&'a T, Output = T>,
+{
+    let size = b[0].len();
+    a.iter()
+        .map(|row| {
+            (0..size)
+                .map(|j| {
+                    row.iter()
+                        .zip(b)
+                        .fold(zero.clone(), |acc, (x, b_row)| &acc + &(x * &b_row[j]))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn mat_pow<T>(matrix: &Matrix<T>, mut exponent: u64, zero: &T, one: &T) -> Matrix<T>
+where
+    T: Clone,
+    for<'a> &'a T: Add<&'a T, Output = T> + Mul<&'a T, Output = T>,
+{
+    let size = matrix.len();
+    let mut result: Matrix<T> = (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| if i == j { one.clone() } else { zero.clone() })
+                .collect()
+        })
+        .collect();
+    let mut power = matrix.clone();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mat_mul(&result, &power, zero);
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            power = mat_mul(&power, &power, zero);
+        }
     }
-    let result = if remain == 0 {
-        memoized_lanternfish(6, time - 1, memoization)
-            + memoized_lanternfish(8, time - 1, memoization)
-    } else {
-        memoized_lanternfish(remain - 1, time - 1, memoization)
-    };
-    memoization.insert((remain, time), result);
     result
 }
 
-fn ex1(starting_point: &[u128], time: u128) -> u128 {
-    let mut total = 0;
-    let mut memoization = Memoize::new();
-    for value in starting_point {
-        total += memoized_lanternfish(*value, time, &mut memoization);
+// A fish at timer 0 goes back to `reset_timer` and spawns a fish at `newborn_timer`
+struct LanternfishModel {
+    reset_timer: usize,
+    newborn_timer: usize,
+}
+
+impl LanternfishModel {
+    fn new(reset_timer: usize, newborn_timer: usize) -> Self {
+        LanternfishModel {
+            reset_timer,
+            newborn_timer,
+        }
+    }
+
+    fn states(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer) + 1
+    }
+
+    // transition[i][j]: number of fish with timer i produced in one day by a fish with timer j
+    fn transition(&self) -> Matrix<u64> {
+        let size = self.states();
+        let mut matrix = vec![vec![0; size]; size];
+        for timer in 1..size {
+            matrix[timer - 1][timer] = 1;
+        }
+        matrix[self.reset_timer][0] += 1;
+        matrix[self.newborn_timer][0] += 1;
+        matrix
+    }
+
+    fn check_timers(&self, fish: &[usize]) -> Result<(), String> {
+        match fish.iter().find(|timer| **timer >= self.states()) {
+            Some(timer) => Err(format!(
+                "timer {} is above the life cycle of the model",
+                timer
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // The timers must have been checked
+    fn initial_counts(&self, fish: &[usize]) -> Vec<u64> {
+        let mut counts = vec![0; self.states()];
+        for timer in fish {
+            assert!(
+                *timer < counts.len(),
+                "timer {} is above the life cycle of the model",
+                timer
+            );
+            counts[*timer] += 1;
+        }
+        counts
+    }
+
+    fn population<T>(&self, fish: &[usize], days: u64, zero: T, from: impl Fn(u64) -> T) -> T
+    where
+        T: Clone,
+        for<'a> &'a T: Add<&'a T, Output = T> + Mul<&'a T, Output = T>,
+    {
+        let transition = self
+            .transition()
+            .into_iter()
+            .map(|row| row.into_iter().map(&from).collect())
+            .collect();
+        let matrix = mat_pow(&transition, days, &zero, &from(1));
+        let counts = self.initial_counts(fish);
+        // Total population = sum over the columns weighted by the initial counts
+        matrix
+            .iter()
+            .flatten()
+            .zip(counts.iter().cycle())
+            .fold(zero.clone(), |acc, (coefficient, count)| {
+                &acc + &(coefficient * &from(*count))
+            })
+    }
+
+    fn population_exact(&self, fish: &[usize], days: u64) -> BigUint {
+        self.population(fish, days, BigUint::zero(), BigUint::from)
+    }
+
+    fn population_modulo(&self, fish: &[usize], days: u64, modulus: u64) -> u64 {
+        self.population(fish, days, Modular::new(0, modulus), |value| {
+            Modular::new(value, modulus)
+        })
+        .value
+    }
+}
+
+fn ex1(entry: &[usize], days: u64) -> BigUint {
+    LanternfishModel::new(6, 8).population_exact(entry, days)
+}
+
+fn option_value(args: &[String], name: &str) -> Result<Option<u64>, String> {
+    match args.iter().position(|arg| arg == name) {
+        None => Ok(None),
+        Some(position) => match args.get(position + 1).map(|value| value.parse()) {
+            Some(Ok(value)) => Ok(Some(value)),
+            _ => Err(format!("{} expects a number", name)),
+        },
+    }
+}
+
+fn modulus_option(args: &[String]) -> Result<Option<u64>, String> {
+    match option_value(args, "--modulo")? {
+        Some(0) => Err("--modulo expects a positive number".to_string()),
+        modulus => Ok(modulus),
     }
-    total
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut line = String::new();
-    stdin().read_line(&mut line).unwrap();
+    stdin().read_line(&mut line)?;
     let entry = line
-        .strip_suffix('\n')
-        .unwrap()
+        .trim()
         .split(',')
-        .map(|x| x.parse::<u128>().unwrap())
-        .collect_vec();
+        .map(|x| x.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
 
-    println!("{}", ex1(&entry, 80));
-    println!("{}", ex1(&entry, 256));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let model = LanternfishModel::new(
+        option_value(&args, "--reset")?.unwrap_or(6) as usize,
+        option_value(&args, "--newborn")?.unwrap_or(8) as usize,
+    );
+    model.check_timers(&entry)?;
+    if args.is_empty() {
+        println!("{}", ex1(&entry, 80));
+        println!("{}", ex1(&entry, 256));
+        return Ok(());
+    }
+
+    let days = option_value(&args, "--days")?.unwrap_or(256);
+    match modulus_option(&args)? {
+        Some(modulus) => println!("{}", model.population_modulo(&entry, days, modulus)),
+        None => println!("{}", model.population_exact(&entry, days)),
+    }
+    Ok(())
 }
 
 #[test]
 fn test_ex() {
-    let entry = [3u128, 4, 3, 1, 2];
-    assert_eq!(ex1(&entry, 80), 5934);
-    assert_eq!(ex1(&entry, 256), 26984457539);
+    let entry = [3, 4, 3, 1, 2];
+    assert_eq!(ex1(&entry, 18), BigUint::from(26u32));
+    assert_eq!(ex1(&entry, 80), BigUint::from(5934u32));
+    assert_eq!(ex1(&entry, 256), BigUint::from(26984457539u64));
+}
+
+#[test]
+fn test_modulo() {
+    let entry = [3, 4, 3, 1, 2];
+    let model = LanternfishModel::new(6, 8);
+    assert_eq!(model.population_modulo(&entry, 256, 1_000_000), 457539);
+    assert_eq!(model.population_modulo(&entry, 0, 7), 5);
+    assert_eq!(model.population_modulo(&entry, 80, 1), 0);
+}
+
+#[test]
+fn test_life_cycle() {
+    // Every fish splits in two each day
+    let doubling = LanternfishModel::new(0, 0);
+    assert_eq!(
+        doubling.population_exact(&[0], 100),
+        BigUint::from(2u32).pow(100)
+    );
+    // 2^(10^9) mod 10^9 + 7
+    assert_eq!(
+        doubling.population_modulo(&[0], 1_000_000_000, 1_000_000_007),
+        140625001
+    );
+
+    // Newborns spawn the next day, parents every other day: Fibonacci numbers
+    let fibonacci = LanternfishModel::new(1, 0);
+    assert_eq!(fibonacci.population_exact(&[0], 10), BigUint::from(144u32));
+}
+
+#[test]
+fn test_options() {
+    let args = ["--days", "80", "--modulo", "0"].map(String::from);
+    assert_eq!(option_value(&args, "--days"), Ok(Some(80)));
+    assert_eq!(option_value(&args, "--reset"), Ok(None));
+    assert!(modulus_option(&args).is_err());
+    assert!(option_value(&args[..1], "--days").is_err());
+    assert_eq!(modulus_option(&args[..2]), Ok(None));
+}
+
+#[test]
+fn test_check_timers() {
+    assert_eq!(LanternfishModel::new(6, 8).check_timers(&[3, 8, 0]), Ok(()));
+    assert_eq!(
+        LanternfishModel::new(2, 3).check_timers(&[1, 5]),
+        Err("timer 5 is above the life cycle of the model".to_string())
+    );
 }
@@ -1,46 +1,309 @@
 use std::io::stdin;
 
+#[cfg(test)]
+use advent_of_code_common::integer::Checked;
+use advent_of_code_common::integer::{run_checked, Integer, Solver, CHECKED_FLAG};
 use itertools::Itertools;
 
-fn ex1(entry: &[u32]) -> u32 {
-    let mut entry = entry.to_vec();
-    entry.sort_unstable();
-    let median = entry[entry.len() / 2];
-    entry
-        .iter()
-        .map(|x| (*x as i32 - median as i32).abs() as u32)
-        .sum()
-}
-fn ex2(entry: &[u32]) -> u32 {
-    let mut entry = entry.to_vec();
-    entry.sort_unstable();
-    let mut minimal = u32::MAX;
-    for target in entry[0]..=entry[entry.len() - 1] {
-        let fuel = entry
-            .iter()
-            .map(|x| (*x as i32 - target as i32).abs() as u32)
-            .map(|x| x * (x + 1) / 2)
-            .sum();
-        minimal = minimal.min(fuel);
+// Overflowing fuel totals are reported as None even without --checked
+type Int = u64;
+
+type CostFunction<I> = Box<dyn Fn(&I) -> Option<I>>;
+
+// Fuel spent by a crab for a given distance, None when it does not fit in the integer type
+enum Cost<I> {
+    Linear,
+    Triangular,
+    Quadratic,
+    // Must be convex and non-decreasing
+    Convex(CostFunction<I>),
+}
+
+impl<I: Integer> Cost<I> {
+    fn of(&self, distance: &I) -> Option<I> {
+        let two = I::from_u64(2).expect("2 fits in every integer type");
+        match self {
+            Cost::Linear => Some(distance.clone()),
+            // Halve the even factor first, so that only the result must fit
+            Cost::Triangular => {
+                let next = distance.checked_add(&I::one())?;
+                if distance.clone() % two.clone() == I::zero() {
+                    (distance.clone() / two).checked_mul(&next)
+                } else {
+                    distance.checked_mul(&(next / two))
+                }
+            }
+            Cost::Quadratic => distance.checked_mul(distance),
+            Cost::Convex(cost) => cost(distance),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Alignment<I> {
+    position: I,
+    fuel: I,
+}
+
+fn abs_diff<I: Integer>(x: &I, y: &I) -> I {
+    if x >= y {
+        x.clone() - y.clone()
+    } else {
+        y.clone() - x.clone()
+    }
+}
+
+fn total_fuel<I: Integer>(crabs: &[I], target: &I, cost: &Cost<I>) -> Option<I> {
+    crabs.iter().try_fold(I::zero(), |total, x| {
+        total.checked_add(&cost.of(&abs_diff(x, target))?)
+    })
+}
+
+// An overflowing total is worse than any other
+fn fuel_key<I: Integer>(fuel: Option<I>) -> (bool, I) {
+    (fuel.is_none(), fuel.unwrap_or_else(I::zero))
+}
+
+// Quotient rounded down and non-negative remainder, for negative positions too
+fn div_rem_euclid<I: Integer>(x: &I, divisor: &I) -> (I, I) {
+    let (quotient, remainder) = (x.clone() / divisor.clone(), x.clone() % divisor.clone());
+    if remainder < I::zero() {
+        (quotient - I::one(), remainder + divisor.clone())
+    } else {
+        (quotient, remainder)
+    }
+}
+
+// Floor and ceil of the mean, summing the quotients and the remainders by the number of
+// crabs separately so that no sum exceeds the largest crab times the number of crabs
+fn mean_bounds<I: Integer>(crabs: &[I]) -> (I, I) {
+    let length = I::from_u64(crabs.len() as u64).expect("the number of crabs fits");
+    let (quotients, remainders): (Vec<I>, Vec<I>) =
+        crabs.iter().map(|x| div_rem_euclid(x, &length)).unzip();
+    let (quotients, remainders): (I, I) =
+        (quotients.into_iter().sum(), remainders.into_iter().sum());
+    let floor = quotients + remainders.clone() / length.clone();
+    if remainders % length == I::zero() {
+        (floor.clone(), floor)
+    } else {
+        (floor.clone(), floor + I::one())
+    }
+}
+
+// The total fuel is convex in the target: binary search on the sign of its slope
+fn convex_minimum<I: Integer>(crabs: &[I], cost: &Cost<I>, min: I, max: I) -> I {
+    let two = I::from_u64(2).expect("2 fits in every integer type");
+    let (mut low, mut high) = (min, max);
+    while low < high {
+        let middle = low.clone() + (high.clone() - low.clone()) / two.clone();
+        let next = middle.clone() + I::one();
+        if fuel_key(total_fuel(crabs, &middle, cost)) <= fuel_key(total_fuel(crabs, &next, cost)) {
+            high = middle;
+        } else {
+            low = next;
+        }
     }
-    minimal
+    low
+}
+
+// Cheapest position (the smallest one on ties), None without crabs or if its fuel overflows
+fn align<I: Integer>(crabs: &[I], cost: &Cost<I>) -> Option<Alignment<I>> {
+    let crabs = crabs.iter().cloned().sorted_unstable().collect_vec();
+    let (min, max) = (crabs.first()?.clone(), crabs.last()?.clone());
+    let candidates = match cost {
+        Cost::Linear => vec![crabs[(crabs.len() - 1) / 2].clone()],
+        Cost::Quadratic => {
+            let (low, high) = mean_bounds(&crabs);
+            vec![low, high]
+        }
+        // The optimum is within 1/2 of the mean
+        Cost::Triangular => {
+            let (low, high) = mean_bounds(&crabs);
+            let mut position = if low > min { low - I::one() } else { min };
+            let last = if high < max { high + I::one() } else { max };
+            let mut candidates = vec![position.clone()];
+            while position < last {
+                position += I::one();
+                candidates.push(position.clone());
+            }
+            candidates
+        }
+        Cost::Convex(_) => vec![convex_minimum(&crabs, cost, min, max)],
+    };
+    candidates
+        .into_iter()
+        .filter_map(|position| {
+            total_fuel(&crabs, &position, cost).map(|fuel| Alignment { position, fuel })
+        })
+        .min_by(|a, b| (&a.fuel, &a.position).cmp(&(&b.fuel, &b.position)))
 }
 
-fn main() {
-    let mut line = String::new();
-    stdin().read_line(&mut line).unwrap();
-    let entry = line
-        .strip_suffix('\n')
-        .unwrap()
-        .split(',')
-        .map(|x| x.parse::<u32>().unwrap())
-        .collect_vec();
-    println!("{}", ex1(&entry));
-    println!("{}", ex2(&entry));
+fn ex1<I: Integer>(entry: &[I]) -> Option<Alignment<I>> {
+    align(entry, &Cost::Linear)
+}
+
+fn ex2<I: Integer>(entry: &[I]) -> Option<Alignment<I>> {
+    align(entry, &Cost::Triangular)
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cost = std::env::args().skip(1).find(|arg| arg != CHECKED_FLAG);
+    run_checked::<Int, _>(Solve { cost })
+}
+
+struct Solve {
+    cost: Option<String>,
+}
+
+impl Solver for Solve {
+    type Output = Result<(), Box<dyn std::error::Error>>;
+
+    fn solve<I: Integer>(self) -> Self::Output {
+        let mut line = String::new();
+        stdin().read_line(&mut line)?;
+        let entry = line
+            .trim()
+            .split(',')
+            .map(str::parse::<I>)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let cost = match self.cost.as_deref() {
+            None => {
+                println!("{}", ex1(&entry).ok_or("no crab or fuel overflow")?.fuel);
+                println!("{}", ex2(&entry).ok_or("no crab or fuel overflow")?.fuel);
+                return Ok(());
+            }
+            Some("linear") => Cost::Linear,
+            Some("triangular") => Cost::Triangular,
+            Some("quadratic") => Cost::Quadratic,
+            Some("cubic") => Cost::Convex(Box::new(|d: &I| d.checked_mul(d)?.checked_mul(d))),
+            Some(other) => {
+                return Err(format!(
+                    "unknown cost {}, expected linear, triangular, quadratic or cubic",
+                    other
+                )
+                .into())
+            }
+        };
+        match align(&entry, &cost) {
+            Some(Alignment { position, fuel }) => println!("position {}: {} fuel", position, fuel),
+            None => println!("no crab or fuel overflow"),
+        }
+        Ok(())
+    }
 }
 
 #[test]
 fn test_ex() {
-    assert_eq!(ex1(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]), 37);
-    assert_eq!(ex2(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]), 168);
+    assert_eq!(
+        ex1::<Int>(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]),
+        Some(Alignment {
+            position: 2,
+            fuel: 37
+        })
+    );
+    assert_eq!(
+        ex2::<Int>(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]),
+        Some(Alignment {
+            position: 5,
+            fuel: 168
+        })
+    );
+}
+
+#[cfg(test)]
+fn assert_costs_match_brute_force<I: Integer>(entries: &[&[I]], costs: &[Cost<I>]) {
+    for entry in entries {
+        for cost in costs {
+            let max = entry.iter().max().unwrap();
+            let mut position = entry.iter().min().unwrap().clone();
+            let mut best = total_fuel(entry, &position, cost).unwrap();
+            while &position < max {
+                position += I::one();
+                best = best.min(total_fuel(entry, &position, cost).unwrap());
+            }
+            assert_eq!(align(entry, cost).unwrap().fuel, best, "{:?}", entry);
+        }
+    }
+}
+
+#[test]
+fn test_costs_match_brute_force() {
+    assert_costs_match_brute_force::<u64>(
+        &[
+            &[16, 1, 2, 0, 4, 2, 7, 1, 2, 14],
+            &[0, 100],
+            &[3, 3, 3],
+            &[0, 1, 1, 1, 1, 1, 1, 1, 1, 40],
+        ],
+        &[
+            Cost::Linear,
+            Cost::Triangular,
+            Cost::Quadratic,
+            Cost::Convex(Box::new(|d| d.checked_pow(3))),
+            Cost::Convex(Box::new(|d| Some(d.saturating_sub(5)))),
+        ],
+    );
+    // The mean of negative positions is rounded down, not toward zero
+    assert_costs_match_brute_force::<i64>(
+        &[
+            &[-5, 0, 0],
+            &[-16, -1, -2, 0, 4, -2, 7, 1, 2, -14],
+            &[-7, -3],
+        ],
+        &[
+            Cost::Linear,
+            Cost::Triangular,
+            Cost::Quadratic,
+            Cost::Convex(Box::new(|d| d.checked_pow(3))),
+        ],
+    );
+    assert_eq!(
+        align(&[-5i64, 0, 0], &Cost::Quadratic),
+        Some(Alignment {
+            position: -2,
+            fuel: 17
+        })
+    );
+}
+
+#[test]
+fn test_overflow() {
+    assert_eq!(align::<Int>(&[], &Cost::Linear), None);
+    assert_eq!(align(&[0, 0, u64::MAX, u64::MAX], &Cost::Linear), None);
+    assert_eq!(
+        align(&[0, u64::MAX, u64::MAX], &Cost::Linear),
+        Some(Alignment {
+            position: u64::MAX,
+            fuel: u64::MAX
+        })
+    );
+    assert_eq!(align(&[0u64, 1 << 33], &Cost::Quadratic), None);
+}
+
+#[test]
+fn test_other_integers() {
+    assert_eq!(
+        ex2(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14].map(Checked::<u8>)),
+        Some(Alignment {
+            position: Checked(5),
+            fuel: Checked(168)
+        })
+    );
+    assert_eq!(
+        ex2(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14].map(Checked::<i16>))
+            .unwrap()
+            .fuel,
+        Checked(168)
+    );
+    // The mean of 200 and 210 is computed without their sum
+    assert_eq!(
+        align(&[200, 210].map(Checked::<u8>), &Cost::Quadratic),
+        Some(Alignment {
+            position: Checked(205),
+            fuel: Checked(50)
+        })
+    );
+    // 125 * 125 does not fit in an u8
+    assert_eq!(align(&[0u8, 250], &Cost::Quadratic), None);
 }
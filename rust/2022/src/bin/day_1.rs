@@ -1,19 +1,15 @@
-use std::io::{stdin, BufRead};
+use advent_of_code_common::input::Input;
 
-fn parse(lines: &[String]) -> Vec<Vec<u64>> {
-    let mut elves_backpack = Vec::new();
-    let mut current_bp = Vec::new();
-    for l in lines {
-        if l.is_empty() {
-            elves_backpack.push(current_bp.clone());
-            current_bp.clear();
-        } else {
-            let current_val = l.parse::<u64>().unwrap();
-            current_bp.push(current_val);
-        }
-    }
-    elves_backpack.push(current_bp);
-    elves_backpack
+fn parse(input: &Input) -> Result<Vec<Vec<u64>>, String> {
+    input
+        .paragraphs()
+        .iter()
+        .map(|bp| {
+            bp.iter()
+                .map(|l| l.parse::<u64>().map_err(|e| format!("{:?}: {}", l, e)))
+                .collect()
+        })
+        .collect()
 }
 
 fn ex1(elves_backpack: &[Vec<u64>]) -> u64 {
@@ -31,50 +27,42 @@ fn ex2(elves_backpack: &[Vec<u64>]) -> u64 {
     elves_total[0] + elves_total[1] + elves_total[2]
 }
 
-fn main() {
-    let elves_backpack = parse(
-        &stdin()
-            .lock()
-            .lines()
-            .map(Result::unwrap)
-            .collect::<Vec<String>>(),
-    );
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let elves_backpack = parse(&Input::from_stdin()?)?;
     println!("{}\n", ex1(&elves_backpack));
     println!("{}\n", ex2(&elves_backpack));
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
 
-    fn gen_input_test() -> Vec<String> {
+    fn gen_input_test() -> Input {
         vec![
-            "1000".to_string(),
-            "2000".to_string(),
-            "3000".to_string(),
-            "".to_string(),
-            "4000".to_string(),
-            "".to_string(),
-            "5000".to_string(),
-            "6000".to_string(),
-            "".to_string(),
-            "7000".to_string(),
-            "8000".to_string(),
-            "9000".to_string(),
-            "".to_string(),
-            "10000".to_string(),
+            "1000", "2000", "3000", "", "4000", "", "5000", "6000", "", "7000", "8000", "9000", "",
+            "10000",
         ]
+        .into_iter()
+        .collect()
     }
 
     #[test]
     fn test_ex1() {
-        let entry = parse(&gen_input_test());
+        let entry = parse(&gen_input_test()).unwrap();
         assert_eq!(ex1(&entry), 24000);
     }
     #[test]
     fn test_ex2() {
-        let entry = parse(&gen_input_test());
+        let entry = parse(&gen_input_test()).unwrap();
         println!("{:?}", entry);
         assert_eq!(ex2(&entry), 45000);
     }
+
+    #[test]
+    fn test_parse() {
+        let entry = parse(&Input::new("1\r\n2\r\n\r\n3\r\n\r\n\r\n")).unwrap();
+        assert_eq!(entry, vec![vec![1, 2], vec![3]]);
+        assert!(parse(&Input::new("1\n\nx")).is_err());
+    }
 }
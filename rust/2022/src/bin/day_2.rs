@@ -1,158 +1,269 @@
-use std::io::{stdin, BufRead};
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{stdin, BufRead},
+};
 
-#[derive(Debug, PartialEq, Clone)]
-enum GameMoveOpponent {
-    A,
-    B,
-    C,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-enum GameMoveMe {
-    X,
-    Y,
-    Z,
-}
-
-#[derive(Debug, PartialEq, Clone)]
-enum RockPaperScisor {
-    Rock,
-    Paper,
-    Scisor,
-}
+use itertools::Itertools;
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 enum Outcome {
-    Win,
     Lose,
     Draw,
+    Win,
 }
 
-type Hint = (GameMoveOpponent, GameMoveMe);
-
-type Entry = Vec<Hint>;
+// Moves 0..n in a cycle, n odd: each move beats the (n - 1) / 2 moves before it
+#[derive(Debug)]
+struct CyclicGame {
+    names: Vec<&'static str>,
+    move_scores: Vec<u64>,
+    // Indexed by Outcome
+    outcome_scores: [u64; 3],
+}
 
-fn parse(lines: &[String]) -> Entry {
-    lines
-        .iter()
-        .map(|l| {
-            let first = match l.chars().nth(0).unwrap() {
-                'A' => GameMoveOpponent::A,
-                'B' => GameMoveOpponent::B,
-                'C' => GameMoveOpponent::C,
-                _ => unreachable!(),
-            };
-            let second = match l.chars().nth(2).unwrap() {
-                'X' => GameMoveMe::X,
-                'Y' => GameMoveMe::Y,
-                'Z' => GameMoveMe::Z,
-                _ => unreachable!(),
-            };
-            (first, second)
+impl CyclicGame {
+    fn new(
+        names: Vec<&'static str>,
+        move_scores: Vec<u64>,
+        outcome_scores: [u64; 3],
+    ) -> Result<Self, String> {
+        if names.len().is_multiple_of(2) {
+            return Err(format!(
+                "a cyclic game needs an odd number of moves, not {}",
+                names.len()
+            ));
+        }
+        if names.len() != move_scores.len() {
+            return Err(format!(
+                "{} moves but {} move scores",
+                names.len(),
+                move_scores.len()
+            ));
+        }
+        Ok(CyclicGame {
+            names,
+            move_scores,
+            outcome_scores,
         })
-        .collect()
-}
+    }
 
-fn who_win(opponent: RockPaperScisor, me: RockPaperScisor) -> Outcome {
-    use RockPaperScisor::*;
-    match (opponent, me) {
-        (Rock, Rock) | (Paper, Paper) | (Scisor, Scisor) => Outcome::Draw,
-        (Rock, Scisor) | (Scisor, Paper) | (Paper, Rock) => Outcome::Lose,
-        (Scisor, Rock) | (Paper, Scisor) | (Rock, Paper) => Outcome::Win,
+    fn rock_paper_scissors() -> Self {
+        CyclicGame::new(vec!["Rock", "Paper", "Scissors"], vec![1, 2, 3], [0, 3, 6]).unwrap()
     }
-}
 
-fn gmo_to_rpc(g: GameMoveOpponent) -> RockPaperScisor {
-    use GameMoveOpponent::*;
-    use RockPaperScisor::*;
-    match g {
-        A => Rock,
-        B => Paper,
-        C => Scisor,
+    fn rock_paper_scissors_lizard_spock() -> Self {
+        CyclicGame::new(
+            vec!["Rock", "Spock", "Paper", "Lizard", "Scissors"],
+            vec![1, 2, 3, 4, 5],
+            [0, 3, 6],
+        )
+        .unwrap()
     }
-}
-fn gmm_to_rpc(g: GameMoveMe) -> RockPaperScisor {
-    use GameMoveMe::*;
-    use RockPaperScisor::*;
-    match g {
-        X => Rock,
-        Y => Paper,
-        Z => Scisor,
+
+    fn moves(&self) -> usize {
+        self.names.len()
     }
-}
 
-fn score_outcome(out: Outcome) -> u64 {
-    match out {
-        Outcome::Win => 6,
-        Outcome::Draw => 3,
-        Outcome::Lose => 0,
+    fn outcome(&self, opponent: usize, me: usize) -> Outcome {
+        let n = self.moves();
+        match (me + n - opponent) % n {
+            0 => Outcome::Draw,
+            distance if distance <= (n - 1) / 2 => Outcome::Win,
+            _ => Outcome::Lose,
+        }
     }
+
+    // With more than three moves several answers give the outcome, the best scoring one is played
+    fn answer(&self, opponent: usize, outcome: Outcome) -> usize {
+        (0..self.moves())
+            .filter(|me| self.outcome(opponent, *me) == outcome)
+            .max_by_key(|me| (self.move_scores[*me], std::cmp::Reverse(*me)))
+            .unwrap()
+    }
+
+    fn score(&self, opponent: usize, me: usize) -> u64 {
+        self.move_scores[me] + self.outcome_scores[self.outcome(opponent, me) as usize]
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Instruction {
+    Play(usize),
+    Reach(Outcome),
+}
+
+// How the letters of the strategy guide are read
+#[derive(Debug, Clone)]
+struct Mapping {
+    opponent: HashMap<char, usize>,
+    me: HashMap<char, Instruction>,
 }
 
-fn score_choice(rpc: RockPaperScisor) -> u64 {
-    match rpc {
-        RockPaperScisor::Rock => 1,
-        RockPaperScisor::Paper => 2,
-        RockPaperScisor::Scisor => 3,
+impl Mapping {
+    fn new(opponent: &str, me: &[(char, Instruction)]) -> Self {
+        Mapping {
+            opponent: opponent.chars().enumerate().map(|(i, c)| (c, i)).collect(),
+            me: me.iter().copied().collect(),
+        }
+    }
+
+    fn play(&self, game: &CyclicGame, (opponent, me): Hint) -> Result<(usize, usize), String> {
+        let opponent = *self
+            .opponent
+            .get(&opponent)
+            .ok_or(format!("unknown opponent letter {}", opponent))?;
+        let me = match self.me.get(&me) {
+            Some(Instruction::Play(me)) => *me,
+            Some(Instruction::Reach(outcome)) => game.answer(opponent, *outcome),
+            None => return Err(format!("unknown letter {}", me)),
+        };
+        Ok((opponent, me))
     }
 }
 
-fn evaluate_match(opp: RockPaperScisor, me: RockPaperScisor) -> u64 {
-    score_choice(me.clone()) + score_outcome(who_win(opp, me))
+type Hint = (char, char);
+
+type Entry = Vec<Hint>;
+
+fn parse(lines: &[String]) -> Result<Entry, String> {
+    lines
+        .iter()
+        .map(|l| {
+            l.split_whitespace()
+                .map(|letter| letter.chars().exactly_one().ok())
+                .collect_tuple()
+                .and_then(|(first, second)| first.zip(second))
+                .ok_or(format!("{:?} is not two letters", l))
+        })
+        .collect()
 }
 
-fn ex1(entry: &Entry) -> u64 {
+fn total_score(game: &CyclicGame, mapping: &Mapping, entry: &Entry) -> Result<u64, String> {
     entry
-        .clone()
-        .into_iter()
-        .map(|(gmo, gmm)| (gmo_to_rpc(gmo), gmm_to_rpc(gmm)))
-        .map(|(x, y)| evaluate_match(x, y))
-        .sum::<u64>()
+        .iter()
+        .map(|hint| mapping.play(game, *hint).map(|(x, y)| game.score(x, y)))
+        .sum()
 }
 
-fn find_my_choice(opp: RockPaperScisor, out: Outcome) -> RockPaperScisor {
-    use Outcome::*;
-    use RockPaperScisor::*;
-    match (opp, out) {
-        (x, Draw) => x,
-        (Rock, Win) | (Scisor, Lose) => Paper,
-        (Rock, Lose) | (Paper, Win) => Scisor,
-        (Scisor, Win) | (Paper, Lose) => Rock,
-    }
+#[derive(Debug, Clone, Copy)]
+enum Goal {
+    Maximise,
+    Minimise,
 }
 
-fn gmm_to_outcome(g: GameMoveMe) -> Outcome {
-    use GameMoveMe::*;
-    use Outcome::*;
-    match g {
-        X => Lose,
-        Y => Draw,
-        Z => Win,
+// Best assignment of distinct moves to my letters, with its total score
+fn optimise(
+    game: &CyclicGame,
+    opponent: &HashMap<char, usize>,
+    entry: &Entry,
+    goal: Goal,
+) -> Result<(Mapping, u64), String> {
+    let letters = entry.iter().map(|(_, me)| *me).collect::<BTreeSet<_>>();
+    if letters.len() > game.moves() {
+        return Err(format!(
+            "{} letters cannot be given distinct moves among {}",
+            letters.len(),
+            game.moves()
+        ));
+    }
+    // counts[letter][opponent move]
+    let mut counts: HashMap<char, Vec<u64>> = HashMap::new();
+    for (o, me) in entry {
+        let o = *opponent
+            .get(o)
+            .ok_or(format!("unknown opponent letter {}", o))?;
+        counts.entry(*me).or_insert_with(|| vec![0; game.moves()])[o] += 1;
     }
+
+    let assignments = (0..game.moves()).permutations(letters.len()).map(|moves| {
+        let score = letters
+            .iter()
+            .zip(&moves)
+            .map(|(letter, me)| {
+                counts[letter]
+                    .iter()
+                    .enumerate()
+                    .map(|(o, count)| count * game.score(o, *me))
+                    .sum::<u64>()
+            })
+            .sum::<u64>();
+        (moves, score)
+    });
+    let (moves, score) = match goal {
+        Goal::Maximise => assignments.max_by_key(|(_, score)| *score),
+        Goal::Minimise => assignments.min_by_key(|(_, score)| *score),
+    }
+    .unwrap();
+
+    let mapping = Mapping {
+        opponent: opponent.clone(),
+        me: letters
+            .into_iter()
+            .zip(moves.into_iter().map(Instruction::Play))
+            .collect(),
+    };
+    Ok((mapping, score))
 }
 
-fn ex2(entry: &Entry) -> u64 {
-    entry
-        .clone()
-        .into_iter()
-        .map(|(gmo, gmm)| (gmo_to_rpc(gmo), gmm_to_outcome(gmm)))
-        .map(|(opp, out)| {
-            let me = find_my_choice(opp.clone(), out);
-            (opp, me)
-        })
-        .map(|(x, y)| evaluate_match(x, y))
-        .sum::<u64>()
+fn ex1(entry: &Entry) -> Result<u64, String> {
+    let mapping = Mapping::new(
+        "ABC",
+        &[
+            ('X', Instruction::Play(0)),
+            ('Y', Instruction::Play(1)),
+            ('Z', Instruction::Play(2)),
+        ],
+    );
+    total_score(&CyclicGame::rock_paper_scissors(), &mapping, entry)
 }
 
-fn main() {
+fn ex2(entry: &Entry) -> Result<u64, String> {
+    let mapping = Mapping::new(
+        "ABC",
+        &[
+            ('X', Instruction::Reach(Outcome::Lose)),
+            ('Y', Instruction::Reach(Outcome::Draw)),
+            ('Z', Instruction::Reach(Outcome::Win)),
+        ],
+    );
+    total_score(&CyclicGame::rock_paper_scissors(), &mapping, entry)
+}
+
+fn main() -> Result<(), String> {
     let entry = parse(
         &stdin()
             .lock()
             .lines()
             .map(Result::unwrap)
             .collect::<Vec<String>>(),
-    );
-    println!("{}\n", ex1(&entry));
-    println!("{}\n", ex2(&entry));
+    )?;
+
+    if std::env::args().any(|arg| arg == "--optimise") {
+        let game = if std::env::args().any(|arg| arg == "--lizard-spock") {
+            CyclicGame::rock_paper_scissors_lizard_spock()
+        } else {
+            CyclicGame::rock_paper_scissors()
+        };
+        let opponent = Mapping::new(&"ABCDE"[..game.moves()], &[]).opponent;
+        for goal in [Goal::Maximise, Goal::Minimise] {
+            let (mapping, score) = optimise(&game, &opponent, &entry, goal)?;
+            let letters = mapping
+                .me
+                .iter()
+                .sorted_by_key(|(letter, _)| **letter)
+                .map(|(letter, instruction)| match instruction {
+                    Instruction::Play(me) => format!("{}={}", letter, game.names[*me]),
+                    Instruction::Reach(outcome) => format!("{}={:?}", letter, outcome),
+                })
+                .join(" ");
+            println!("{:?}: {} ({})", goal, score, letters);
+        }
+        return Ok(());
+    }
+
+    println!("{}\n", ex1(&entry)?);
+    println!("{}\n", ex2(&entry)?);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -168,13 +279,89 @@ mod test {
 
     #[test]
     fn test_ex1() {
-        let entry = parse(&gen_input_test());
-        assert_eq!(ex1(&entry), 15);
+        let entry = parse(&gen_input_test()).unwrap();
+        assert_eq!(ex1(&entry), Ok(15));
     }
     #[test]
     fn test_ex2() {
-        let entry = parse(&gen_input_test());
+        let entry = parse(&gen_input_test()).unwrap();
         println!("{:?}", entry);
-        assert_eq!(ex2(&entry), 12);
+        assert_eq!(ex2(&entry), Ok(12));
+    }
+
+    #[test]
+    fn test_lizard_spock() {
+        let game = CyclicGame::rock_paper_scissors_lizard_spock();
+        let [rock, spock, paper, lizard, scissors] = [0, 1, 2, 3, 4];
+        assert_eq!(game.outcome(rock, spock), Outcome::Win);
+        assert_eq!(game.outcome(rock, paper), Outcome::Win);
+        assert_eq!(game.outcome(rock, lizard), Outcome::Lose);
+        assert_eq!(game.outcome(rock, scissors), Outcome::Lose);
+        assert_eq!(game.outcome(spock, scissors), Outcome::Lose);
+        assert_eq!(game.outcome(lizard, scissors), Outcome::Win);
+        assert_eq!(game.outcome(paper, lizard), Outcome::Win);
+        assert_eq!(game.outcome(lizard, lizard), Outcome::Draw);
+        // Paper and Spock both beat Rock, Paper scores more
+        assert_eq!(game.answer(rock, Outcome::Win), paper);
+
+        let mapping = Mapping::new("ABCDE", &[('V', Instruction::Reach(Outcome::Lose))]);
+        let entry = parse(&["A V".to_string(), "E V".to_string()]).unwrap();
+        // Scissors against Rock, then Lizard against Scissors
+        assert_eq!(total_score(&game, &mapping, &entry), Ok(5 + 4));
+
+        assert!(CyclicGame::new(vec!["a", "b"], vec![1, 2], [0, 3, 6]).is_err());
+    }
+
+    #[test]
+    fn test_optimise() {
+        let game = CyclicGame::rock_paper_scissors();
+        let opponent = Mapping::new("ABC", &[]).opponent;
+        let entry = parse(&gen_input_test()).unwrap();
+
+        let (mapping, score) = optimise(&game, &opponent, &entry, Goal::Maximise).unwrap();
+        // Win every round: Paper, Scissors, Rock
+        assert_eq!(score, 2 + 3 + 1 + 3 * 6);
+        assert_eq!(mapping.me[&'Y'], Instruction::Play(1));
+        assert_eq!(total_score(&game, &mapping, &entry), Ok(score));
+
+        let (mapping, score) = optimise(&game, &opponent, &entry, Goal::Minimise).unwrap();
+        assert_eq!(score, 3 + 1 + 2);
+        assert_eq!(total_score(&game, &mapping, &entry), Ok(score));
+
+        assert!(parse(&["A".to_string()]).is_err());
+        assert!(ex1(&vec![('D', 'X')]).is_err());
+    }
+
+    #[test]
+    fn test_optimise_lizard_spock() {
+        let game = CyclicGame::rock_paper_scissors_lizard_spock();
+        let opponent = Mapping::new("ABCDE", &[]).opponent;
+        let entry = parse(&gen_input_test()).unwrap();
+
+        let (mapping, score) = optimise(&game, &opponent, &entry, Goal::Maximise).unwrap();
+        // Win every round with the highest scoring move: Paper, Lizard, Scissors
+        assert_eq!(score, 3 + 4 + 5 + 3 * 6);
+        assert_eq!(
+            mapping.me,
+            HashMap::from([
+                ('X', Instruction::Play(3)),
+                ('Y', Instruction::Play(2)),
+                ('Z', Instruction::Play(4)),
+            ])
+        );
+        assert_eq!(total_score(&game, &mapping, &entry), Ok(score));
+
+        let (mapping, score) = optimise(&game, &opponent, &entry, Goal::Minimise).unwrap();
+        // X and Z would both lose with Rock, Z loses with Spock instead
+        assert_eq!(score, 1 + 4 + 2);
+        assert_eq!(
+            mapping.me,
+            HashMap::from([
+                ('X', Instruction::Play(0)),
+                ('Y', Instruction::Play(3)),
+                ('Z', Instruction::Play(1)),
+            ])
+        );
+        assert_eq!(total_score(&game, &mapping, &entry), Ok(score));
     }
 }
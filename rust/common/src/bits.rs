@@ -0,0 +1,443 @@
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Not};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitsError {
+    UnknownChar {
+        position: usize,
+        found: char,
+    },
+    /// Patterns hold at most 64 bits
+    TooWide {
+        width: usize,
+    },
+    WidthMismatch {
+        row: usize,
+        width: usize,
+        expected: usize,
+    },
+}
+
+impl fmt::Display for BitsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitsError::UnknownChar { position, found } => {
+                write!(f, "unexpected {:?} at position {}", found, position)
+            }
+            BitsError::TooWide { width } => write!(f, "{} bits do not fit in 64", width),
+            BitsError::WidthMismatch {
+                row,
+                width,
+                expected,
+            } => write!(f, "row {} has {} bits instead of {}", row, width, expected),
+        }
+    }
+}
+
+impl std::error::Error for BitsError {}
+
+/// Characters read as 0, as 1, and as a bit left open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alphabet<'a> {
+    pub zeros: &'a str,
+    pub ones: &'a str,
+    pub wildcards: &'a str,
+}
+
+pub const BINARY: Alphabet = Alphabet {
+    zeros: "0",
+    ones: "1",
+    wildcards: "",
+};
+
+pub const MASK: Alphabet = Alphabet {
+    zeros: "0",
+    ones: "1",
+    wildcards: "X",
+};
+
+impl Alphabet<'_> {
+    fn read(&self, position: usize, c: char) -> Result<Option<bool>, BitsError> {
+        if self.zeros.contains(c) {
+            Ok(Some(false))
+        } else if self.ones.contains(c) {
+            Ok(Some(true))
+        } else if self.wildcards.contains(c) {
+            Ok(None)
+        } else {
+            Err(BitsError::UnknownChar { position, found: c })
+        }
+    }
+}
+
+/// Bits of a string, the first character being the most significant bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Pattern {
+    /// Bits read as 1
+    pub value: u64,
+    /// Bits read as 0 or 1
+    pub mask: u64,
+    /// Bits left open
+    pub wildcards: u64,
+    pub width: usize,
+}
+
+impl Pattern {
+    pub fn parse(s: &str, alphabet: &Alphabet) -> Result<Self, BitsError> {
+        let width = s.chars().count();
+        if width > 64 {
+            return Err(BitsError::TooWide { width });
+        }
+        let mut pattern = Pattern {
+            width,
+            ..Pattern::default()
+        };
+        for (position, c) in s.chars().enumerate() {
+            let bit = 1 << (width - 1 - position);
+            match alphabet.read(position, c)? {
+                Some(one) => {
+                    pattern.mask |= bit;
+                    if one {
+                        pattern.value |= bit;
+                    }
+                }
+                None => pattern.wildcards |= bit,
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// Overwrites the bits of x given by the pattern
+    pub fn apply(&self, x: u64) -> u64 {
+        x & !self.mask | self.value
+    }
+
+    /// Every value the pattern matches
+    pub fn matches(&self) -> impl Iterator<Item = u64> {
+        let value = self.value;
+        subsets(self.wildcards).map(move |bits| value | bits)
+    }
+}
+
+/// Every subset of the bits of a mask, in increasing order, from 0 to the mask itself
+pub fn subsets(mask: u64) -> Subsets {
+    Subsets {
+        mask,
+        next: Some(0),
+    }
+}
+
+pub struct Subsets {
+    mask: u64,
+    next: Option<u64>,
+}
+
+impl Iterator for Subsets {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let current = self.next?;
+        // Adds 1 as if the bits of the mask were contiguous
+        let next = current.wrapping_sub(self.mask) & self.mask;
+        self.next = if next == 0 { None } else { Some(next) };
+        Some(current)
+    }
+}
+
+/// Bits of any width, bit 0 being the first character when parsed
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new(len: usize) -> Self {
+        BitVec {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn ones(len: usize) -> Self {
+        !BitVec::new(len)
+    }
+
+    /// Wildcards are not allowed
+    pub fn parse(s: &str, alphabet: &Alphabet) -> Result<Self, BitsError> {
+        let mut bits = BitVec::new(s.chars().count());
+        for (position, c) in s.chars().enumerate() {
+            match alphabet.read(position, c)? {
+                Some(one) => bits.set(position, one),
+                None => return Err(BitsError::UnknownChar { position, found: c }),
+            }
+        }
+        Ok(bits)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> bool {
+        assert!(i < self.len, "bit {} out of {}", i, self.len);
+        self.words[i / 64] >> (i % 64) & 1 == 1
+    }
+
+    pub fn set(&mut self, i: usize, one: bool) {
+        assert!(i < self.len, "bit {} out of {}", i, self.len);
+        if one {
+            self.words[i / 64] |= 1 << (i % 64);
+        } else {
+            self.words[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    /// New bits are 0
+    pub fn resize(&mut self, len: usize) {
+        self.words.resize(len.div_ceil(64), 0);
+        self.len = len;
+        *self = std::mem::take(self).trim();
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+
+    /// Positions of the bits set
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(w * 64 + bit)
+            })
+        })
+    }
+
+    /// Unsigned value, bit 0 being the most significant, None beyond 64 bits
+    pub fn to_u64(&self) -> Option<u64> {
+        if self.len > 64 {
+            return None;
+        }
+        Some(self.iter().fold(0, |value, one| value << 1 | one as u64))
+    }
+
+    // Clears the bits past the length in the last word
+    fn trim(mut self) -> Self {
+        if !self.len.is_multiple_of(64) {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << (self.len % 64)) - 1;
+            }
+        }
+        self
+    }
+}
+
+impl BitAnd for &BitVec {
+    type Output = BitVec;
+    fn bitand(self, other: &BitVec) -> BitVec {
+        assert_eq!(self.len, other.len, "bit vectors of different lengths");
+        BitVec {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a & b)
+                .collect(),
+            len: self.len,
+        }
+    }
+}
+
+impl BitOr for &BitVec {
+    type Output = BitVec;
+    fn bitor(self, other: &BitVec) -> BitVec {
+        assert_eq!(self.len, other.len, "bit vectors of different lengths");
+        BitVec {
+            words: self
+                .words
+                .iter()
+                .zip(&other.words)
+                .map(|(a, b)| a | b)
+                .collect(),
+            len: self.len,
+        }
+    }
+}
+
+impl Not for BitVec {
+    type Output = BitVec;
+    fn not(mut self) -> BitVec {
+        for word in self.words.iter_mut() {
+            *word = !*word;
+        }
+        self.trim()
+    }
+}
+
+impl Not for &BitVec {
+    type Output = BitVec;
+    fn not(self) -> BitVec {
+        !self.clone()
+    }
+}
+
+impl fmt::Display for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for one in self.iter() {
+            write!(f, "{}", one as u8)?;
+        }
+        Ok(())
+    }
+}
+
+/// Rows of equal width stored column by column, so that counting the ones of a
+/// column among any subset of rows is a popcount
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Columns {
+    columns: Vec<BitVec>,
+    height: usize,
+}
+
+impl Columns {
+    pub fn new(rows: &[BitVec]) -> Result<Self, BitsError> {
+        let width = rows.first().map_or(0, BitVec::len);
+        let mut columns = vec![BitVec::new(rows.len()); width];
+        for (r, row) in rows.iter().enumerate() {
+            if row.len() != width {
+                return Err(BitsError::WidthMismatch {
+                    row: r,
+                    width: row.len(),
+                    expected: width,
+                });
+            }
+            for c in row.iter_ones() {
+                columns[c].set(r, true);
+            }
+        }
+        Ok(Columns {
+            columns,
+            height: rows.len(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Rows with a 1 in this column
+    pub fn column(&self, c: usize) -> &BitVec {
+        &self.columns[c]
+    }
+
+    /// Number of 1 in the column among the selected rows
+    pub fn ones(&self, c: usize, rows: &BitVec) -> usize {
+        (&self.columns[c] & rows).count_ones()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pattern() {
+        let pattern = Pattern::parse("X1XX0", &MASK).unwrap();
+        assert_eq!(pattern.value, 0b01000);
+        assert_eq!(pattern.mask, 0b01001);
+        assert_eq!(pattern.wildcards, 0b10110);
+        assert_eq!(pattern.apply(0b00011), 0b01010);
+        assert_eq!(pattern.matches().count(), 8);
+        assert!(pattern.matches().all(|x| pattern.apply(x) == x));
+
+        let seats = Alphabet {
+            zeros: "FL",
+            ones: "BR",
+            wildcards: "",
+        };
+        assert_eq!(Pattern::parse("FBFBBFFRLR", &seats).unwrap().value, 357);
+        assert_eq!(
+            Pattern::parse("FBX", &seats),
+            Err(BitsError::UnknownChar {
+                position: 2,
+                found: 'X'
+            })
+        );
+        assert_eq!(
+            Pattern::parse(&"1".repeat(65), &BINARY),
+            Err(BitsError::TooWide { width: 65 })
+        );
+        assert_eq!(
+            Pattern::parse(&"1".repeat(64), &BINARY).unwrap().value,
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_subsets() {
+        assert_eq!(
+            subsets(0b1010).collect::<Vec<_>>(),
+            vec![0, 0b10, 0b1000, 0b1010]
+        );
+        assert_eq!(subsets(0).collect::<Vec<_>>(), vec![0]);
+        assert_eq!(subsets(1 << 63 | 1).count(), 4);
+    }
+
+    #[test]
+    fn test_bit_vec() {
+        let bits = BitVec::parse(&"10".repeat(50), &BINARY).unwrap();
+        assert_eq!(bits.len(), 100);
+        assert_eq!(bits.count_ones(), 50);
+        assert!(bits.get(98) && !bits.get(99));
+        assert_eq!((!&bits).count_ones(), 50);
+        assert_eq!((&bits & &!&bits).count_ones(), 0);
+        assert_eq!(BitVec::ones(70).count_ones(), 70);
+        assert_eq!(bits.iter_ones().take(3).collect::<Vec<_>>(), vec![0, 2, 4]);
+        assert_eq!(bits.iter_ones().last(), Some(98));
+        assert_eq!(bits.to_u64(), None);
+        assert_eq!(BitVec::parse("0110", &BINARY).unwrap().to_u64(), Some(6));
+        assert_eq!(bits.to_string(), "10".repeat(50));
+        assert_eq!((&bits | &!&bits), BitVec::ones(100));
+
+        let mut resized = bits.clone();
+        resized.resize(3);
+        assert_eq!(resized.to_string(), "101");
+        resized.resize(130);
+        assert_eq!(resized.count_ones(), 2);
+        assert!(!resized.get(129));
+    }
+
+    #[test]
+    fn test_columns() {
+        let rows = ["00100", "11110", "10110", "10111"]
+            .iter()
+            .map(|row| BitVec::parse(row, &BINARY).unwrap())
+            .collect::<Vec<_>>();
+        let columns = Columns::new(&rows).unwrap();
+        assert_eq!((columns.width(), columns.height()), (5, 4));
+        let all = BitVec::ones(4);
+        assert_eq!(
+            (0..5).map(|c| columns.ones(c, &all)).collect::<Vec<_>>(),
+            vec![3, 1, 4, 3, 1]
+        );
+        assert_eq!(columns.ones(0, columns.column(1)), 1);
+        assert!(Columns::new(&[rows[0].clone(), BitVec::new(3)]).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// State number `start + length` is state number `start` again.
+/// A fixed point is a cycle of length 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    pub fn is_fixed_point(&self) -> bool {
+        self.length == 1
+    }
+
+    /// Step, before the end of the first cycle, at which the state is the one after n steps
+    pub fn equivalent_step(&self, n: u64) -> usize {
+        if n < self.start as u64 {
+            n as usize
+        } else {
+            self.start + ((n - self.start as u64) % self.length as u64) as usize
+        }
+    }
+}
+
+/// Remembers the states visited one after another, until one comes back
+#[derive(Debug, Clone)]
+pub struct CycleDetector<S> {
+    seen: HashMap<S, usize>,
+}
+
+impl<S: Eq + Hash> Default for CycleDetector<S> {
+    fn default() -> Self {
+        CycleDetector {
+            seen: HashMap::new(),
+        }
+    }
+}
+
+impl<S: Eq + Hash> CycleDetector<S> {
+    pub fn new() -> Self {
+        CycleDetector::default()
+    }
+
+    /// Records the state of the next step, or returns the cycle it closes
+    pub fn visit(&mut self, state: S) -> Option<Cycle> {
+        let step = self.seen.len();
+        match self.seen.get(&state) {
+            Some(start) => Some(Cycle {
+                start: *start,
+                length: step - start,
+            }),
+            None => {
+                self.seen.insert(state, step);
+                None
+            }
+        }
+    }
+
+    /// Number of distinct states visited
+    pub fn steps(&self) -> usize {
+        self.seen.len()
+    }
+}
+
+/// Every state up to the end of the first cycle
+#[derive(Debug, Clone)]
+pub struct History<S> {
+    states: Vec<S>,
+    cycle: Cycle,
+}
+
+impl<S> History<S> {
+    pub fn cycle(&self) -> Cycle {
+        self.cycle
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    /// State after n steps, however large n is
+    pub fn nth(&self, n: u64) -> &S {
+        &self.states[self.cycle.equivalent_step(n)]
+    }
+
+    pub fn fixed_point(&self) -> Option<&S> {
+        if self.cycle.is_fixed_point() {
+            self.states.last()
+        } else {
+            None
+        }
+    }
+}
+
+/// Applies `step` from `initial` until a state repeats.
+/// Never returns if the states do not cycle.
+pub fn detect<S: Clone + Eq + Hash>(initial: S, mut step: impl FnMut(&S) -> S) -> History<S> {
+    let mut detector = CycleDetector::new();
+    let mut states = vec![];
+    let mut state = initial;
+    loop {
+        if let Some(cycle) = detector.visit(state.clone()) {
+            return History { states, cycle };
+        }
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+}
+
+/// Brent's algorithm: same cycle as `detect`, storing no state but computing more steps
+pub fn brent<S: Clone + PartialEq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle {
+    // Grow a window of power of two size until the hare meets the tortoise in it
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    // Then walk two states `length` apart until they meet at the start of the cycle
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+    Cycle { start, length }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        // 3, 10, 5, 16, 8, 4, 2, 1, 4, ...
+        let collatz = |n: &u64| if n.is_multiple_of(2) { n / 2 } else { 3 * n + 1 };
+        let history = detect(3, collatz);
+        let cycle = Cycle {
+            start: 5,
+            length: 3,
+        };
+        assert_eq!(history.cycle(), cycle);
+        assert_eq!(brent(3, collatz), cycle);
+        assert_eq!(history.states().len(), 8);
+        assert_eq!(*history.nth(2), 5);
+        assert_eq!(*history.nth(1_000_000_000_000), 1);
+        assert_eq!(history.fixed_point(), None);
+
+        let history = detect(100u32, |n| n / 2);
+        assert!(history.cycle().is_fixed_point());
+        assert_eq!(history.fixed_point(), Some(&0));
+        assert_eq!(brent(100u32, |n| n / 2), history.cycle());
+    }
+
+    #[test]
+    fn test_brent() {
+        for modulus in 1..40u64 {
+            for start in 0..modulus {
+                let step = |n: &u64| (n * n + 1) % modulus;
+                assert_eq!(brent(start, step), detect(start, step).cycle());
+            }
+        }
+    }
+
+    #[test]
+    fn test_detector() {
+        let mut detector = CycleDetector::new();
+        assert_eq!(detector.visit("a"), None);
+        assert_eq!(detector.visit("b"), None);
+        assert_eq!(
+            detector.visit("a"),
+            Some(Cycle {
+                start: 0,
+                length: 2
+            })
+        );
+        assert_eq!(detector.steps(), 2);
+    }
+}
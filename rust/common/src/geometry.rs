@@ -0,0 +1,364 @@
+use std::fmt;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+pub type Coordinate = i64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeometryError {
+    /// Only multiples of 90° keep integer coordinates
+    NotRightAngle(Coordinate),
+    UnknownDirection(char),
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeometryError::NotRightAngle(degrees) => {
+                write!(f, "{}° is not a multiple of 90°", degrees)
+            }
+            GeometryError::UnknownDirection(c) => write!(f, "{:?} is not a direction", c),
+        }
+    }
+}
+
+impl std::error::Error for GeometryError {}
+
+/// Number of counterclockwise quarter turns, in 0..4
+pub fn quarter_turns(degrees: Coordinate) -> Result<u8, GeometryError> {
+    if degrees % 90 != 0 {
+        return Err(GeometryError::NotRightAngle(degrees));
+    }
+    Ok((degrees / 90).rem_euclid(4) as u8)
+}
+
+/// Point, or vector, with N integer coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PointN<const N: usize>(pub [Coordinate; N]);
+
+pub type Point2 = PointN<2>;
+pub type Point3 = PointN<3>;
+
+impl<const N: usize> PointN<N> {
+    pub const ORIGIN: Self = PointN([0; N]);
+
+    pub fn coordinates(&self) -> &[Coordinate; N] {
+        &self.0
+    }
+
+    pub fn manhattan(&self) -> Coordinate {
+        self.0.iter().map(|c| c.abs()).sum()
+    }
+
+    pub fn chebyshev(&self) -> Coordinate {
+        self.0.iter().map(|c| c.abs()).max().unwrap_or(0)
+    }
+
+    pub fn manhattan_distance(&self, other: &Self) -> Coordinate {
+        (*self - *other).manhattan()
+    }
+
+    pub fn chebyshev_distance(&self, other: &Self) -> Coordinate {
+        (*self - *other).chebyshev()
+    }
+
+    /// The 3^N - 1 points at Chebyshev distance 1
+    pub fn neighbours(&self) -> impl Iterator<Item = Self> + '_ {
+        (0..3usize.pow(N as u32))
+            .map(|mut code| {
+                let mut offset = [0; N];
+                for c in offset.iter_mut() {
+                    *c = (code % 3) as Coordinate - 1;
+                    code /= 3;
+                }
+                PointN(offset)
+            })
+            .filter(|offset| *offset != PointN::ORIGIN)
+            .map(move |offset| *self + offset)
+    }
+
+    fn zip_with(self, other: Self, f: impl Fn(Coordinate, Coordinate) -> Coordinate) -> Self {
+        let mut coordinates = self.0;
+        for (c, o) in coordinates.iter_mut().zip(other.0) {
+            *c = f(*c, o);
+        }
+        PointN(coordinates)
+    }
+}
+
+impl Point2 {
+    pub fn new(x: Coordinate, y: Coordinate) -> Self {
+        PointN([x, y])
+    }
+
+    pub fn x(&self) -> Coordinate {
+        self.0[0]
+    }
+
+    pub fn y(&self) -> Coordinate {
+        self.0[1]
+    }
+
+    /// Rotation about the origin, counterclockwise when y points up
+    pub fn rotate(&self, quarter_turns: u8) -> Self {
+        let [x, y] = self.0;
+        match quarter_turns % 4 {
+            0 => Point2::new(x, y),
+            1 => Point2::new(-y, x),
+            2 => Point2::new(-x, -y),
+            _ => Point2::new(y, -x),
+        }
+    }
+
+    pub fn rotate_degrees(&self, degrees: Coordinate) -> Result<Self, GeometryError> {
+        Ok(self.rotate(quarter_turns(degrees)?))
+    }
+}
+
+impl Point3 {
+    pub fn new(x: Coordinate, y: Coordinate, z: Coordinate) -> Self {
+        PointN([x, y, z])
+    }
+}
+
+impl<const N: usize> Default for PointN<N> {
+    fn default() -> Self {
+        PointN::ORIGIN
+    }
+}
+
+impl<const N: usize> From<[Coordinate; N]> for PointN<N> {
+    fn from(coordinates: [Coordinate; N]) -> Self {
+        PointN(coordinates)
+    }
+}
+
+impl<const N: usize> Add for PointN<N> {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a + b)
+    }
+}
+
+impl<const N: usize> Sub for PointN<N> {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a - b)
+    }
+}
+
+impl<const N: usize> AddAssign for PointN<N> {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const N: usize> SubAssign for PointN<N> {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl<const N: usize> Mul<Coordinate> for PointN<N> {
+    type Output = Self;
+    fn mul(self, factor: Coordinate) -> Self {
+        PointN(self.0.map(|c| c * factor))
+    }
+}
+
+impl<const N: usize> Neg for PointN<N> {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self * -1
+    }
+}
+
+impl<const N: usize> fmt::Display for PointN<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, c) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", c)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Compass direction, north being y + 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    /// Clockwise from North
+    pub const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    /// N, E, S, W or U, R, D, L
+    pub fn from_letter(letter: char) -> Result<Self, GeometryError> {
+        match letter {
+            'N' | 'U' => Ok(Direction::North),
+            'E' | 'R' => Ok(Direction::East),
+            'S' | 'D' => Ok(Direction::South),
+            'W' | 'L' => Ok(Direction::West),
+            _ => Err(GeometryError::UnknownDirection(letter)),
+        }
+    }
+
+    pub fn unit(self) -> Point2 {
+        match self {
+            Direction::North => Point2::new(0, 1),
+            Direction::East => Point2::new(1, 0),
+            Direction::South => Point2::new(0, -1),
+            Direction::West => Point2::new(-1, 0),
+        }
+    }
+
+    /// Counterclockwise quarter turns
+    pub fn rotate(self, quarter_turns: u8) -> Self {
+        let index = Direction::ALL.iter().position(|d| *d == self).unwrap();
+        Direction::ALL[(index + 4 - quarter_turns as usize % 4) % 4]
+    }
+
+    pub fn rotate_degrees(self, degrees: Coordinate) -> Result<Self, GeometryError> {
+        Ok(self.rotate(quarter_turns(degrees)?))
+    }
+
+    pub fn turn_left(self) -> Self {
+        self.rotate(1)
+    }
+
+    pub fn turn_right(self) -> Self {
+        self.rotate(3)
+    }
+
+    pub fn reverse(self) -> Self {
+        self.rotate(2)
+    }
+}
+
+/// Smallest box holding some points, bounds included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoundingBox<const N: usize> {
+    pub min: PointN<N>,
+    pub max: PointN<N>,
+}
+
+impl<const N: usize> BoundingBox<N> {
+    pub fn new(point: PointN<N>) -> Self {
+        BoundingBox {
+            min: point,
+            max: point,
+        }
+    }
+
+    /// None when there is no point
+    pub fn from_points(points: impl IntoIterator<Item = PointN<N>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let mut bounding_box = BoundingBox::new(points.next()?);
+        for point in points {
+            bounding_box.include(point);
+        }
+        Some(bounding_box)
+    }
+
+    pub fn include(&mut self, point: PointN<N>) {
+        self.min = self.min.zip_with(point, Coordinate::min);
+        self.max = self.max.zip_with(point, Coordinate::max);
+    }
+
+    pub fn contains(&self, point: &PointN<N>) -> bool {
+        (0..N).all(|i| self.min.0[i] <= point.0[i] && point.0[i] <= self.max.0[i])
+    }
+
+    /// Grown by `margin` on every side
+    pub fn expand(&self, margin: Coordinate) -> Self {
+        BoundingBox {
+            min: self.min - PointN([margin; N]),
+            max: self.max + PointN([margin; N]),
+        }
+    }
+
+    /// Number of integer points along each axis
+    pub fn size(&self) -> PointN<N> {
+        self.max - self.min + PointN([1; N])
+    }
+
+    /// Number of integer points inside
+    pub fn volume(&self) -> u64 {
+        self.size().0.iter().map(|c| *c as u64).product()
+    }
+
+    /// Every integer point inside, the first coordinate changing the fastest
+    pub fn points(&self) -> impl Iterator<Item = PointN<N>> + '_ {
+        let size = self.size();
+        (0..self.volume()).map(move |mut code| {
+            let mut point = self.min;
+            for i in 0..N {
+                point.0[i] += (code % size.0[i] as u64) as Coordinate;
+                code /= size.0[i] as u64;
+            }
+            point
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rotations() {
+        let p = Point2::new(10, 4);
+        assert_eq!(p.rotate(1), Point2::new(-4, 10));
+        assert_eq!(p.rotate_degrees(-90), Ok(Point2::new(4, -10)));
+        assert_eq!(p.rotate_degrees(450), Ok(p.rotate(1)));
+        assert_eq!(p.rotate_degrees(360), Ok(p));
+        assert_eq!(p.rotate_degrees(45), Err(GeometryError::NotRightAngle(45)));
+
+        assert_eq!(Direction::East.turn_left(), Direction::North);
+        assert_eq!(Direction::East.turn_right(), Direction::South);
+        assert_eq!(Direction::North.reverse(), Direction::South);
+        assert_eq!(Direction::West.rotate_degrees(-270), Ok(Direction::South));
+        for direction in Direction::ALL {
+            assert_eq!(direction.turn_left().unit(), direction.unit().rotate(1));
+        }
+    }
+
+    #[test]
+    fn test_distances() {
+        let a = Point3::new(1, -2, 3);
+        let b = Point3::new(-1, 2, 4);
+        assert_eq!(a.manhattan_distance(&b), 7);
+        assert_eq!(a.chebyshev_distance(&b), 4);
+        assert_eq!(-a + b * 2, Point3::new(-3, 6, 5));
+        assert_eq!(PointN([0; 4]).neighbours().count(), 80);
+        assert!(Point2::ORIGIN
+            .neighbours()
+            .all(|n| n.chebyshev() == 1 && n.manhattan() <= 2));
+        assert_eq!(a.to_string(), "(1, -2, 3)");
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let points = [Point2::new(1, 5), Point2::new(-2, 3), Point2::new(0, 7)];
+        let bounding_box = BoundingBox::from_points(points).unwrap();
+        assert_eq!(bounding_box.min, Point2::new(-2, 3));
+        assert_eq!(bounding_box.max, Point2::new(1, 7));
+        assert_eq!(bounding_box.volume(), 20);
+        assert_eq!(bounding_box.points().count(), 20);
+        assert!(bounding_box.points().all(|p| bounding_box.contains(&p)));
+        assert!(!bounding_box.contains(&Point2::new(2, 5)));
+        assert_eq!(bounding_box.expand(1).volume(), 42);
+        assert_eq!(BoundingBox::<3>::from_points([]), None);
+    }
+}
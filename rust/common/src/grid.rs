@@ -0,0 +1,443 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// (row, column)
+pub type Position = (usize, usize);
+
+/// (row delta, column delta)
+pub type Offset = (isize, isize);
+
+pub const NEIGHBOURS_4: [Offset; 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+pub const NEIGHBOURS_8: [Offset; 8] = [
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    Ragged {
+        row: usize,
+        width: usize,
+        expected: usize,
+    },
+    UnknownChar {
+        position: Position,
+        found: char,
+    },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "the grid has no cell"),
+            GridError::Ragged {
+                row,
+                width,
+                expected,
+            } => write!(f, "row {} has {} cells instead of {}", row, width, expected),
+            GridError::UnknownChar { position, found } => {
+                write!(f, "unexpected {:?} at {:?}", found, position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+/// Which axes go around: moving past the last column comes back to the first one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Wrap {
+    pub rows: bool,
+    pub columns: bool,
+}
+
+/// Rectangular grid stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+    wrap: Wrap,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+            wrap: Wrap::default(),
+        }
+    }
+
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Self, GridError> {
+        let width = rows.first().map_or(0, Vec::len);
+        if width == 0 {
+            return Err(GridError::Empty);
+        }
+        if let Some((row, cells)) = rows.iter().enumerate().find(|(_, r)| r.len() != width) {
+            return Err(GridError::Ragged {
+                row,
+                width: cells.len(),
+                expected: width,
+            });
+        }
+        Ok(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+            wrap: Wrap::default(),
+        })
+    }
+
+    /// Reads a char map, `mapping` returns None on the chars it does not expect.
+    pub fn parse<S: AsRef<str>>(
+        lines: &[S],
+        mapping: impl Fn(char) -> Option<T>,
+    ) -> Result<Self, GridError> {
+        let rows = lines
+            .iter()
+            .enumerate()
+            .map(|(row, line)| {
+                line.as_ref()
+                    .chars()
+                    .enumerate()
+                    .map(|(column, c)| {
+                        mapping(c).ok_or(GridError::UnknownChar {
+                            position: (row, column),
+                            found: c,
+                        })
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<T>>, _>>()?;
+        Grid::from_rows(rows)
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, (row, column): Position) -> Option<&T> {
+        if row < self.height && column < self.width {
+            Some(&self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, (row, column): Position) -> Option<&mut T> {
+        if row < self.height && column < self.width {
+            Some(&mut self.cells[row * self.width + column])
+        } else {
+            None
+        }
+    }
+
+    /// Moves from a position, going around the wrapped axes; None when leaving the grid.
+    pub fn offset(&self, (row, column): Position, (d_row, d_column): Offset) -> Option<Position> {
+        let row = Self::shift(row, d_row, self.height, self.wrap.rows)?;
+        let column = Self::shift(column, d_column, self.width, self.wrap.columns)?;
+        Some((row, column))
+    }
+
+    fn shift(value: usize, delta: isize, size: usize, wrap: bool) -> Option<usize> {
+        if wrap {
+            let size = size as isize;
+            Some((value as isize + delta % size + size) as usize % size as usize)
+        } else {
+            let shifted = value.checked_add_signed(delta)?;
+            (shifted < size).then_some(shifted)
+        }
+    }
+
+    pub fn neighbours_4(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        NEIGHBOURS_4
+            .iter()
+            .filter_map(move |offset| self.offset(position, *offset))
+    }
+
+    pub fn neighbours_8(&self, position: Position) -> impl Iterator<Item = Position> + '_ {
+        NEIGHBOURS_8
+            .iter()
+            .filter_map(move |offset| self.offset(position, *offset))
+    }
+
+    /// Positions met going straight from `start` (excluded), until the border or back to `start`.
+    pub fn ray(&self, start: Position, direction: Offset) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            start,
+            current: Some(start),
+            direction,
+        }
+    }
+
+    /// Every position, row by row
+    pub fn positions(&self) -> impl Iterator<Item = Position> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |column| (row, column)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn values(&self) -> std::slice::Iter<'_, T> {
+        self.cells.iter()
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> std::slice::Chunks<'_, T> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn column(&self, column: usize) -> impl Iterator<Item = &T> {
+        self.cells[column..].iter().step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(move |column| self.column(column))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+            wrap: self.wrap,
+        }
+    }
+
+    /// Builds a grid of the same shape from the position of each cell
+    pub fn map_positions<U>(&self, f: impl Fn(Position, &T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self
+                .iter()
+                .map(|(position, value)| f(position, value))
+                .collect(),
+            wrap: self.wrap,
+        }
+    }
+
+    pub fn transpose(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells: self.columns().flatten().cloned().collect(),
+            wrap: Wrap {
+                rows: self.wrap.columns,
+                columns: self.wrap.rows,
+            },
+        }
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let mut rotated = self.transpose();
+        rotated
+            .cells
+            .chunks_mut(rotated.width)
+            .for_each(|row| row.reverse());
+        rotated
+    }
+
+    pub fn rotate_counterclockwise(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let mut rotated = self.transpose();
+        rotated.flip_rows();
+        rotated
+    }
+
+    // Upside down
+    fn flip_rows(&mut self) {
+        let width = self.width;
+        for row in 0..self.height / 2 {
+            let other = self.height - 1 - row;
+            for column in 0..width {
+                self.cells
+                    .swap(row * width + column, other * width + column);
+            }
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        self.get(position)
+            .unwrap_or_else(|| panic!("{:?} is outside of the grid", position))
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        self.get_mut(position)
+            .unwrap_or_else(|| panic!("{:?} is outside of the grid", position))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    start: Position,
+    current: Option<Position>,
+    direction: Offset,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = Position;
+
+    fn next(&mut self) -> Option<Position> {
+        let next = self
+            .grid
+            .offset(self.current?, self.direction)
+            .filter(|next| *next != self.start);
+        self.current = next;
+        next
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn example() -> Grid<char> {
+        Grid::parse(&["abc", "def"], Some).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(1, 0)], 'd');
+        assert_eq!(grid.get((2, 0)), None);
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+
+        let digits = Grid::parse(&["12", "3x"], |c| c.to_digit(10));
+        assert_eq!(
+            digits,
+            Err(GridError::UnknownChar {
+                position: (1, 1),
+                found: 'x'
+            })
+        );
+        assert_eq!(
+            Grid::parse(&["ab", "c"], Some),
+            Err(GridError::Ragged {
+                row: 1,
+                width: 1,
+                expected: 2
+            })
+        );
+        assert_eq!(
+            Grid::<char>::parse::<&str>(&[], Some),
+            Err(GridError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = example();
+        assert_eq!(
+            grid.neighbours_4((0, 0)).collect::<Vec<_>>(),
+            vec![(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbours_8((0, 1)).count(), 5);
+
+        let wrapped = example().with_wrap(Wrap {
+            rows: true,
+            columns: true,
+        });
+        assert_eq!(wrapped.neighbours_4((0, 0)).count(), 4);
+        assert_eq!(wrapped.offset((0, 0), (-1, -1)), Some((1, 2)));
+        assert_eq!(wrapped.offset((0, 0), (5, 7)), Some((1, 1)));
+    }
+
+    #[test]
+    fn test_ray() {
+        let grid = example();
+        assert_eq!(
+            grid.ray((0, 0), (0, 1)).collect::<Vec<_>>(),
+            vec![(0, 1), (0, 2)]
+        );
+        assert_eq!(grid.ray((1, 2), (-1, -1)).collect::<Vec<_>>(), vec![(0, 1)]);
+
+        let wrapped = example().with_wrap(Wrap {
+            rows: false,
+            columns: true,
+        });
+        assert_eq!(
+            wrapped.ray((0, 2), (1, 1)).collect::<Vec<_>>(),
+            vec![(1, 0)]
+        );
+        // Stops when coming back to the start
+        assert_eq!(wrapped.ray((0, 0), (0, 1)).count(), 2);
+    }
+
+    #[test]
+    fn test_views() {
+        let grid = example();
+        assert_eq!(grid.row(1), &['d', 'e', 'f']);
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_clockwise().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_counterclockwise().to_string(), "cf\nbe\nad\n");
+        assert_eq!(
+            grid.rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise()
+                .rotate_clockwise(),
+            grid
+        );
+        assert_eq!(grid.map(|c| c.is_ascii_lowercase()).values().count(), 6);
+        assert_eq!(
+            grid.map_positions(|(row, column), _| row * 10 + column)[(1, 2)],
+            12
+        );
+    }
+}
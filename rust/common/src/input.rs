@@ -0,0 +1,207 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+use crate::grid::{Grid, GridError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line of the input, starting at 0
+    pub line: usize,
+    pub token: String,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: cannot parse {:?}: {}",
+            self.line + 1,
+            self.token,
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Paragraph whose first line may be a header ending with ':'
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'a> {
+    pub header: Option<&'a str>,
+    pub lines: Vec<&'a str>,
+}
+
+/// Puzzle input, read whole. Line endings may be CRLF or LF, the final newline and
+/// trailing blank lines do not matter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Input {
+    text: String,
+}
+
+impl Input {
+    pub fn new(text: &str) -> Self {
+        Input {
+            text: text.replace("\r\n", "\n").trim_end().to_string(),
+        }
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(Input::new(&text))
+    }
+
+    pub fn from_stdin() -> io::Result<Self> {
+        Input::from_reader(io::stdin().lock())
+    }
+
+    pub fn from_lines<S: AsRef<str>>(lines: &[S]) -> Self {
+        lines.iter().collect()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn lines(&self) -> Vec<&str> {
+        if self.text.is_empty() {
+            return vec![];
+        }
+        self.text.split('\n').collect()
+    }
+
+    /// Groups of lines separated by blank lines, never empty
+    pub fn paragraphs(&self) -> Vec<Vec<&str>> {
+        let mut paragraphs = vec![];
+        let mut current = vec![];
+        for line in self.lines() {
+            if line.trim().is_empty() {
+                if !current.is_empty() {
+                    paragraphs.push(std::mem::take(&mut current));
+                }
+            } else {
+                current.push(line);
+            }
+        }
+        if !current.is_empty() {
+            paragraphs.push(current);
+        }
+        paragraphs
+    }
+
+    /// Every number of the input, separated by whitespace or commas
+    pub fn numbers<T>(&self) -> Result<Vec<T>, ParseError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let mut numbers = vec![];
+        for (line, content) in self.lines().into_iter().enumerate() {
+            for token in content
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|token| !token.is_empty())
+            {
+                numbers.push(token.parse().map_err(|error: T::Err| ParseError {
+                    line,
+                    token: token.to_string(),
+                    message: error.to_string(),
+                })?);
+            }
+        }
+        Ok(numbers)
+    }
+
+    pub fn grid<T>(&self, mapping: impl Fn(char) -> Option<T>) -> Result<Grid<T>, GridError> {
+        Grid::parse(&self.lines(), mapping)
+    }
+
+    pub fn sections_by_header(&self) -> Vec<Section<'_>> {
+        self.paragraphs()
+            .into_iter()
+            .map(|lines| match lines[0].trim_end().strip_suffix(':') {
+                Some(header) => Section {
+                    header: Some(header),
+                    lines: lines[1..].to_vec(),
+                },
+                None => Section {
+                    header: None,
+                    lines,
+                },
+            })
+            .collect()
+    }
+
+    pub fn section(&self, header: &str) -> Option<Vec<&str>> {
+        self.sections_by_header()
+            .into_iter()
+            .find(|section| section.header == Some(header))
+            .map(|section| section.lines)
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<S> for Input {
+    fn from_iter<I: IntoIterator<Item = S>>(lines: I) -> Self {
+        let text = lines
+            .into_iter()
+            .map(|line| line.as_ref().to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Input::new(&text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_line_endings() {
+        let expected = vec!["a", "", "b"];
+        assert_eq!(Input::new("a\n\nb").lines(), expected);
+        assert_eq!(Input::new("a\n\nb\n").lines(), expected);
+        assert_eq!(Input::new("a\r\n\r\nb\r\n\r\n\r\n").lines(), expected);
+        assert_eq!(Input::new("").lines(), Vec::<&str>::new());
+        assert_eq!(Input::new("\n\n").paragraphs(), Vec::<Vec<&str>>::new());
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let input = Input::new("a\nb\n\n\nc\n  \nd\n");
+        assert_eq!(
+            input.paragraphs(),
+            vec![vec!["a", "b"], vec!["c"], vec!["d"]]
+        );
+        assert_eq!(
+            Input::from_lines(&["a", "b", "", "c"]).paragraphs().len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_numbers() {
+        let input = Input::new("1,2, 3\n-4\n");
+        assert_eq!(input.numbers::<i32>(), Ok(vec![1, 2, 3, -4]));
+        let error = Input::new("1\n2 x\n").numbers::<u8>().unwrap_err();
+        assert_eq!((error.line, error.token.as_str()), (1, "x"));
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = Input::new("a: 1\nb: 2\n\nyour ticket:\n7,1\r\n\r\nPlayer 2:\n5\n8");
+        let sections = input.sections_by_header();
+        assert_eq!(sections[0].header, None);
+        assert_eq!(sections[0].lines, vec!["a: 1", "b: 2"]);
+        assert_eq!(input.section("your ticket"), Some(vec!["7,1"]));
+        assert_eq!(input.section("Player 2"), Some(vec!["5", "8"]));
+        assert_eq!(input.section("Player 1"), None);
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Input::new("#.\r\n.#\r\n").grid(|c| Some(c == '#')).unwrap();
+        assert!(grid[(1, 1)]);
+        assert_eq!(grid.height(), 2);
+    }
+}
@@ -0,0 +1,429 @@
+use std::any::type_name;
+use std::fmt;
+use std::hash::Hash;
+use std::iter::{Product, Sum};
+use std::num::ParseIntError;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Rem, Sub, SubAssign};
+use std::str::FromStr;
+
+/// Integers a solver can be generic over: primitive integers, their `Checked` version,
+/// and big integers with the `bignum` feature
+pub trait Integer:
+    Clone
+    + Ord
+    + Hash
+    + fmt::Debug
+    + fmt::Display
+    + FromStr<Err: std::error::Error + Send + Sync + 'static>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + Sum
+    + Product
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    /// None when it does not fit
+    fn from_u64(n: u64) -> Option<Self>;
+    fn to_usize(&self) -> Option<usize>;
+    fn from_i128(n: i128) -> Option<Self>;
+    fn to_i128(&self) -> Option<i128>;
+    /// None when the result is out of the range of the type, or on a division by zero
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+    fn checked_div(&self, other: &Self) -> Option<Self>;
+    fn checked_rem(&self, other: &Self) -> Option<Self>;
+}
+
+/// Primitive integers, which `Checked` wraps
+pub trait Primitive: Integer + Copy + FromStr<Err = ParseIntError> {}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn zero() -> Self {
+                    0
+                }
+                fn one() -> Self {
+                    1
+                }
+                fn from_u64(n: u64) -> Option<Self> {
+                    Self::try_from(n).ok()
+                }
+                fn to_usize(&self) -> Option<usize> {
+                    usize::try_from(*self).ok()
+                }
+                fn from_i128(n: i128) -> Option<Self> {
+                    Self::try_from(n).ok()
+                }
+                fn to_i128(&self) -> Option<i128> {
+                    i128::try_from(*self).ok()
+                }
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *other)
+                }
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+                fn checked_div(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_div(*self, *other)
+                }
+                fn checked_rem(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_rem(*self, *other)
+                }
+            }
+
+            impl Primitive for $t {}
+        )*
+    };
+}
+
+impl_primitive!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Same values as T, but every operation out of the range of T panics
+/// with the operands and the line of the operation, in release builds too
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Checked<T>(pub T);
+
+impl<T: Primitive> Checked<T> {
+    #[track_caller]
+    fn apply(self, operator: &str, other: Self, operation: fn(&T, &T) -> Option<T>) -> Self {
+        match operation(&self.0, &other.0) {
+            Some(result) => Checked(result),
+            None if matches!(operator, "/" | "%") && other.0 == T::zero() => {
+                panic!("{} {} 0: division by zero", self.0, operator)
+            }
+            None => panic!(
+                "{} {} {} overflows {}",
+                self.0,
+                operator,
+                other.0,
+                type_name::<T>()
+            ),
+        }
+    }
+}
+
+macro_rules! impl_checked_operator {
+    ($($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $checked:ident, $operator:literal);*) => {
+        $(
+            impl<T: Primitive> $trait for Checked<T> {
+                type Output = Self;
+                #[track_caller]
+                fn $method(self, other: Self) -> Self {
+                    self.apply($operator, other, T::$checked)
+                }
+            }
+
+            impl<T: Primitive> $assign_trait for Checked<T> {
+                #[track_caller]
+                fn $assign_method(&mut self, other: Self) {
+                    *self = self.apply($operator, other, T::$checked);
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_operator!(
+    Add, add, AddAssign, add_assign, checked_add, "+";
+    Sub, sub, SubAssign, sub_assign, checked_sub, "-";
+    Mul, mul, MulAssign, mul_assign, checked_mul, "*"
+);
+
+impl<T: Primitive> Div for Checked<T> {
+    type Output = Self;
+    #[track_caller]
+    fn div(self, other: Self) -> Self {
+        self.apply("/", other, T::checked_div)
+    }
+}
+
+impl<T: Primitive> Rem for Checked<T> {
+    type Output = Self;
+    #[track_caller]
+    fn rem(self, other: Self) -> Self {
+        self.apply("%", other, T::checked_rem)
+    }
+}
+
+impl<T: Primitive> Sum for Checked<T> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Checked(T::zero()), Add::add)
+    }
+}
+
+impl<T: Primitive> Product for Checked<T> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Checked(T::one()), Mul::mul)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Checked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: FromStr> FromStr for Checked<T> {
+    type Err = T::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Checked)
+    }
+}
+
+impl<T: Primitive> Integer for Checked<T> {
+    fn zero() -> Self {
+        Checked(T::zero())
+    }
+    fn one() -> Self {
+        Checked(T::one())
+    }
+    fn from_u64(n: u64) -> Option<Self> {
+        T::from_u64(n).map(Checked)
+    }
+    fn to_usize(&self) -> Option<usize> {
+        self.0.to_usize()
+    }
+    fn from_i128(n: i128) -> Option<Self> {
+        T::from_i128(n).map(Checked)
+    }
+    fn to_i128(&self) -> Option<i128> {
+        self.0.to_i128()
+    }
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        self.0.checked_add(&other.0).map(Checked)
+    }
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.0.checked_sub(&other.0).map(Checked)
+    }
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        self.0.checked_mul(&other.0).map(Checked)
+    }
+    fn checked_div(&self, other: &Self) -> Option<Self> {
+        self.0.checked_div(&other.0).map(Checked)
+    }
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        self.0.checked_rem(&other.0).map(Checked)
+    }
+}
+
+/// Argument of the binaries making `run_checked` solve with `Checked` integers
+pub const CHECKED_FLAG: &str = "--checked";
+
+/// Solution of a puzzle written once for every integer type
+pub trait Solver {
+    type Output;
+    fn solve<I: Integer>(self) -> Self::Output;
+}
+
+/// Solves with T, or with `Checked<T>` when the binary gets `CHECKED_FLAG`, so that
+/// the first overflow is reported with its operands instead of wrapping in release builds
+pub fn run_checked<T: Primitive, S: Solver>(solver: S) -> S::Output {
+    solve_with::<T, S>(std::env::args().any(|arg| arg == CHECKED_FLAG), solver)
+}
+
+fn solve_with<T: Primitive, S: Solver>(checked: bool, solver: S) -> S::Output {
+    if checked {
+        solver.solve::<Checked<T>>()
+    } else {
+        solver.solve::<T>()
+    }
+}
+
+#[cfg(feature = "bignum")]
+pub use num_bigint::{BigInt, BigUint};
+
+#[cfg(feature = "bignum")]
+mod bignum {
+    use super::Integer;
+    use num_bigint::{BigInt, BigUint};
+
+    impl Integer for BigInt {
+        fn zero() -> Self {
+            BigInt::from(0)
+        }
+        fn one() -> Self {
+            BigInt::from(1)
+        }
+        fn from_u64(n: u64) -> Option<Self> {
+            Some(BigInt::from(n))
+        }
+        fn to_usize(&self) -> Option<usize> {
+            usize::try_from(self).ok()
+        }
+        fn from_i128(n: i128) -> Option<Self> {
+            Some(BigInt::from(n))
+        }
+        fn to_i128(&self) -> Option<i128> {
+            i128::try_from(self).ok()
+        }
+        fn checked_add(&self, other: &Self) -> Option<Self> {
+            Some(self + other)
+        }
+        fn checked_sub(&self, other: &Self) -> Option<Self> {
+            Some(self - other)
+        }
+        fn checked_mul(&self, other: &Self) -> Option<Self> {
+            Some(self * other)
+        }
+        fn checked_div(&self, other: &Self) -> Option<Self> {
+            (*other != Self::zero()).then(|| self / other)
+        }
+        fn checked_rem(&self, other: &Self) -> Option<Self> {
+            (*other != Self::zero()).then(|| self % other)
+        }
+    }
+
+    impl Integer for BigUint {
+        fn zero() -> Self {
+            BigUint::from(0u32)
+        }
+        fn one() -> Self {
+            BigUint::from(1u32)
+        }
+        fn from_u64(n: u64) -> Option<Self> {
+            Some(BigUint::from(n))
+        }
+        fn to_usize(&self) -> Option<usize> {
+            usize::try_from(self).ok()
+        }
+        fn from_i128(n: i128) -> Option<Self> {
+            Self::try_from(n).ok()
+        }
+        fn to_i128(&self) -> Option<i128> {
+            i128::try_from(self).ok()
+        }
+        fn checked_add(&self, other: &Self) -> Option<Self> {
+            Some(self + other)
+        }
+        fn checked_sub(&self, other: &Self) -> Option<Self> {
+            (self >= other).then(|| self - other)
+        }
+        fn checked_mul(&self, other: &Self) -> Option<Self> {
+            Some(self * other)
+        }
+        fn checked_div(&self, other: &Self) -> Option<Self> {
+            (*other != Self::zero()).then(|| self / other)
+        }
+        fn checked_rem(&self, other: &Self) -> Option<Self> {
+            (*other != Self::zero()).then(|| self % other)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Sum of the squares, written once for every integer type
+    fn sum_of_squares<I: Integer>(n: u64) -> I {
+        (1..=n)
+            .map(|k| I::from_u64(k).unwrap())
+            .map(|k| k.clone() * k)
+            .sum()
+    }
+
+    #[test]
+    fn test_generic() {
+        assert_eq!(sum_of_squares::<u8>(5), 55);
+        assert_eq!(sum_of_squares::<Checked<i64>>(100), Checked(338350));
+        assert_eq!(Checked(7u32).to_string(), "7");
+        assert_eq!("-12".parse::<Checked<i32>>(), Ok(Checked(-12)));
+        assert!("300".parse::<Checked<u8>>().is_err());
+        assert_eq!(Checked(300u32).to_usize(), Some(300));
+        assert_eq!(Checked::<i8>::from_u64(128), None);
+        assert_eq!(Integer::checked_sub(&3u8, &5), None);
+        assert_eq!(Checked(3i8).checked_sub(&Checked(5)), Some(Checked(-2)));
+        assert_eq!(Integer::checked_rem(&Checked(3u8), &Checked(0)), None);
+
+        let mut x = Checked(10i32);
+        x -= Checked(20);
+        x *= Checked(3);
+        assert_eq!(x / Checked(7), Checked(-4));
+        assert_eq!(x % Checked(7), Checked(-2));
+    }
+
+    struct SumOfSquares(u64);
+
+    impl Solver for SumOfSquares {
+        type Output = (&'static str, String);
+        fn solve<I: Integer>(self) -> Self::Output {
+            (type_name::<I>(), sum_of_squares::<I>(self.0).to_string())
+        }
+    }
+
+    #[test]
+    fn test_solve_with() {
+        assert_eq!(
+            solve_with::<u8, _>(false, SumOfSquares(5)),
+            ("u8", "55".to_string())
+        );
+        assert_eq!(
+            solve_with::<u8, _>(true, SumOfSquares(5)),
+            (type_name::<Checked<u8>>(), "55".to_string())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows u8")]
+    fn test_solve_with_overflow() {
+        // 1 + 4 + ... + 81 = 285
+        solve_with::<u8, _>(true, SumOfSquares(9));
+    }
+
+    #[test]
+    #[should_panic(expected = "200 + 100 overflows u8")]
+    fn test_checked_add() {
+        let _ = Checked(200u8) + Checked(100);
+    }
+
+    #[test]
+    #[should_panic(expected = "0 - 1 overflows u64")]
+    fn test_checked_sub() {
+        let mut x = Checked(0u64);
+        x -= Checked(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows i8")]
+    fn test_checked_sum() {
+        let _ = sum_of_squares::<Checked<i8>>(10);
+    }
+
+    #[test]
+    #[should_panic(expected = "-128 / -1 overflows i8")]
+    fn test_checked_div() {
+        let _ = Checked(i8::MIN) / Checked(-1);
+    }
+
+    #[test]
+    #[should_panic(expected = "5 % 0: division by zero")]
+    fn test_checked_rem() {
+        let _ = Checked(5u32) % Checked(0);
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_bignum() {
+        let big = sum_of_squares::<BigUint>(1000);
+        assert_eq!(big.to_string(), "333833500");
+        let mut x = BigInt::from(u64::MAX);
+        x *= BigInt::from(u64::MAX);
+        assert_eq!(x.to_usize(), None);
+        let (two, three) = (BigUint::from(2u32), BigUint::from(3u32));
+        assert_eq!(two.checked_sub(&three), None);
+        assert_eq!(three.checked_sub(&two), Some(BigUint::from(1u32)));
+        assert_eq!(Integer::checked_div(&two, &BigUint::zero()), None);
+    }
+}
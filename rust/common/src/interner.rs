@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use crate::bits::BitVec;
+
+/// Dense identifier of an interned name, ids being given in the order names are first seen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Id(pub u32);
+
+impl Id {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Names stored once, each with a dense id
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interner {
+    ids: HashMap<String, Id>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Id of the name, given the next id if it is new
+    pub fn intern(&mut self, name: &str) -> Id {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = Id(self.names.len() as u32);
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        id
+    }
+
+    pub fn get(&self, name: &str) -> Option<Id> {
+        self.ids.get(name).copied()
+    }
+
+    /// Panics if the id does not come from this interner
+    pub fn name(&self, id: Id) -> &str {
+        &self.names[id.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = Id> {
+        (0..self.names.len() as u32).map(Id)
+    }
+
+    /// Every id with its name, in the order of the ids
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (Id(i as u32), name.as_str()))
+    }
+
+    /// Every id of the set with its name
+    pub fn names<'a>(&'a self, set: &'a IdSet) -> impl Iterator<Item = &'a str> + 'a {
+        set.iter().map(move |id| self.name(id))
+    }
+}
+
+/// Set of ids stored as bits, growing with the largest id
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IdSet {
+    // As long as the largest id plus one, so that equal sets are equal bits
+    bits: BitVec,
+}
+
+impl IdSet {
+    pub fn new() -> Self {
+        IdSet::default()
+    }
+
+    /// The ids 0 to len - 1
+    pub fn full(len: usize) -> Self {
+        IdSet {
+            bits: BitVec::ones(len),
+        }
+    }
+
+    /// Whether the id was not in the set
+    pub fn insert(&mut self, id: Id) -> bool {
+        if id.index() >= self.bits.len() {
+            self.bits.resize(id.index() + 1);
+        }
+        let absent = !self.bits.get(id.index());
+        self.bits.set(id.index(), true);
+        absent
+    }
+
+    /// Whether the id was in the set
+    pub fn remove(&mut self, id: Id) -> bool {
+        let present = self.contains(id);
+        if present {
+            self.bits.set(id.index(), false);
+            self.trim();
+        }
+        present
+    }
+
+    pub fn contains(&self, id: Id) -> bool {
+        id.index() < self.bits.len() && self.bits.get(id.index())
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Ids in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = Id> + '_ {
+        self.bits.iter_ones().map(|i| Id(i as u32))
+    }
+
+    pub fn union_with(&mut self, other: &IdSet) {
+        if other.bits.len() > self.bits.len() {
+            self.bits.resize(other.bits.len());
+        }
+        self.bits = &self.bits | &other.aligned(self);
+    }
+
+    pub fn intersect_with(&mut self, other: &IdSet) {
+        self.bits = &self.bits & &other.aligned(self);
+        self.trim();
+    }
+
+    pub fn difference_with(&mut self, other: &IdSet) {
+        self.bits = &self.bits & &!other.aligned(self);
+        self.trim();
+    }
+
+    pub fn is_subset(&self, other: &IdSet) -> bool {
+        self.bits.len() <= other.bits.len()
+            && (&self.bits & &!other.aligned(self)).count_ones() == 0
+    }
+
+    // Bits of the set as long as those of the other one
+    fn aligned(&self, other: &IdSet) -> BitVec {
+        let mut bits = self.bits.clone();
+        bits.resize(other.bits.len());
+        bits
+    }
+
+    fn trim(&mut self) {
+        let len = self.bits.iter_ones().last().map_or(0, |i| i + 1);
+        self.bits.resize(len);
+    }
+}
+
+impl FromIterator<Id> for IdSet {
+    fn from_iter<I: IntoIterator<Item = Id>>(ids: I) -> Self {
+        let mut set = IdSet::new();
+        set.extend(ids);
+        set
+    }
+}
+
+impl Extend<Id> for IdSet {
+    fn extend<I: IntoIterator<Item = Id>>(&mut self, ids: I) {
+        for id in ids {
+            self.insert(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        assert_eq!(interner.intern("dairy"), Id(0));
+        assert_eq!(interner.intern("fish"), Id(1));
+        assert_eq!(interner.intern("dairy"), Id(0));
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.get("fish"), Some(Id(1)));
+        assert_eq!(interner.get("soy"), None);
+        assert_eq!(interner.name(Id(1)), "fish");
+        assert_eq!(
+            interner.iter().collect::<Vec<_>>(),
+            vec![(Id(0), "dairy"), (Id(1), "fish")]
+        );
+        let set = IdSet::from_iter([Id(1)]);
+        assert_eq!(interner.names(&set).collect::<Vec<_>>(), vec!["fish"]);
+    }
+
+    #[test]
+    fn test_id_set() {
+        let mut a = [1, 5, 64, 200].map(Id).into_iter().collect::<IdSet>();
+        assert_eq!(a.len(), 4);
+        assert!(a.contains(Id(64)) && !a.contains(Id(63)) && !a.contains(Id(1000)));
+        assert!(!a.insert(Id(5)));
+        assert!(a.remove(Id(200)));
+        assert!(!a.remove(Id(200)));
+        assert_eq!(a, [1, 5, 64].map(Id).into_iter().collect());
+        assert_eq!(a.iter().collect::<Vec<_>>(), [1, 5, 64].map(Id));
+
+        let b = [5, 64, 300].map(Id).into_iter().collect::<IdSet>();
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert_eq!(union.len(), 4);
+        let mut intersection = a.clone();
+        intersection.intersect_with(&b);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), [5, 64].map(Id));
+        assert!(intersection.is_subset(&a) && intersection.is_subset(&b));
+        assert!(!a.is_subset(&b));
+        let mut difference = b.clone();
+        difference.difference_with(&a);
+        assert_eq!(difference.iter().collect::<Vec<_>>(), vec![Id(300)]);
+        difference.difference_with(&b);
+        assert!(difference.is_empty());
+        assert_eq!(difference, IdSet::new());
+
+        assert_eq!(IdSet::full(70).len(), 70);
+        assert!(!IdSet::full(70).contains(Id(70)));
+        assert_eq!(IdSet::full(128).len(), 128);
+        assert!(IdSet::full(0).is_empty());
+    }
+}
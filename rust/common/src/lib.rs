@@ -0,0 +1,11 @@
+pub mod bits;
+pub mod cycle;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod integer;
+pub mod interner;
+pub mod number_theory;
+pub mod range_set;
+pub mod search;
+pub mod tokens;
@@ -0,0 +1,489 @@
+use std::collections::HashMap;
+
+use crate::integer::{Checked, Integer, Primitive};
+
+// Intermediate values stay below the arguments, or below the modulus for modular
+// arithmetic, so that any integer type holding the arguments holds the computations
+
+/// Integers with negative values, for Bézout coefficients
+pub trait Signed: Integer {}
+
+impl Signed for i8 {}
+impl Signed for i16 {}
+impl Signed for i32 {}
+impl Signed for i64 {}
+impl Signed for i128 {}
+impl Signed for isize {}
+impl<T: Signed + Primitive> Signed for Checked<T> {}
+#[cfg(feature = "bignum")]
+impl Signed for crate::integer::BigInt {}
+
+fn two<T: Integer>() -> T {
+    T::one() + T::one()
+}
+
+fn abs<T: Integer>(value: T) -> T {
+    if value < T::zero() {
+        T::zero() - value
+    } else {
+        value
+    }
+}
+
+// Only positive moduli make sense
+fn check_modulus<T: Integer>(modulus: &T) -> Option<()> {
+    (*modulus > T::zero()).then_some(())
+}
+
+// In 0..modulus, the modulus being positive
+fn rem_euclid<T: Integer>(value: &T, modulus: &T) -> T {
+    let remainder = value.clone() % modulus.clone();
+    if remainder < T::zero() {
+        remainder + modulus.clone()
+    } else {
+        remainder
+    }
+}
+
+// a + b mod m, a and b in 0..m
+fn add_mod<T: Integer>(a: &T, b: &T, m: &T) -> T {
+    let room = m.clone() - b.clone();
+    if *a >= room {
+        a.clone() - room
+    } else {
+        a.clone() + b.clone()
+    }
+}
+
+// a - b mod m, a and b in 0..m
+fn sub_mod<T: Integer>(a: &T, b: &T, m: &T) -> T {
+    if a >= b {
+        a.clone() - b.clone()
+    } else {
+        a.clone() + (m.clone() - b.clone())
+    }
+}
+
+// a * b mod m, a and b in 0..m
+fn mul_mod<T: Integer>(a: &T, b: &T, m: &T) -> T {
+    if let Some(product) = a.checked_mul(b) {
+        return product % m.clone();
+    }
+    // Double and add, every intermediate value staying below m
+    let (mut a, mut b) = (a.clone(), b.clone());
+    let mut result = T::zero();
+    while b > T::zero() {
+        if b.clone() % two() == T::one() {
+            result = add_mod(&result, &a, m);
+        }
+        a = add_mod(&a, &a, m);
+        b = b / two();
+    }
+    result
+}
+
+/// a * x + b * y = gcd
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtendedGcd<T> {
+    pub gcd: T,
+    pub x: T,
+    pub y: T,
+}
+
+/// The gcd is never negative
+pub fn extended_gcd<T: Signed>(a: T, b: T) -> ExtendedGcd<T> {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::one(), T::zero());
+    let (mut old_y, mut y) = (T::zero(), T::one());
+    while r != T::zero() {
+        let q = old_r.clone() / r.clone();
+        (old_r, r) = (r.clone(), old_r - q.clone() * r);
+        (old_x, x) = (x.clone(), old_x - q.clone() * x);
+        (old_y, y) = (y.clone(), old_y - q * y);
+    }
+    if old_r < T::zero() {
+        ExtendedGcd {
+            gcd: T::zero() - old_r,
+            x: T::zero() - old_x,
+            y: T::zero() - old_y,
+        }
+    } else {
+        ExtendedGcd {
+            gcd: old_r,
+            x: old_x,
+            y: old_y,
+        }
+    }
+}
+
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    let (mut a, mut b) = (a, b);
+    while b != T::zero() {
+        (a, b) = (b.clone(), a % b);
+    }
+    abs(a)
+}
+
+/// None when it does not fit in T
+pub fn lcm<T: Integer>(a: T, b: T) -> Option<T> {
+    if a == T::zero() || b == T::zero() {
+        return Some(T::zero());
+    }
+    let (a, b) = (abs(a), abs(b));
+    (a.clone() / gcd(a, b.clone())).checked_mul(&b)
+}
+
+/// base^exponent mod modulus, in 0..modulus, None unless the modulus is positive
+pub fn mod_pow<T: Integer>(base: T, exponent: T, modulus: T) -> Option<T> {
+    assert!(exponent >= T::zero(), "negative exponent, use mod_inverse");
+    check_modulus(&modulus)?;
+    let (mut base, mut exponent) = (rem_euclid(&base, &modulus), exponent);
+    let mut result = T::one() % modulus.clone();
+    while exponent > T::zero() {
+        if exponent.clone() % two() == T::one() {
+            result = mul_mod(&result, &base, &modulus);
+        }
+        base = mul_mod(&base, &base, &modulus);
+        exponent = exponent / two();
+    }
+    Some(result)
+}
+
+// Extended Euclid keeping the coefficient of a in 0..modulus, so that unsigned types work
+fn positive_mod_inverse<T: Integer>(a: &T, modulus: &T) -> Option<T> {
+    let (mut old_r, mut r) = (rem_euclid(a, modulus), modulus.clone());
+    let (mut old_x, mut x) = (T::one() % modulus.clone(), T::zero());
+    while r != T::zero() {
+        let q = old_r.clone() / r.clone();
+        let q_x = mul_mod(&(q.clone() % modulus.clone()), &x, modulus);
+        (old_r, r) = (r.clone(), old_r - q * r);
+        (old_x, x) = (x.clone(), sub_mod(&old_x, &q_x, modulus));
+    }
+    (old_r == T::one()).then_some(old_x)
+}
+
+/// x in 0..modulus with a * x = 1 mod modulus, None if a and modulus are not coprime or
+/// the modulus is not positive
+pub fn mod_inverse<T: Integer>(a: T, modulus: T) -> Option<T> {
+    check_modulus(&modulus)?;
+    positive_mod_inverse(&a, &modulus)
+}
+
+/// x = residue mod modulus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Congruence<T> {
+    pub residue: T,
+    pub modulus: T,
+}
+
+/// The single congruence equivalent to all of them, moduli need not be coprime.
+/// None when they contradict each other, a modulus is not positive, or the combined
+/// modulus does not fit in T.
+pub fn crt<T: Integer>(congruences: &[Congruence<T>]) -> Option<Congruence<T>> {
+    let (mut residue, mut modulus) = (T::zero(), T::one());
+    for congruence in congruences {
+        let m = congruence.modulus.clone();
+        check_modulus(&m)?;
+        // residue + modulus * k = r mod m, k being unique mod m / gcd
+        let gcd = gcd(modulus.clone(), m.clone());
+        let difference = sub_mod(
+            &rem_euclid(&congruence.residue, &m),
+            &rem_euclid(&residue, &m),
+            &m,
+        );
+        if difference.clone() % gcd.clone() != T::zero() {
+            return None;
+        }
+        let step = m / gcd.clone();
+        let inverse = positive_mod_inverse(&(modulus.clone() / gcd.clone()), &step)
+            .expect("the moduli divided by their gcd are coprime");
+        let k = mul_mod(&((difference / gcd) % step.clone()), &inverse, &step);
+        let combined = modulus.checked_mul(&step)?;
+        // At most modulus - 1 + modulus * (step - 1), below the combined modulus
+        residue += modulus * k;
+        modulus = combined;
+    }
+    Some(Congruence { residue, modulus })
+}
+
+/// Smallest x >= 0 with base^x = target mod modulus, by baby-step giant-step.
+/// None if there is none or the modulus is not positive.
+pub fn discrete_log<T: Integer>(base: T, target: T, modulus: T) -> Option<T> {
+    check_modulus(&modulus)?;
+    let mut modulus = modulus;
+    let mut base = rem_euclid(&base, &modulus);
+    let mut target = rem_euclid(&target, &modulus);
+    // Divide out the common factors of base and modulus, solving factor * base^x = target
+    let mut factor = T::one() % modulus.clone();
+    let mut offset = T::zero();
+    loop {
+        if factor == target {
+            return Some(offset);
+        }
+        let gcd = gcd(base.clone(), modulus.clone());
+        if gcd == T::one() {
+            break;
+        }
+        if target.clone() % gcd.clone() != T::zero() {
+            return None;
+        }
+        target = target / gcd.clone();
+        modulus = modulus / gcd.clone();
+        offset += T::one();
+        factor = mul_mod(
+            &((base.clone() / gcd) % modulus.clone()),
+            &(factor % modulus.clone()),
+            &modulus,
+        );
+    }
+
+    base = base % modulus.clone();
+    let n = isqrt(modulus.clone()) + T::one();
+    // target * base^j for the baby steps, the largest j kept
+    let mut baby_steps = HashMap::new();
+    let mut value = target % modulus.clone();
+    let mut j = T::zero();
+    while j < n {
+        baby_steps.insert(value.clone(), j.clone());
+        value = mul_mod(&value, &base, &modulus);
+        j += T::one();
+    }
+    let giant_step = mod_pow(base, n.clone(), modulus.clone())?;
+    let mut value = factor % modulus.clone();
+    // x = i * n - j, written (i - 1) * n + (n - j) as the smallest x fits in T
+    let mut giant_steps = T::zero();
+    while giant_steps < n {
+        value = mul_mod(&value, &giant_step, &modulus);
+        if let Some(j) = baby_steps.get(&value) {
+            return (giant_steps * n.clone() + (n - j.clone())).checked_add(&offset);
+        }
+        giant_steps += T::one();
+    }
+    None
+}
+
+/// Largest r with r * r <= n, by Newton's method
+pub fn isqrt<T: Integer>(n: T) -> T {
+    assert!(n >= T::zero(), "square root of a negative number");
+    if n < two() {
+        return n;
+    }
+    // Starting above the root, the iterates decrease to it
+    let mut root = n.clone() / two() + T::one();
+    loop {
+        let next = (root.clone() + n.clone() / root.clone()) / two();
+        if next >= root {
+            return root;
+        }
+        root = next;
+    }
+}
+
+/// Prime factors with their exponents, in increasing order, by trial division
+pub fn factorise<T: Integer>(n: T) -> Vec<(T, u32)> {
+    assert!(n > T::zero(), "only positive numbers have a factorisation");
+    let mut n = n;
+    let mut factors = vec![];
+    let mut divisor: T = two();
+    while divisor <= n.clone() / divisor.clone() {
+        let mut exponent = 0;
+        while n.clone() % divisor.clone() == T::zero() {
+            n = n / divisor.clone();
+            exponent += 1;
+        }
+        if exponent > 0 {
+            factors.push((divisor.clone(), exponent));
+        }
+        divisor += if divisor == two() { T::one() } else { two() };
+    }
+    if n > T::one() {
+        factors.push((n, 1));
+    }
+    factors
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        let egcd = extended_gcd(240i64, 46);
+        assert_eq!(egcd.gcd, 2);
+        assert_eq!(240 * egcd.x + 46 * egcd.y, 2);
+        assert_eq!(extended_gcd(-12i32, 18).gcd, 6);
+        assert_eq!(gcd(0u64, 7), 7);
+        assert_eq!(lcm(4u8, 6), Some(12));
+        assert_eq!(lcm(200u8, 3), None);
+        assert_eq!(mod_inverse(3i64, 11), Some(4));
+        assert_eq!(mod_inverse(-3i64, 11), Some(7));
+        assert_eq!(mod_inverse(6u32, 9), None);
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(2u64, 10, 1000), Some(24));
+        assert_eq!(mod_pow(-2i32, 3, 5), Some(2));
+        assert_eq!(mod_pow(5u8, 0, 1), Some(0));
+        // Products over 2^127 go through doubling
+        let p: i128 = (1 << 89) - 1;
+        assert_eq!(mod_pow(3, p - 1, p), Some(1));
+        assert_eq!(
+            mod_pow(u64::MAX - 1, u64::MAX - 2, u64::MAX),
+            Some(u64::MAX - 1)
+        );
+        assert_eq!(mod_pow(200u8, 250, 251), Some(1));
+    }
+
+    fn congruence<T>(residue: T, modulus: T) -> Congruence<T> {
+        Congruence { residue, modulus }
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(
+            crt(&[congruence(2i64, 3), congruence(3, 5), congruence(2, 7)]),
+            Some(congruence(23, 105))
+        );
+        // Moduli sharing factors
+        assert_eq!(
+            crt(&[congruence(3u32, 4), congruence(5, 6)]),
+            Some(congruence(11, 12))
+        );
+        assert_eq!(crt(&[congruence(1u32, 4), congruence(2, 6)]), None);
+        assert_eq!(crt::<i32>(&[]), Some(congruence(0, 1)));
+        assert_eq!(
+            crt(&[congruence(1u8, 16), congruence(2, 17)]),
+            None,
+            "272 does not fit in u8"
+        );
+        // Residues out of range
+        assert_eq!(
+            crt(&[congruence(-1i64, 7), congruence(20, 11)]),
+            Some(congruence(20, 77))
+        );
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        assert_eq!(discrete_log(2u64, 22, 29), Some(26));
+        assert_eq!(discrete_log(3i64, 1, 7), Some(0));
+        for modulus in 1..40u32 {
+            for base in 0..modulus {
+                for target in 0..modulus {
+                    let expected = (0..2 * modulus)
+                        .find(|x| mod_pow(base, *x, modulus) == Some(target % modulus));
+                    assert_eq!(
+                        discrete_log(base, target, modulus),
+                        expected,
+                        "{}^x = {} mod {}",
+                        base,
+                        target,
+                        modulus
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_isqrt_factorise() {
+        assert_eq!(isqrt(0u8), 0);
+        assert_eq!(isqrt(99i32), 9);
+        assert_eq!(isqrt(100i32), 10);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(isqrt(i128::MAX), 13043817825332782212);
+        assert_eq!(factorise(360u32), vec![(2, 3), (3, 2), (5, 1)]);
+        assert_eq!(factorise(1i64), vec![]);
+        assert_eq!(factorise(600851475143u64).last(), Some(&(6857, 1)));
+        assert_eq!(factorise(4294967291u64), vec![(4294967291, 1)]);
+    }
+
+    #[test]
+    fn test_other_integers() {
+        assert_eq!(gcd(Checked(12u32), Checked(18)), Checked(6));
+        assert_eq!(extended_gcd(Checked(240i64), Checked(46)).gcd, Checked(2));
+        assert_eq!(
+            crt(&[
+                congruence(Checked(2i64), Checked(3)),
+                congruence(Checked(3), Checked(5))
+            ]),
+            Some(congruence(Checked(8), Checked(15)))
+        );
+        assert_eq!(mod_pow(2u128, 10, 1000), Some(24));
+    }
+
+    #[test]
+    fn test_zero_modulus() {
+        assert_eq!(mod_pow(2u64, 10, 0), None);
+        assert_eq!(mod_pow(2i64, 10, -7), None);
+        assert_eq!(mod_inverse(3u32, 0), None);
+        assert_eq!(discrete_log(2u64, 1, 0), None);
+        assert_eq!(crt(&[congruence(1u32, 4), congruence(0, 0)]), None);
+        assert_eq!(crt(&[congruence(1i32, -4)]), None);
+    }
+
+    #[test]
+    fn test_beyond_i128() {
+        // 2^127 + 45 is a prime above i128::MAX
+        let p = (1u128 << 127) + 45;
+        assert_eq!(mod_pow(3, p - 1, p), Some(1));
+        // u128::MAX = -91 mod p
+        assert_eq!(mod_pow(u128::MAX, 2, p), Some(91 * 91));
+        let inverse = mod_inverse(p - 2, p).unwrap();
+        assert_eq!(mod_pow(p - 2, p - 2, p), Some(inverse));
+        assert_eq!(gcd(u128::MAX, 5), 5);
+        assert_eq!(
+            crt(&[congruence(p - 1, p), congruence(0, 2)]),
+            None,
+            "2p does not fit in u128"
+        );
+        assert_eq!(
+            crt(&[congruence(u128::MAX, p)]),
+            Some(congruence(p - 91, p))
+        );
+        assert_eq!(discrete_log(2, 8, 1u128 << 127), Some(3));
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        assert_eq!(factorise(3u128 << 126), vec![(2, 126), (3, 1)]);
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_bignum() {
+        use crate::integer::{BigInt, BigUint};
+        let big = |n: i64| BigInt::from(n);
+        assert_eq!(
+            crt(&[
+                congruence(big(2), big(3)),
+                congruence(big(3), big(5)),
+                congruence(big(2), big(7))
+            ]),
+            Some(congruence(big(23), big(105)))
+        );
+        assert_eq!(extended_gcd(big(-12), big(18)).gcd, big(6));
+        assert_eq!(isqrt(BigUint::from(u64::MAX)), BigUint::from(u32::MAX));
+        assert_eq!(
+            mod_inverse(BigUint::from(3u32), BigUint::from(11u32)),
+            Some(BigUint::from(4u32))
+        );
+        // Far beyond i128: 2^521 - 1 is prime
+        let p = BigUint::from(2u32).pow(521) - BigUint::from(1u32);
+        let two = BigUint::from(2u32);
+        assert_eq!(
+            mod_pow(two.clone(), p.clone() - BigUint::from(1u32), p.clone()),
+            Some(BigUint::from(1u32))
+        );
+        assert_eq!(
+            mod_inverse(two.clone(), p.clone()),
+            Some((p.clone() + BigUint::from(1u32)) / two.clone())
+        );
+        assert_eq!(
+            crt(&[
+                congruence(BigUint::from(1u32), p.clone()),
+                congruence(BigUint::from(0u32), two.clone())
+            ]),
+            Some(congruence(p.clone() + BigUint::from(1u32), p.clone() * two))
+        );
+        assert_eq!(mod_pow(big(2), big(3), big(0)), None);
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Integers, where adjacent ranges can be merged
+pub trait Discrete: Copy + Ord + fmt::Debug {
+    fn successor(self) -> Option<Self>;
+    fn predecessor(self) -> Option<Self>;
+    /// Number of values in start..=end, start <= end
+    fn count(start: Self, end: Self) -> u128;
+}
+
+macro_rules! impl_discrete {
+    ($($t:ty),*) => {
+        $(
+            impl Discrete for $t {
+                fn successor(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+                fn predecessor(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+                fn count(start: Self, end: Self) -> u128 {
+                    (end as i128 - start as i128) as u128 + 1
+                }
+            }
+        )*
+    };
+}
+
+impl_discrete!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Set of integers stored as sorted, disjoint and non-adjacent inclusive ranges
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RangeSet<T> {
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for RangeSet<T> {
+    fn default() -> Self {
+        RangeSet { ranges: vec![] }
+    }
+}
+
+impl<T: Discrete> RangeSet<T> {
+    pub fn new() -> Self {
+        RangeSet::default()
+    }
+
+    /// Merges the range with every range it overlaps or touches, empty ranges are ignored
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (mut start, mut end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        // Ranges ending before start - 1, then ranges starting before end + 1
+        let low = self
+            .ranges
+            .partition_point(|(_, e)| e.successor().is_some_and(|next| next < start));
+        let high = self
+            .ranges
+            .partition_point(|(s, _)| end.successor().is_none_or(|next| *s <= next));
+        if low < high {
+            start = start.min(self.ranges[low].0);
+            end = end.max(self.ranges[high - 1].1);
+        }
+        self.ranges.splice(low..high, [(start, end)]);
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let i = self.ranges.partition_point(|(_, e)| e < value);
+        self.ranges.get(i).is_some_and(|(s, _)| s <= value)
+    }
+
+    /// Number of values in the set
+    pub fn len(&self) -> u128 {
+        self.ranges.iter().map(|(s, e)| T::count(*s, *e)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn ranges(&self) -> impl Iterator<Item = RangeInclusive<T>> + '_ {
+        self.ranges.iter().map(|(s, e)| *s..=*e)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for range in other.ranges() {
+            union.insert(range);
+        }
+        union
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a_start, a_end) = self.ranges[i];
+            let (b_start, b_end) = other.ranges[j];
+            let (start, end) = (a_start.max(b_start), a_end.min(b_end));
+            if start <= end {
+                ranges.push((start, end));
+            }
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        RangeSet { ranges }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = vec![];
+        let mut j = 0;
+        for &(start, end) in &self.ranges {
+            // Skip what ends before this range
+            while j < other.ranges.len() && other.ranges[j].1 < start {
+                j += 1;
+            }
+            let mut current = Some(start);
+            let mut k = j;
+            while let Some(from) = current {
+                match other.ranges.get(k) {
+                    Some(&(s, e)) if s <= end => {
+                        if from < s {
+                            ranges.push((from, s.predecessor().unwrap()));
+                        }
+                        current = e.successor().filter(|next| *next <= end);
+                        k += 1;
+                    }
+                    _ => {
+                        ranges.push((from, end));
+                        current = None;
+                    }
+                }
+            }
+        }
+        RangeSet { ranges }
+    }
+}
+
+impl<T: Discrete> FromIterator<RangeInclusive<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(ranges: I) -> Self {
+        let mut set = RangeSet::new();
+        for range in ranges {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (s, e)) in self.ranges.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}-{}", s, e)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn set(ranges: &[RangeInclusive<i32>]) -> RangeSet<i32> {
+        ranges.iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_insert() {
+        let s = set(&[5..=7, 1..=3, 13..=40, 45..=50, 6..=11, 33..=44]);
+        assert_eq!(s.ranges().collect::<Vec<_>>(), vec![1..=3, 5..=11, 13..=50]);
+        assert_eq!(s.len(), 48);
+        assert_eq!(s.to_string(), "1-3 5-11 13-50");
+        assert!(s.contains(&1) && s.contains(&11) && s.contains(&50));
+        assert!(!s.contains(&0) && !s.contains(&4) && !s.contains(&12) && !s.contains(&51));
+        assert_eq!(set(&[1..=2, 3..=4]), set(&[1..=4]));
+        assert!(set(&[RangeInclusive::new(3, 2)]).is_empty());
+
+        let mut full = RangeSet::new();
+        full.insert(250..=u8::MAX);
+        full.insert(0..=249);
+        assert_eq!(full.len(), 256);
+        assert_eq!(full.ranges().count(), 1);
+    }
+
+    #[test]
+    fn test_operations() {
+        let a = set(&[0..=10, 20..=30]);
+        let b = set(&[5..=25, 30..=40]);
+        assert_eq!(a.union(&b), set(&[0..=40]));
+        assert_eq!(a.intersection(&b), set(&[5..=10, 20..=25, 30..=30]));
+        assert_eq!(a.difference(&b), set(&[0..=4, 26..=29]));
+        assert_eq!(b.difference(&a), set(&[11..=19, 31..=40]));
+        assert_eq!(a.difference(&a), RangeSet::new());
+
+        // Same answers as with explicit sets of values
+        let values = |s: &RangeSet<i32>| (-5..50).filter(|x| s.contains(x)).collect::<Vec<_>>();
+        let c = set(&[-3..=-1, 2..=2, 4..=12, 18..=19, 41..=45]);
+        for (x, y) in [(&a, &c), (&c, &a), (&b, &c), (&c, &b)] {
+            let (vx, vy) = (values(x), values(y));
+            assert_eq!(
+                values(&x.difference(y)),
+                vx.iter()
+                    .filter(|v| !vy.contains(v))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                values(&x.intersection(y)),
+                vx.iter()
+                    .filter(|v| vy.contains(v))
+                    .cloned()
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                x.union(y).len() + x.intersection(y).len(),
+                x.len() + y.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,521 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Cost of an edge, the default value being zero
+pub trait Cost: Copy + Ord + Default + Add<Output = Self> {}
+
+impl<C: Copy + Ord + Default + Add<Output = C>> Cost for C {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N, C> {
+    /// From the start to the goal, both included
+    pub nodes: Vec<N>,
+    pub cost: C,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stats {
+    /// Distinct states reached
+    pub visited: usize,
+    /// States whose successors were computed
+    pub expanded: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search<N, C> {
+    pub path: Option<Path<N, C>>,
+    pub stats: Stats,
+}
+
+// States are numbered in the order they are reached, parents point to these numbers
+struct Arena<N> {
+    nodes: Vec<N>,
+    ids: HashMap<N, usize>,
+}
+
+impl<N: Clone + Eq + Hash> Arena<N> {
+    fn new() -> Self {
+        Arena {
+            nodes: vec![],
+            ids: HashMap::new(),
+        }
+    }
+
+    // The id of the node, and whether it is new
+    fn id(&mut self, node: N) -> (usize, bool) {
+        match self.ids.get(&node) {
+            Some(id) => (*id, false),
+            None => {
+                let id = self.nodes.len();
+                self.ids.insert(node.clone(), id);
+                self.nodes.push(node);
+                (id, true)
+            }
+        }
+    }
+
+    fn path(&self, parents: &[Option<usize>], mut id: usize) -> Vec<N> {
+        let mut path = vec![self.nodes[id].clone()];
+        while let Some(parent) = parents[id] {
+            path.push(self.nodes[parent].clone());
+            id = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Breadth-first search, every edge costing 1
+pub fn bfs<N, I>(
+    start: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+{
+    let mut arena = Arena::new();
+    let mut parents = vec![None];
+    let mut depths = vec![0];
+    let mut stats = Stats::default();
+    let mut queue = VecDeque::from([arena.id(start).0]);
+    while let Some(id) = queue.pop_front() {
+        if is_goal(&arena.nodes[id]) {
+            stats.visited = arena.nodes.len();
+            return Search {
+                path: Some(Path {
+                    nodes: arena.path(&parents, id),
+                    cost: depths[id],
+                }),
+                stats,
+            };
+        }
+        stats.expanded += 1;
+        for next in successors(&arena.nodes[id]) {
+            let (next, new) = arena.id(next);
+            if new {
+                parents.push(Some(id));
+                depths.push(depths[id] + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+    stats.visited = arena.nodes.len();
+    Search { path: None, stats }
+}
+
+/// Shortest path with non-negative edge costs
+pub fn dijkstra<N, C, I>(
+    start: N,
+    successors: impl FnMut(&N) -> I,
+    is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (N, C)>,
+{
+    astar(start, successors, |_| C::default(), is_goal)
+}
+
+/// Shortest path guided by a heuristic that never overestimates the remaining cost
+pub fn astar<N, C, I>(
+    start: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut heuristic: impl FnMut(&N) -> C,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> Search<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut arena = Arena::new();
+    let mut parents = vec![None];
+    let mut costs = vec![C::default()];
+    let mut stats = Stats::default();
+    let mut heap = BinaryHeap::new();
+    let start = arena.id(start).0;
+    heap.push(Reverse((
+        heuristic(&arena.nodes[start]),
+        C::default(),
+        start,
+    )));
+    while let Some(Reverse((_, cost, id))) = heap.pop() {
+        if cost > costs[id] {
+            // Already expanded with a smaller cost
+            continue;
+        }
+        if is_goal(&arena.nodes[id]) {
+            stats.visited = arena.nodes.len();
+            return Search {
+                path: Some(Path {
+                    nodes: arena.path(&parents, id),
+                    cost,
+                }),
+                stats,
+            };
+        }
+        stats.expanded += 1;
+        for (next, step) in successors(&arena.nodes[id]) {
+            let next_cost = cost + step;
+            let (next, new) = arena.id(next);
+            if new {
+                parents.push(Some(id));
+                costs.push(next_cost);
+            } else if next_cost < costs[next] {
+                parents[next] = Some(id);
+                costs[next] = next_cost;
+            } else {
+                continue;
+            }
+            let estimate = next_cost + heuristic(&arena.nodes[next]);
+            heap.push(Reverse((estimate, next_cost, next)));
+        }
+    }
+    stats.visited = arena.nodes.len();
+    Search { path: None, stats }
+}
+
+/// Breadth-first search from both ends, meeting in the middle.
+/// `predecessors` gives the states leading to a state, the same as `successors`
+/// on undirected graphs.
+pub fn bidirectional_bfs<N, I, J>(
+    start: N,
+    goal: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut predecessors: impl FnMut(&N) -> J,
+) -> Search<N, usize>
+where
+    N: Clone + Eq + Hash,
+    I: IntoIterator<Item = N>,
+    J: IntoIterator<Item = N>,
+{
+    let mut stats = Stats::default();
+    if start == goal {
+        stats.visited = 1;
+        return Search {
+            path: Some(Path {
+                nodes: vec![start],
+                cost: 0,
+            }),
+            stats,
+        };
+    }
+    // Parent towards the start, then towards the goal
+    let mut forward: HashMap<N, Option<N>> = HashMap::from([(start.clone(), None)]);
+    let mut backward: HashMap<N, Option<N>> = HashMap::from([(goal.clone(), None)]);
+    let mut forward_layer = vec![start];
+    let mut backward_layer = vec![goal];
+
+    let meeting = loop {
+        if forward_layer.is_empty() || backward_layer.is_empty() {
+            stats.visited = forward.len() + backward.len();
+            return Search { path: None, stats };
+        }
+        // Grow the smaller frontier by a whole layer
+        let expand_forward = forward_layer.len() <= backward_layer.len();
+        let (layer, parents, others) = if expand_forward {
+            (&mut forward_layer, &mut forward, &backward)
+        } else {
+            (&mut backward_layer, &mut backward, &forward)
+        };
+        let mut next_layer = vec![];
+        let mut meeting = None;
+        for node in layer.drain(..) {
+            stats.expanded += 1;
+            let neighbours: Vec<N> = if expand_forward {
+                successors(&node).into_iter().collect()
+            } else {
+                predecessors(&node).into_iter().collect()
+            };
+            for next in neighbours {
+                if parents.contains_key(&next) {
+                    continue;
+                }
+                parents.insert(next.clone(), Some(node.clone()));
+                if others.contains_key(&next) {
+                    meeting = Some(next.clone());
+                    break;
+                }
+                next_layer.push(next);
+            }
+            if meeting.is_some() {
+                break;
+            }
+        }
+        *layer = next_layer;
+        if let Some(meeting) = meeting {
+            break meeting;
+        }
+    };
+
+    stats.visited = forward.len() + backward.len() - 1;
+    let mut nodes = vec![meeting.clone()];
+    while let Some(Some(parent)) = forward.get(nodes.last().unwrap()) {
+        nodes.push(parent.clone());
+    }
+    nodes.reverse();
+    while let Some(Some(parent)) = backward.get(nodes.last().unwrap()) {
+        nodes.push(parent.clone());
+    }
+    Search {
+        path: Some(Path {
+            cost: nodes.len() - 1,
+            nodes,
+        }),
+        stats,
+    }
+}
+
+/// Every shortest path from a start to the nearest goals
+#[derive(Debug, Clone)]
+pub struct ShortestPaths<N, C> {
+    nodes: Vec<N>,
+    // Every parent reaching each state with its minimal cost
+    parents: Vec<Vec<usize>>,
+    goals: Vec<usize>,
+    pub cost: Option<C>,
+    pub stats: Stats,
+}
+
+impl<N: Clone, C> ShortestPaths<N, C> {
+    pub fn goals(&self) -> impl Iterator<Item = &N> {
+        self.goals.iter().map(|id| &self.nodes[*id])
+    }
+
+    /// Number of distinct shortest paths, saturating at `u64::MAX`
+    pub fn count(&self) -> u64 {
+        let mut counts: Vec<Option<u64>> = vec![None; self.nodes.len()];
+        self.goals
+            .iter()
+            .map(|goal| self.count_to(*goal, &mut counts))
+            .fold(0, u64::saturating_add)
+    }
+
+    fn count_to(&self, id: usize, counts: &mut [Option<u64>]) -> u64 {
+        if let Some(count) = counts[id] {
+            return count;
+        }
+        let count = if self.parents[id].is_empty() {
+            1
+        } else {
+            self.parents[id]
+                .iter()
+                .map(|parent| self.count_to(*parent, counts))
+                .fold(0, u64::saturating_add)
+        };
+        counts[id] = Some(count);
+        count
+    }
+
+    /// Every shortest path, there may be exponentially many
+    pub fn paths(&self) -> Vec<Vec<N>> {
+        let mut paths = vec![];
+        for goal in &self.goals {
+            self.collect_paths(*goal, &mut vec![], &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths(&self, id: usize, suffix: &mut Vec<usize>, paths: &mut Vec<Vec<N>>) {
+        suffix.push(id);
+        if self.parents[id].is_empty() {
+            paths.push(
+                suffix
+                    .iter()
+                    .rev()
+                    .map(|id| self.nodes[*id].clone())
+                    .collect(),
+            );
+        }
+        for parent in &self.parents[id] {
+            self.collect_paths(*parent, suffix, paths);
+        }
+        suffix.pop();
+    }
+}
+
+/// Dijkstra keeping every parent of equal cost.
+/// Panics on an edge of cost zero, which could make two states parents of each other.
+pub fn all_shortest_paths<N, C, I>(
+    start: N,
+    mut successors: impl FnMut(&N) -> I,
+    mut is_goal: impl FnMut(&N) -> bool,
+) -> ShortestPaths<N, C>
+where
+    N: Clone + Eq + Hash,
+    C: Cost,
+    I: IntoIterator<Item = (N, C)>,
+{
+    let mut arena = Arena::new();
+    let mut parents = vec![vec![]];
+    let mut costs = vec![C::default()];
+    let mut expanded = vec![false];
+    let mut goals = vec![];
+    let mut best = None;
+    let mut stats = Stats::default();
+    let mut heap = BinaryHeap::from([Reverse((C::default(), arena.id(start).0))]);
+    while let Some(Reverse((cost, id))) = heap.pop() {
+        if expanded[id] || best.is_some_and(|best| cost > best) {
+            continue;
+        }
+        expanded[id] = true;
+        if is_goal(&arena.nodes[id]) {
+            best = Some(cost);
+            goals.push(id);
+            continue;
+        }
+        stats.expanded += 1;
+        for (next, step) in successors(&arena.nodes[id]) {
+            assert!(step > C::default(), "all_shortest_paths needs positive costs");
+            let next_cost = cost + step;
+            let (next, new) = arena.id(next);
+            if new {
+                parents.push(vec![id]);
+                costs.push(next_cost);
+                expanded.push(false);
+            } else if next_cost < costs[next] {
+                parents[next] = vec![id];
+                costs[next] = next_cost;
+            } else {
+                if next_cost == costs[next] && !parents[next].contains(&id) {
+                    parents[next].push(id);
+                }
+                continue;
+            }
+            heap.push(Reverse((next_cost, next)));
+        }
+    }
+    stats.visited = arena.nodes.len();
+    ShortestPaths {
+        nodes: arena.nodes,
+        parents,
+        goals,
+        cost: best,
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Moves on an open 2D lattice, except through the wall x = 3 for y < 5
+    fn lattice((x, y): &(i32, i32)) -> Vec<(i32, i32)> {
+        [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .map(|(dx, dy)| (x + dx, y + dy))
+            .filter(|(x, y)| x.abs() <= 10 && y.abs() <= 10 && !(*x == 3 && *y < 5))
+            .collect()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let search = bfs((0, 0), lattice, |p| *p == (6, 0));
+        let path = search.path.unwrap();
+        // Around the wall through (3, 5)
+        assert_eq!(path.cost, 16);
+        assert_eq!(path.nodes.len(), 17);
+        assert!(path.nodes.contains(&(3, 5)));
+        assert!(path
+            .nodes
+            .windows(2)
+            .all(|w| lattice(&w[0]).contains(&w[1])));
+        assert!(search.stats.expanded <= search.stats.visited);
+
+        assert_eq!(bfs(0, |n| [n + 1], |n| *n == 0).path.unwrap().cost, 0);
+        assert_eq!(bfs(0u8, |n| [n / 2], |n| *n == 1).path, None);
+    }
+
+    #[test]
+    fn test_weighted() {
+        let weighted = |p: &(i32, i32)| {
+            lattice(p)
+                .into_iter()
+                .map(|q| (q, if q.1 > p.1 { 3 } else { 1 }))
+                .collect::<Vec<_>>()
+        };
+        let goal = (6, 0);
+        let heuristic = |(x, y): &(i32, i32)| (goal.0 - x).abs() + (goal.1 - y).abs();
+        let dijkstra = dijkstra((0, 0), weighted, |p| *p == goal);
+        let astar = astar((0, 0), weighted, heuristic, |p| *p == goal);
+        // Climbing costs 3 per step, coming down 1
+        assert_eq!(dijkstra.path.as_ref().unwrap().cost, 6 + 5 * 3 + 5);
+        assert_eq!(
+            astar.path.as_ref().unwrap().cost,
+            dijkstra.path.unwrap().cost
+        );
+        assert!(astar.stats.expanded < dijkstra.stats.expanded);
+    }
+
+    #[test]
+    fn test_bidirectional() {
+        for goal in [(6, 0), (0, 0), (-10, 10), (3, 6)] {
+            let expected = bfs((0, 0), lattice, |p| *p == goal).path.unwrap();
+            let path = bidirectional_bfs((0, 0), goal, lattice, lattice)
+                .path
+                .unwrap();
+            assert_eq!(path.cost, expected.cost);
+            assert_eq!(path.nodes.first(), Some(&(0, 0)));
+            assert_eq!(path.nodes.last(), Some(&goal));
+            assert!(path
+                .nodes
+                .windows(2)
+                .all(|w| lattice(&w[0]).contains(&w[1])));
+        }
+        // Only moving up, the start cannot be reached back
+        let up = |n: &i32| if *n < 10 { vec![n + 1] } else { vec![] };
+        let down = |n: &i32| if *n > -10 { vec![n - 1] } else { vec![] };
+        assert_eq!(
+            bidirectional_bfs(5, 0, up, down).path,
+            None::<Path<i32, usize>>
+        );
+    }
+
+    #[test]
+    fn test_all_shortest_paths() {
+        // From a corner to the opposite one of a 3 x 3 square: C(4, 2) paths
+        let square = |&(x, y): &(i32, i32)| {
+            [(x + 1, y), (x, y + 1)]
+                .into_iter()
+                .filter(|(x, y)| *x <= 2 && *y <= 2)
+                .map(|p| (p, 1))
+                .collect::<Vec<_>>()
+        };
+        let shortest = all_shortest_paths((0, 0), square, |p| *p == (2, 2));
+        assert_eq!(shortest.cost, Some(4));
+        assert_eq!(shortest.count(), 6);
+        let mut paths = shortest.paths();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), 6);
+        assert!(paths.iter().all(|path| path.len() == 5));
+
+        // Two goals at the same distance
+        let shortest = all_shortest_paths(0, |n: &i32| [(n + 1, 1), (n - 1, 1)], |n| n.abs() == 2);
+        assert_eq!(shortest.count(), 2);
+        assert_eq!(shortest.goals().count(), 2);
+
+        // 2^100 paths through a chain of diamonds
+        let diamonds = |n: &u32| {
+            if n.is_multiple_of(2) {
+                vec![(n + 1, 1), (n + 2, 2)]
+            } else {
+                vec![(n + 1, 1)]
+            }
+        };
+        let shortest = all_shortest_paths(0, diamonds, |n| *n == 200);
+        assert_eq!(shortest.cost, Some(200));
+        assert_eq!(shortest.count(), u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "positive costs")]
+    fn test_all_shortest_paths_zero_cost() {
+        all_shortest_paths(0, |n: &i32| [((n + 1) % 3, 0)], |n| *n == 5);
+    }
+}
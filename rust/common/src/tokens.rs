@@ -0,0 +1,118 @@
+use std::num::{IntErrorKind, ParseIntError};
+use std::str::FromStr;
+
+/// Integer for a `{? }` grammar action, the error being what peg reports as expected
+pub fn integer<T: FromStr<Err = ParseIntError>>(s: &str) -> Result<T, &'static str> {
+    s.parse().map_err(|e: ParseIntError| match e.kind() {
+        IntErrorKind::PosOverflow => "a smaller integer",
+        IntErrorKind::NegOverflow => "a larger integer",
+        _ => "an integer",
+    })
+}
+
+/// `peg::parser!` with shared rules added to the grammar:
+/// - `u8()` to `u128()` and `usize()`: digits
+/// - `i8()` to `i128()` and `isize()`: digits with an optional sign
+/// - `identifier()`: a letter or `_`, then letters, digits and `_`
+/// - `comma_list(<item>)`: one or more items separated by commas and optional spaces
+/// - `space_list(<item>)`: one or more items separated by spaces
+/// - `key_value(<key>, <value>)`: key, colon and optional spaces, value
+///
+/// Integers out of the range of their type fail to parse instead of panicking.
+#[macro_export]
+macro_rules! parser {
+    (grammar $name:ident() for str { $($rules:tt)* }) => {
+        $crate::parser!(@shared ($) $name { $($rules)* });
+    };
+    // `$d` is a dollar sign, which cannot be written directly in the peg rules
+    (@shared ($d:tt) $name:ident { $($rules:tt)* }) => {
+        ::peg::parser! {
+            grammar $name() for str {
+                rule unsigned_digits() = ['0'..='9']+
+                rule signed_digits() = ['+' | '-']? ['0'..='9']+
+
+                rule u8() -> u8 = n:$d(unsigned_digits()) {? $crate::tokens::integer(n) }
+                rule u16() -> u16 = n:$d(unsigned_digits()) {? $crate::tokens::integer(n) }
+                rule u32() -> u32 = n:$d(unsigned_digits()) {? $crate::tokens::integer(n) }
+                rule u64() -> u64 = n:$d(unsigned_digits()) {? $crate::tokens::integer(n) }
+                rule u128() -> u128 = n:$d(unsigned_digits()) {? $crate::tokens::integer(n) }
+                rule usize() -> usize = n:$d(unsigned_digits()) {? $crate::tokens::integer(n) }
+
+                rule i8() -> i8 = n:$d(signed_digits()) {? $crate::tokens::integer(n) }
+                rule i16() -> i16 = n:$d(signed_digits()) {? $crate::tokens::integer(n) }
+                rule i32() -> i32 = n:$d(signed_digits()) {? $crate::tokens::integer(n) }
+                rule i64() -> i64 = n:$d(signed_digits()) {? $crate::tokens::integer(n) }
+                rule i128() -> i128 = n:$d(signed_digits()) {? $crate::tokens::integer(n) }
+                rule isize() -> isize = n:$d(signed_digits()) {? $crate::tokens::integer(n) }
+
+                rule identifier() -> &'input str
+                    = $d(['a'..='z' | 'A'..='Z' | '_'] ['a'..='z' | 'A'..='Z' | '0'..='9' | '_']*)
+                rule comma_list<T>(item: rule<T>) -> Vec<T> = separated(<item()>, <"," " "*>)
+                rule space_list<T>(item: rule<T>) -> Vec<T> = separated(<item()>, <" "+>)
+                // `**` and `++` written in this macro body reach peg as two separate `*` or `+`,
+                // unlike in the rules passed in, hence this rule
+                rule separated<T, S>(item: rule<T>, separator: rule<S>) -> Vec<T>
+                    = first:item() rest:(separator() i:item() { i })* {
+                        let mut items = vec![first];
+                        items.extend(rest);
+                        items
+                    }
+                rule key_value<K, V>(key: rule<K>, value: rule<V>) -> (K, V)
+                    = k:key() ":" " "* v:value() { (k, v) }
+
+                $($rules)*
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    crate::parser! {
+        grammar test_parser() for str {
+            pub rule byte() -> u8 = u8()
+            pub rule offset() -> i32 = i32()
+            pub rule numbers() -> Vec<u64> = comma_list(<u64()>)
+            pub rule bytes() -> Vec<u8> = u8() ** ";"
+            pub rule words() -> Vec<&'input str> = space_list(<identifier()>)
+            pub rule entry() -> (&'input str, i64) = key_value(<identifier()>, <i64()>)
+        }
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(integer::<u8>("255"), Ok(255));
+        assert_eq!(integer::<u8>("256"), Err("a smaller integer"));
+        assert_eq!(integer::<i8>("-129"), Err("a larger integer"));
+        assert_eq!(integer::<i8>("+12"), Ok(12));
+        assert_eq!(integer::<u32>("1a"), Err("an integer"));
+    }
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(test_parser::byte("200"), Ok(200));
+        assert!(test_parser::byte("300").is_err());
+        assert!(test_parser::byte("-1").is_err());
+        assert_eq!(test_parser::offset("+7"), Ok(7));
+        assert_eq!(test_parser::offset("-2147483648"), Ok(i32::MIN));
+        assert!(test_parser::offset("2147483648").is_err());
+        assert_eq!(test_parser::numbers("1,2, 3"), Ok(vec![1, 2, 3]));
+        assert!(test_parser::numbers("").is_err());
+        assert!(test_parser::numbers("1,").is_err());
+        assert_eq!(test_parser::words("a"), Ok(vec!["a"]));
+        assert!(test_parser::words("").is_err());
+        assert_eq!(test_parser::bytes("1;2"), Ok(vec![1, 2]));
+        assert_eq!(
+            test_parser::words("mxmxvkd  kfcds _x1"),
+            Ok(vec!["mxmxvkd", "kfcds", "_x1"])
+        );
+        assert!(test_parser::words("1abc").is_err());
+        assert_eq!(test_parser::entry("acc: -3"), Ok(("acc", -3)));
+
+        let error = test_parser::byte("256").unwrap_err();
+        assert_eq!(error.location.offset, 3);
+        assert!(error.expected.tokens().any(|t| t == "a smaller integer"));
+    }
+}